@@ -1,35 +1,251 @@
+use std::future::Future;
+use std::io::ErrorKind as IOErrorKind;
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
 use futures::lock::Mutex;
+use log::info;
 use serde::Serialize;
 
-use libapi_http::api::ValueResponse;
-#[cfg(not(feature = "mock_upstream"))]
-use libapi_net::client::Client;
+use libapi_http::api::{ErrorCode, ErrorResponse, ValueResponse};
 #[cfg(feature = "mock_upstream")]
 use libapi_net::client::mock::Client;
+#[cfg(not(feature = "mock_upstream"))]
+use libapi_net::client::Client;
+use libvideo::Camera;
+
+use crate::recorder::Recorder;
+use crate::CLIENT_NAME;
+
+// longest wait for api-net to accept connection, requests meanwhile wait as well
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
 
 pub struct State {
-    pub rover_client: Mutex<Client>,
+    pub rover: Upstream,
+
+    /// Absent if there is no camera to show.
+    pub camera: Option<Camera>,
+    pub recorder: Recorder,
+}
+
+/// Connection to api-net, made on first use and made again once lost, so that api-http outlives
+/// restarts of the rover.
+pub struct Upstream {
+    address: String,
+    client: Mutex<Option<Client>>,
+}
+
+impl Upstream {
+    pub fn new(address: String) -> Upstream {
+        Upstream {
+            address,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Client of open connection, connecting first if there is none.
+    pub async fn client(&self) -> Result<Client, libapi_net::Error> {
+        let mut client = self.client.lock().await;
+
+        if let Some(ref client) = *client {
+            if client.is_connected() {
+                return Ok(client.clone());
+            }
+        }
+
+        info!("Connecting to api-net at {}.", self.address);
+
+        let connect = Client::with_name(self.address.as_str(), CLIENT_NAME);
+        let connected = match actix_rt::time::timeout(CONNECT_TIMEOUT, connect).await {
+            Ok(connected) => connected?,
+            Err(_) => return Err(libapi_net::Error::IO(IOErrorKind::TimedOut.into())),
+        };
+
+        *client = Some(connected.clone());
+
+        Ok(connected)
+    }
+
+    /// Makes the call with client of open connection.
+    pub async fn call<T, F, R>(&self, call: F) -> Result<T, libapi_net::Error>
+    where
+        F: FnOnce(Client) -> R,
+        R: Future<Output = Result<T, libapi_net::Error>>,
+    {
+        call(self.client().await?).await
+    }
+}
+
+/// Failure that can be explained to API clients.
+pub trait ToErrorResponse {
+    fn to_error_response(&self) -> ErrorResponse;
+}
+
+impl ToErrorResponse for libapi_net::Error {
+    fn to_error_response(&self) -> ErrorResponse {
+        use libapi_net::contract::data::ErrorKind;
+        use libapi_net::Error;
+
+        let code = match self {
+            Error::Disconnected | Error::IO(_) => ErrorCode::Unavailable,
+            Error::Server(kind, _) => match kind {
+                ErrorKind::Invalid => ErrorCode::InvalidRequest,
+                ErrorKind::Conflict => ErrorCode::Conflict,
+                ErrorKind::Unsupported => ErrorCode::Unsupported,
+                ErrorKind::Failed => ErrorCode::RoverFailure,
+            },
+            Error::Incompatible(_) | Error::Protocol(_) | Error::Serialization(_) => {
+                ErrorCode::RoverFailure
+            }
+            Error::Client(_) => ErrorCode::Internal,
+        };
+
+        // server explains its refusals well enough on its own
+        let message = match self {
+            Error::Server(_, message) => message.clone(),
+            e => e.to_string(),
+        };
+
+        ErrorResponse::new(code, message)
+    }
+}
+
+pub fn error_response(error: ErrorResponse) -> HttpResponse {
+    let status =
+        StatusCode::from_u16(error.code.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+    HttpResponse::build(status).json(error)
 }
 
-pub fn map_rover_status_to_response<T, E: std::error::Error>(r: Result<T, E>) -> HttpResponse {
+pub fn map_rover_status_to_response<T, E: ToErrorResponse>(r: Result<T, E>) -> HttpResponse {
     match r {
         Ok(_) => HttpResponse::NoContent().finish(),
-        Err(e) => HttpResponse::InternalServerError()
-            .content_type("text/plain")
-            .body(e.to_string()),
+        Err(e) => error_response(e.to_error_response()),
     }
 }
 
-pub fn map_rover_result_to_response<T: Serialize, E: std::error::Error>(
+pub fn map_rover_result_to_response<T: Serialize, E: ToErrorResponse>(
     r: Result<T, E>,
 ) -> HttpResponse {
     match r {
         Ok(v) => HttpResponse::Ok()
             .content_type("application/json")
             .json(ValueResponse { value: v }),
-        Err(e) => HttpResponse::InternalServerError()
-            .content_type("text/plain")
-            .body(e.to_string()),
+        Err(e) => error_response(e.to_error_response()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::to_bytes;
+    use libapi_net::contract::data::{ErrorKind, ProtocolMessage};
+    use libapi_net::Error;
+
+    use super::*;
+
+    fn status_of(e: Error) -> u16 {
+        e.to_error_response().code.status()
+    }
+
+    #[test]
+    fn unreachable_rover_is_unavailable() {
+        assert_eq!(status_of(Error::Disconnected), 503);
+        assert_eq!(
+            status_of(Error::IO(IOErrorKind::ConnectionRefused.into())),
+            503
+        );
+    }
+
+    #[test]
+    fn server_refusals_keep_their_kind() {
+        let refused = |kind| Error::Server(kind, "Refused.".to_owned());
+
+        assert_eq!(status_of(refused(ErrorKind::Invalid)), 400);
+        assert_eq!(status_of(refused(ErrorKind::Conflict)), 409);
+        assert_eq!(status_of(refused(ErrorKind::Unsupported)), 501);
+        assert_eq!(status_of(refused(ErrorKind::Failed)), 502);
+
+        let response = refused(ErrorKind::Conflict).to_error_response();
+        assert_eq!(
+            response,
+            ErrorResponse::new(ErrorCode::Conflict, "Refused.")
+        );
+    }
+
+    #[test]
+    fn broken_conversation_is_rover_failure() {
+        assert_eq!(status_of(Error::Incompatible("Too old.".to_owned())), 502);
+        assert_eq!(
+            status_of(Error::Protocol(ProtocolMessage::ControlRequest)),
+            502
+        );
+        assert_eq!(status_of(Error::Client("Bug.".to_owned())), 500);
+    }
+
+    #[actix_rt::test]
+    async fn failure_is_answered_with_error_body() {
+        let response = map_rover_status_to_response::<(), _>(Err(Error::Disconnected));
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, ErrorCode::Unavailable);
+        assert_eq!(error.message, Error::Disconnected.to_string());
+    }
+
+    #[actix_rt::test]
+    async fn success_is_answered_with_value() {
+        let response = map_rover_result_to_response::<_, Error>(Ok(42));
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], br#"{"value":42}"#);
+
+        let response = map_rover_status_to_response::<_, Error>(Ok(42));
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    // mock client connects to nothing, so there is nothing to reconnect to either
+    #[cfg(not(feature = "mock_upstream"))]
+    #[actix_rt::test]
+    async fn rover_is_connected_to_once_it_is_up() {
+        use libapi_net::server::Server;
+        use libdriver::api::AsyncMover;
+        use libdriver::util::a_sync::AsyncRover;
+        use libdriver_sim::{SimRover, World};
+
+        type SimServer = Server<AsyncRover<SimRover>, AsyncRover<SimRover>, AsyncRover<SimRover>>;
+
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let upstream = Upstream::new(address.clone());
+
+        let down = upstream
+            .call(|client| async move { client.get_move_type().await })
+            .await;
+        assert_eq!(status_of(down.unwrap_err()), 503);
+
+        let rover = AsyncRover::from(SimRover::new(World::default()));
+        let mut server: SimServer = Server::new(&address).await.unwrap();
+        server.register_mover(Some(rover.clone()));
+        server.register_looker(Some(rover.clone()));
+        server.register_sensor(Some(rover));
+        actix_rt::spawn(server.serve());
+
+        upstream
+            .call(|mut client| async move { client.move_forward(100).await })
+            .await
+            .unwrap();
+
+        // later calls share the connection, another one would be refused control
+        upstream
+            .call(|mut client| async move { client.move_backward(100).await })
+            .await
+            .unwrap();
     }
 }
@@ -0,0 +1,111 @@
+use actix_web::{get, post, web, Responder};
+use log::{debug, trace};
+use utoipa::OpenApi;
+
+use libapi_http::api::{BehaviorRequest, BehaviorType, ValueResponse};
+use libbehavior::{BehaviorConfig, BehaviorHost, LineFollowConfig, WanderConfig};
+
+use crate::app;
+use crate::app::{map_rover_result_to_response, map_rover_status_to_response};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(start_behavior)
+        .service(stop_behavior)
+        .service(get_behavior);
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(start_behavior, stop_behavior, get_behavior))]
+pub struct Api;
+
+fn to_config(req: &BehaviorRequest) -> BehaviorConfig {
+    match req.r#type {
+        BehaviorType::Wander => {
+            let defaults = WanderConfig::default();
+
+            BehaviorConfig::Wander(WanderConfig {
+                speed: req.speed.unwrap_or(defaults.speed),
+                ..defaults
+            })
+        }
+        BehaviorType::LineFollow => {
+            let defaults = LineFollowConfig::default();
+
+            BehaviorConfig::Line(LineFollowConfig {
+                speed: req.speed.unwrap_or(defaults.speed),
+                ..defaults
+            })
+        }
+    }
+}
+
+fn to_request(config: BehaviorConfig) -> BehaviorRequest {
+    match config {
+        BehaviorConfig::Wander(config) => BehaviorRequest {
+            r#type: BehaviorType::Wander,
+            speed: Some(config.speed),
+        },
+        BehaviorConfig::Line(config) => BehaviorRequest {
+            r#type: BehaviorType::LineFollow,
+            speed: Some(config.speed),
+        },
+    }
+}
+
+/// Starts autonomous behavior.
+#[utoipa::path(
+    request_body = BehaviorRequest,
+    responses((status = 204, description = "Behavior started."))
+)]
+#[post("")]
+pub async fn start_behavior(
+    req: web::Json<BehaviorRequest>,
+    state: web::Data<app::State>,
+) -> impl Responder {
+    debug!(
+        "Requested to start {} behavior with speed {:?}",
+        req.r#type, req.speed
+    );
+
+    let config = to_config(&req);
+    let started = state
+        .rover
+        .call(|mut client| async move { client.start_behavior(config).await });
+    let r = map_rover_status_to_response(started.await);
+
+    trace!("Returning {:#?}", r);
+
+    r
+}
+
+/// Stops autonomous behavior, returning to manual control.
+#[utoipa::path(responses((status = 204, description = "Behavior stopped.")))]
+#[post("/stop")]
+pub async fn stop_behavior(state: web::Data<app::State>) -> impl Responder {
+    debug!("Requested to stop behavior");
+
+    let stopped = state.rover.call(|mut client| async move { client.stop_behavior().await });
+    let r = map_rover_status_to_response(stopped.await);
+
+    trace!("Returning {:#?}", r);
+
+    r
+}
+
+/// Tells running behavior.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Running behavior, null if none.",
+    body = ValueResponse<Option<BehaviorRequest>>
+)))]
+#[get("")]
+pub async fn get_behavior(state: web::Data<app::State>) -> impl Responder {
+    debug!("Requested to provide running behavior.");
+
+    let behavior = state.rover.call(|client| async move { client.get_behavior().await }).await;
+    let r = map_rover_result_to_response(behavior.map(|behavior| behavior.map(to_request)));
+
+    trace!("Returning {:#?}", r);
+
+    r
+}
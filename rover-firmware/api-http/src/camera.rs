@@ -0,0 +1,74 @@
+use config::Config;
+use log::info;
+use serde::Deserialize;
+
+use libutil::app::get_optional;
+use libutil::sys::normalize_path;
+use libvideo::{
+    Camera, CommandSource, CommandSourceConfig, FileSource, FileSourceConfig, TestPattern,
+    TestPatternConfig,
+};
+
+use crate::recorder::Recorder;
+
+const DEFAULT_RECORDINGS_DIR: &str = "recordings";
+
+#[derive(Debug, Deserialize, PartialEq, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum CameraType {
+    Test,
+    Command,
+    File,
+}
+
+/// Starts camera selected in configuration, if any.
+pub fn from_settings(settings: &Config) -> Result<Option<Camera>, Box<dyn std::error::Error>> {
+    let camera_type = match get_optional::<CameraType>(settings, "camera.type")? {
+        Some(camera_type) => camera_type,
+        None => {
+            info!("No camera configured.");
+            return Ok(None);
+        }
+    };
+
+    info!("Using {:?} camera.", camera_type);
+
+    let camera = match camera_type {
+        CameraType::Test => {
+            let pattern_config =
+                get_optional::<TestPatternConfig>(settings, "camera.test")?.unwrap_or_default();
+
+            Camera::start(TestPattern::new(pattern_config))
+        }
+        CameraType::Command => {
+            let command_config = get_optional::<CommandSourceConfig>(settings, "camera.command")?
+                .unwrap_or_default();
+
+            info!("Reading frames from `{}`.", command_config.command);
+
+            Camera::start(CommandSource::new(command_config))
+        }
+        CameraType::File => {
+            let mut file_config =
+                get_optional::<FileSourceConfig>(settings, "camera.file")?.unwrap_or_default();
+            file_config.path = normalize_path(&file_config.path, &std::env::current_dir()?);
+
+            info!("Playing frames from {}.", file_config.path);
+
+            Camera::start(FileSource::from_config(file_config)?)
+        }
+    };
+
+    Ok(Some(camera))
+}
+
+/// Recorder storing recordings in directory from configuration.
+pub fn recorder_from_settings(settings: &Config) -> Result<Recorder, Box<dyn std::error::Error>> {
+    let dir = get_optional::<String>(settings, "camera.recordings")?
+        .unwrap_or_else(|| DEFAULT_RECORDINGS_DIR.to_owned());
+    let dir = normalize_path(&dir, &std::env::current_dir()?);
+
+    info!("Storing camera recordings in {}.", dir);
+
+    Ok(Recorder::new(dir))
+}
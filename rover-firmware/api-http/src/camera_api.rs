@@ -0,0 +1,364 @@
+use std::fs::File;
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix::{Actor, ActorContext, StreamHandler};
+use actix_web::body::SizedStream;
+use actix_web::web::Bytes;
+use actix_web::{get, post, web, Error, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use actix_web_actors::ws::{Message, ProtocolError};
+use futures::stream::{unfold, Stream};
+use futures::StreamExt;
+use log::{debug, trace, warn};
+use utoipa::OpenApi;
+
+use libapi_http::api::{
+    CameraFrame, CaptureTags, ErrorCode, ErrorResponse, LookRequest, Recording, ValueResponse,
+};
+use libdriver::api::{AsyncLooker, AsyncMover};
+use libvideo::{Camera, Frame};
+
+use crate::app;
+use crate::app::{error_response, map_rover_result_to_response, ToErrorResponse, Upstream};
+use crate::recorder;
+use crate::state_api::to_movement;
+
+const BOUNDARY: &str = "frame";
+
+// piece of recording read from disk at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(mjpeg_stream)
+        .service(video_ws)
+        .service(snapshot)
+        .service(start_recording)
+        .service(stop_recording)
+        .service(list_recordings)
+        .service(get_recording);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        mjpeg_stream,
+        video_ws,
+        snapshot,
+        start_recording,
+        stop_recording,
+        list_recordings,
+        get_recording
+    ),
+    components(schemas(CaptureTags))
+)]
+pub struct Api;
+
+fn no_camera() -> HttpResponse {
+    error_response(ErrorResponse::new(
+        ErrorCode::Unavailable,
+        "No camera configured.",
+    ))
+}
+
+/// Tags capture with the rover state, leaving out what the rover fails to tell.
+async fn capture_tags(rover: &Upstream, timestamp_ms: u64) -> CaptureTags {
+    let client = match rover.client().await {
+        Ok(client) => client,
+        Err(e) => {
+            debug!("Capture is not tagged with rover state: {}", e);
+
+            return CaptureTags {
+                timestamp_ms,
+                movement: None,
+                look: None,
+            };
+        }
+    };
+
+    let (move_type, look_direction) =
+        futures::join!(client.get_move_type(), client.get_look_direction());
+
+    let movement = move_type
+        .map_err(|e| debug!("Capture is not tagged with move type: {}", e))
+        .ok()
+        .and_then(to_movement);
+    let look = look_direction
+        .map_err(|e| debug!("Capture is not tagged with look direction: {}", e))
+        .ok()
+        .map(|(h, v)| LookRequest { h, v });
+
+    CaptureTags {
+        timestamp_ms,
+        movement,
+        look,
+    }
+}
+
+/// Streams camera video.
+///
+/// Video as MJPEG stream, viewable directly in `<img>` element.
+#[utoipa::path(responses((
+    status = 200,
+    description = "MJPEG stream, each part with X-Timestamp header of capture time.",
+    content_type = "multipart/x-mixed-replace"
+)))]
+#[get("/stream")]
+pub async fn mjpeg_stream(state: web::Data<app::State>) -> HttpResponse {
+    let camera = match state.camera {
+        Some(ref camera) => camera,
+        None => return no_camera(),
+    };
+
+    let parts = camera.stream().map(|frame| {
+        let header = format!(
+            "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\nX-Timestamp: {}\r\n\r\n",
+            BOUNDARY,
+            frame.data.len(),
+            frame.timestamp_ms
+        );
+
+        let mut part = Vec::with_capacity(header.len() + frame.data.len() + 2);
+        part.extend_from_slice(header.as_bytes());
+        part.extend_from_slice(&frame.data);
+        part.extend_from_slice(b"\r\n");
+
+        Ok::<_, Error>(Bytes::from(part))
+    });
+
+    HttpResponse::Ok()
+        .content_type(format!("multipart/x-mixed-replace; boundary={}", BOUNDARY))
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(parts)
+}
+
+/// Video viewer pushed with binary frames as they are captured.
+struct VideoSocket {
+    camera: Camera,
+}
+
+impl Actor for VideoSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        <Self as StreamHandler<Frame>>::add_stream(self.camera.stream(), ctx);
+    }
+}
+
+impl StreamHandler<Frame> for VideoSocket {
+    fn handle(&mut self, frame: Frame, ctx: &mut Self::Context) {
+        let message = CameraFrame {
+            timestamp_ms: frame.timestamp_ms,
+            jpeg: &frame.data,
+        };
+
+        ctx.binary(message.to_bytes());
+    }
+
+    // camera going away is not a reason to drop the viewer
+    fn finished(&mut self, _: &mut Self::Context) {}
+}
+
+impl StreamHandler<Result<Message, ProtocolError>> for VideoSocket {
+    fn handle(&mut self, item: Result<Message, ProtocolError>, ctx: &mut Self::Context) {
+        match item {
+            Ok(Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Err(e) => {
+                warn!("Video web-socket failed: {}", e);
+                ctx.stop();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Opens camera video web-socket.
+#[utoipa::path(responses((
+    status = 101,
+    description = "Server pushes binary messages of capture time as big-endian u64 ms since UNIX \
+                   epoch followed by JPEG image."
+)))]
+#[get("/ws")]
+pub async fn video_ws(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<app::State>,
+) -> Result<HttpResponse, Error> {
+    let camera = match state.camera {
+        Some(ref camera) => camera.clone(),
+        None => return Ok(no_camera()),
+    };
+
+    let response = ws::start(VideoSocket { camera }, &req, stream);
+
+    trace!("Returning {:?}", response);
+
+    response
+}
+
+/// Takes camera snapshot.
+///
+/// Latest frame as JPEG image, with its capture tags in `X-Capture-Tags` header as JSON.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Latest frame.",
+    content_type = "image/jpeg",
+    headers(("X-Capture-Tags" = String, description = "CaptureTags of the frame as JSON."))
+)))]
+#[post("/snapshot")]
+pub async fn snapshot(state: web::Data<app::State>) -> HttpResponse {
+    debug!("Requested camera snapshot.");
+
+    let frame = match state.camera {
+        Some(ref camera) => camera.latest(),
+        None => return no_camera(),
+    };
+    let frame = match frame {
+        Some(frame) => frame,
+        None => {
+            return error_response(ErrorResponse::new(
+                ErrorCode::Unavailable,
+                "No frame captured yet.",
+            ))
+        }
+    };
+
+    let tags = capture_tags(&state.rover, frame.timestamp_ms).await;
+    let tags = match serde_json::to_string(&tags) {
+        Ok(tags) => tags,
+        Err(e) => return error_response(ErrorResponse::new(ErrorCode::Internal, e.to_string())),
+    };
+
+    HttpResponse::Ok()
+        .content_type("image/jpeg")
+        .insert_header(("X-Capture-Tags", tags))
+        .body(frame.data)
+}
+
+/// Starts recording video.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Started recording.",
+    body = ValueResponse<Recording>
+)))]
+#[post("/record/start")]
+pub async fn start_recording(state: web::Data<app::State>) -> HttpResponse {
+    debug!("Requested to start camera recording.");
+
+    let camera = match state.camera {
+        Some(ref camera) => camera,
+        None => return no_camera(),
+    };
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let tags = capture_tags(&state.rover, now_ms).await;
+
+    let r = map_rover_result_to_response(state.recorder.start(camera, tags));
+
+    trace!("Returning {:#?}", r);
+
+    r
+}
+
+/// Stops recording video.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Completed recording.",
+    body = ValueResponse<Recording>
+)))]
+#[post("/record/stop")]
+pub async fn stop_recording(state: web::Data<app::State>) -> HttpResponse {
+    debug!("Requested to stop camera recording.");
+
+    let r = map_rover_result_to_response(state.recorder.stop().await);
+
+    trace!("Returning {:#?}", r);
+
+    r
+}
+
+/// Lists video recordings.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Recordings, oldest first.",
+    body = ValueResponse<Vec<Recording>>
+)))]
+#[get("/recordings")]
+pub async fn list_recordings(state: web::Data<app::State>) -> HttpResponse {
+    debug!("Requested to list camera recordings.");
+
+    map_rover_result_to_response(state.recorder.list())
+}
+
+/// Downloads completed video recording.
+///
+/// Video of completed recording as MJPEG file.
+#[utoipa::path(
+    params(("name" = String, Path, description = "Name of the recording.")),
+    responses((
+        status = 200,
+        description = "Recording as MJPEG file.",
+        content_type = "video/x-motion-jpeg"
+    ))
+)]
+#[get("/recordings/{name}")]
+pub async fn get_recording(name: web::Path<String>, state: web::Data<app::State>) -> HttpResponse {
+    debug!("Requested camera recording {}.", name);
+
+    let path = match state.recorder.video_of(&name) {
+        Ok(Some(path)) => path,
+        Ok(None) => {
+            return error_response(ErrorResponse::new(
+                ErrorCode::NotFound,
+                format!("No completed recording {}.", name),
+            ))
+        }
+        Err(e) => return error_response(e.to_error_response()),
+    };
+
+    let open = move || -> std::io::Result<(File, u64)> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        Ok((file, size))
+    };
+
+    // recordings are too big to hold in memory, they are sent as read
+    match web::block(open).await {
+        Ok(Ok((file, size))) => HttpResponse::Ok()
+            .content_type("video/x-motion-jpeg")
+            .body(SizedStream::new(size, file_chunks(file))),
+        Ok(Err(e)) => error_response(recorder::Error::from(e).to_error_response()),
+        Err(e) => error_response(ErrorResponse::new(ErrorCode::Internal, e.to_string())),
+    }
+}
+
+/// Contents of the file, read chunk by chunk on the blocking thread pool.
+fn file_chunks(file: File) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+    unfold(Some(file), |file| async move {
+        let mut file = file?;
+
+        let read = web::block(move || {
+            let mut chunk = vec![0; CHUNK_SIZE];
+            let size = file.read(&mut chunk)?;
+            chunk.truncate(size);
+
+            Ok::<_, std::io::Error>((file, chunk))
+        })
+        .await;
+
+        match read {
+            Ok(Ok((_, chunk))) if chunk.is_empty() => None,
+            Ok(Ok((file, chunk))) => Some((Ok(Bytes::from(chunk)), Some(file))),
+            Ok(Err(e)) => Some((Err(e), None)),
+            Err(e) => Some((Err(std::io::Error::other(e)), None)),
+        }
+    })
+}
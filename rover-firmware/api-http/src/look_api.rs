@@ -1,5 +1,6 @@
 use actix_web::{post, web, Responder};
 use log::{debug, trace};
+use utoipa::OpenApi;
 
 use crate::app;
 use crate::app::map_rover_status_to_response;
@@ -10,12 +11,19 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(look_at);
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(look_at))]
+pub struct Api;
+
+/// Turns sensors towards given direction.
+#[utoipa::path(request_body = LookRequest, responses((status = 204, description = "Looking.")))]
 #[post("")]
 pub async fn look_at(req: web::Json<LookRequest>, state: web::Data<app::State>) -> impl Responder {
     debug!("Requested to look at ({}, {})", req.h, req.v);
 
-    let r =
-        map_rover_status_to_response(state.rover_client.lock().await.look_at(req.h, req.v).await);
+    let (h, v) = (req.h, req.v);
+    let looked = state.rover.call(|mut client| async move { client.look_at(h, v).await });
+    let r = map_rover_status_to_response(looked.await);
 
     trace!("Returning {:#?}", r);
 
@@ -1,22 +1,46 @@
-use futures::lock::Mutex;
-
-use actix_rt;
-use actix_web::{web, App, HttpServer};
+use actix_web::error::InternalError;
+use actix_web::{web, App, HttpResponse, HttpServer};
 use log::info;
 
-#[cfg(not(feature = "mock_upstream"))]
-use libapi_net::client::Client;
-#[cfg(feature = "mock_upstream")]
-use libapi_net::client::mock::Client;
+use libapi_http::api::{ErrorCode, ErrorResponse};
+
 use libutil::app::bootstrap;
 
+use crate::app::{error_response, Upstream};
+
 mod app;
+mod behavior_api;
+mod camera;
+mod camera_api;
 mod look_api;
 mod move_api;
+mod openapi_api;
+mod recorder;
 mod sense_api;
+mod state_api;
 mod ws_api;
 
 const CONFIG_FILE: &str = "Config.toml";
+const CLIENT_NAME: &str = concat!("api-http/", env!("CARGO_PKG_VERSION"));
+
+async fn not_found() -> HttpResponse {
+    error_response(ErrorResponse::new(
+        ErrorCode::NotFound,
+        "No such operation.",
+    ))
+}
+
+/// Operations of api-http, each group under its own scope. OpenAPI document nests them the same.
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/move").configure(move_api::config))
+        .service(web::scope("/look").configure(look_api::config))
+        .service(web::scope("/behavior").configure(behavior_api::config))
+        .service(web::scope("/sense").configure(sense_api::config))
+        .service(web::scope("/state").configure(state_api::config))
+        .service(web::scope("/ws").configure(ws_api::config))
+        .service(web::scope("/camera").configure(camera_api::config))
+        .service(web::scope("/openapi.json").configure(openapi_api::config));
+}
 
 #[actix_rt::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -30,17 +54,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let rover_addr = settings.get_string("rover_address")?;
 
+    let camera = camera::from_settings(&settings)?;
+    let recorder = camera::recorder_from_settings(&settings)?;
+
     let state = web::Data::new(app::State {
-        rover_client: Mutex::new(Client::new(rover_addr).await?),
+        rover: Upstream::new(rover_addr),
+        camera,
+        recorder,
     });
 
     let app_factory = move || {
         App::new()
             .app_data(state.clone())
-            .service(web::scope("/move").configure(move_api::config))
-            .service(web::scope("/look").configure(look_api::config))
-            .service(web::scope("/sense").configure(sense_api::config))
-            .service(web::scope("/ws").configure(ws_api::config))
+            .app_data(web::JsonConfig::default().error_handler(|e, _| {
+                let response =
+                    error_response(ErrorResponse::new(ErrorCode::InvalidRequest, e.to_string()));
+                InternalError::from_response(e, response).into()
+            }))
+            .configure(routes)
+            .default_service(web::to(not_found))
     };
 
     HttpServer::new(app_factory)
@@ -1,37 +1,69 @@
 use actix_web::{post, web, Responder};
 use log::{debug, trace};
+use utoipa::OpenApi;
 
-use libapi_http::api::{MoveRequest, MoveType};
+use libapi_http::api::{ErrorCode, ErrorResponse, MoveRequest, MoveType};
 use libdriver::api::AsyncMover;
 
 use crate::app;
-use crate::app::map_rover_status_to_response;
+use crate::app::{error_response, map_rover_status_to_response};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(move_control);
+    cfg.service(move_control).service(heartbeat);
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(move_control, heartbeat))]
+pub struct Api;
+
+/// Moves the rover.
+#[utoipa::path(request_body = MoveRequest, responses((status = 204, description = "Moving.")))]
 #[post("")]
 pub async fn move_control(
     req: web::Json<MoveRequest>,
     state: web::Data<app::State>,
 ) -> impl Responder {
     debug!(
-        "Requested to move {:#?} with speed of {}",
-        req.r#type, req.speed
+        "Requested to move {:#?} with speed of {} ({:?}, {:?})",
+        req.r#type, req.speed, req.left, req.right
     );
 
-    let mut client = state.rover_client.lock().await;
-    let result = match req.r#type {
-        MoveType::Forward => client.move_forward(req.speed),
-        MoveType::Backward => client.move_backward(req.speed),
-        MoveType::CWSpin => client.spin_right(req.speed),
-        MoveType::CCWSpin => client.spin_left(req.speed),
+    let (speed, left, right) = match (req.r#type, req.left, req.right) {
+        (MoveType::Drive, Some(left), Some(right)) => (0, left, right),
+        (MoveType::Drive, _, _) => {
+            return error_response(ErrorResponse::new(
+                ErrorCode::InvalidRequest,
+                "Drive requires both left and right speeds.",
+            ))
+        }
+        _ => (req.speed, 0, 0),
     };
 
+    let move_type = req.r#type;
+    let result = state.rover.call(|mut client| async move {
+        match move_type {
+            MoveType::Forward => client.move_forward(speed).await,
+            MoveType::Backward => client.move_backward(speed).await,
+            MoveType::CWSpin => client.spin_right(speed).await,
+            MoveType::CCWSpin => client.spin_left(speed).await,
+            MoveType::Drive => client.drive(left, right).await,
+        }
+    });
+
     let r = map_rover_status_to_response(result.await);
 
     trace!("Returning {:#?}", r);
 
     r
 }
+
+/// Keeps the rover moving past its watchdog timeout.
+#[utoipa::path(responses((status = 204, description = "Still moving.")))]
+#[post("/heartbeat")]
+pub async fn heartbeat(state: web::Data<app::State>) -> impl Responder {
+    trace!("Requested to keep moving");
+
+    map_rover_status_to_response(
+        state.rover.call(|mut client| async move { client.heartbeat().await }).await,
+    )
+}
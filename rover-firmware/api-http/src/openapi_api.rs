@@ -0,0 +1,200 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use log::debug;
+use utoipa::openapi::path::PathItem;
+use utoipa::openapi::{Content, OpenApi as Document, Ref, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use libapi_http::api::ErrorResponse;
+
+use crate::{behavior_api, camera_api, look_api, move_api, sense_api, state_api, ws_api};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_document);
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_document))]
+pub struct Api;
+
+/// All operations of api-http, nested the way they are scoped by the server.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Rover api-http"),
+    nest(
+        (path = "/move", api = move_api::Api),
+        (path = "/look", api = look_api::Api),
+        (path = "/behavior", api = behavior_api::Api),
+        (path = "/sense", api = sense_api::Api),
+        (path = "/state", api = state_api::Api),
+        (path = "/ws", api = ws_api::Api),
+        (path = "/camera", api = camera_api::Api),
+        (path = "/openapi.json", api = Api)
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&FailedRequests)
+)]
+struct ApiDoc;
+
+/// Answers every operation with [ErrorResponse] on failure.
+struct FailedRequests;
+
+impl Modify for FailedRequests {
+    fn modify(&self, openapi: &mut Document) {
+        let response = ResponseBuilder::new()
+            .description("Failed request.")
+            .content(
+                "application/json",
+                Content::new(Some(Ref::from_schema_name("ErrorResponse"))),
+            )
+            .build();
+
+        for item in openapi.paths.paths.values_mut() {
+            let PathItem { get, put, post, delete, options, head, patch, trace, .. } = item;
+
+            for operation in [get, put, post, delete, options, head, patch, trace]
+                .into_iter()
+                .flatten()
+            {
+                operation
+                    .responses
+                    .responses
+                    .insert("default".to_owned(), response.clone().into());
+            }
+        }
+    }
+}
+
+/// Describes all operations of api-http.
+pub fn document() -> Document {
+    ApiDoc::openapi()
+}
+
+/// Describes the API.
+#[utoipa::path(responses((
+    status = 200,
+    description = "This document.",
+    content_type = "application/json"
+)))]
+#[get("")]
+pub async fn get_document() -> impl Responder {
+    debug!("Requested OpenAPI document.");
+
+    HttpResponse::Ok().json(document())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use actix_web::test::{call_and_read_body_json, init_service, TestRequest};
+    use actix_web::{web, App, HttpRequest, HttpResponse};
+
+    use super::*;
+
+    /// Paths of documented operations by their ids.
+    fn documented() -> BTreeMap<String, String> {
+        let mut operations = BTreeMap::new();
+
+        for (path, item) in document().paths.paths {
+            let PathItem {
+                get,
+                put,
+                post,
+                delete,
+                options,
+                head,
+                patch,
+                trace,
+                ..
+            } = item;
+
+            for operation in [get, put, post, delete, options, head, patch, trace]
+                .into_iter()
+                .flatten()
+            {
+                let id = operation.operation_id.expect("Operation id expected");
+                assert!(
+                    operations.insert(id, path.clone()).is_none(),
+                    "Duplicate operation id"
+                );
+            }
+        }
+
+        operations
+    }
+
+    /// Paths of routes registered by the server, by their names.
+    async fn routed() -> BTreeMap<String, String> {
+        let app = init_service(App::new().configure(crate::routes).default_service(web::to(
+            |req: HttpRequest| async move {
+                // actix does not list its routes, but route macros name them after handlers
+                let map = format!("{:?}", req.resource_map());
+                let names = map
+                    .split("name: Some(\"")
+                    .skip(1)
+                    .filter_map(|rest| rest.split_once('"'))
+                    .map(|(name, _)| name.to_owned());
+
+                let mut routes = BTreeMap::new();
+                for name in names {
+                    let placeholders = ["{name}"];
+                    let elements = if name == "get_recording" {
+                        &placeholders[..]
+                    } else {
+                        &[]
+                    };
+
+                    let url = req
+                        .url_for(&name, elements)
+                        .expect("Route of the name expected");
+                    let path = url.path().replace("%7B", "{").replace("%7D", "}");
+                    routes.insert(name, path);
+                }
+
+                HttpResponse::Ok().json(routes)
+            },
+        )))
+        .await;
+
+        let request = TestRequest::get().uri("/no/such/route").to_request();
+        call_and_read_body_json(&app, request).await
+    }
+
+    #[actix_rt::test]
+    async fn documents_every_route() {
+        let documented = documented();
+        let routed = routed().await;
+
+        assert!(routed.len() >= 20, "Routes expected, found {:?}", routed);
+        for (name, path) in &routed {
+            assert_eq!(
+                documented.get(name),
+                Some(path),
+                "Route {} is not documented",
+                name
+            );
+        }
+        assert_eq!(
+            documented.len(),
+            routed.len(),
+            "Operations without routes: {:?}",
+            documented
+        );
+    }
+
+    #[test]
+    fn documents_failures() {
+        let document = document();
+        let error = document.components.unwrap().schemas;
+
+        assert!(error.contains_key("ErrorResponse"));
+        assert!(error.contains_key("ErrorCode"));
+
+        for item in document.paths.paths.values() {
+            for operation in [&item.get, &item.post].into_iter().flatten() {
+                assert!(operation.responses.responses.contains_key("default"));
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::io::Error as IOError;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use futures::channel::oneshot;
+use futures::StreamExt;
+use log::{info, warn};
+use thiserror::Error as LibError;
+
+use libapi_http::api::{CaptureTags, ErrorCode, ErrorResponse, Recording};
+use libvideo::{Camera, MjpegWriter};
+
+use crate::app::ToErrorResponse;
+
+const VIDEO_EXTENSION: &str = "mjpeg";
+const METADATA_EXTENSION: &str = "json";
+
+#[derive(Debug, LibError)]
+pub enum Error {
+    #[error("Input/output error: {0:?}")]
+    IO(#[from] IOError),
+
+    #[error("Video error: {0}")]
+    Video(#[from] libvideo::Error),
+
+    #[error("Invalid recording metadata: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Already recording.")]
+    AlreadyRecording,
+
+    #[error("Not recording.")]
+    NotRecording,
+
+    #[error("Recording was interrupted.")]
+    Interrupted,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl ToErrorResponse for Error {
+    fn to_error_response(&self) -> ErrorResponse {
+        let code = match self {
+            Error::AlreadyRecording | Error::NotRecording => ErrorCode::Conflict,
+            _ => ErrorCode::Internal,
+        };
+
+        ErrorResponse::new(code, self.to_string())
+    }
+}
+
+/// Records camera video into directory, one recording at a time. Each recording is stored as
+/// MJPEG file along with JSON file of its [Recording] description.
+pub struct Recorder {
+    dir: PathBuf,
+    active: Mutex<Option<ActiveRecording>>,
+}
+
+struct ActiveRecording {
+    recording: Arc<Mutex<Recording>>,
+    stop: oneshot::Sender<()>,
+    done: oneshot::Receiver<Recording>,
+}
+
+impl Recorder {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Recorder {
+        Recorder {
+            dir: dir.into(),
+            active: Mutex::new(None),
+        }
+    }
+
+    pub fn start(&self, camera: &Camera, tags: CaptureTags) -> Result<Recording> {
+        let mut active = self.active.lock().unwrap();
+        if active.is_some() {
+            return Err(Error::AlreadyRecording);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+
+        let name = format!("recording-{}", tags.timestamp_ms);
+        let writer = MjpegWriter::create(self.path_of(&name, VIDEO_EXTENSION))?;
+        let recording = Recording {
+            name,
+            tags,
+            frames: 0,
+            duration_ms: 0,
+            size_bytes: 0,
+            in_progress: true,
+        };
+
+        info!("Starting recording {}.", recording.name);
+
+        let shared = Arc::new(Mutex::new(recording.clone()));
+        let (stop, stopped) = oneshot::channel();
+        let (finished, done) = oneshot::channel();
+        let metadata_path = self.path_of(&recording.name, METADATA_EXTENSION);
+
+        let frames = camera.stream().take_until(stopped);
+        actix_rt::spawn(Self::record(
+            frames,
+            writer,
+            shared.clone(),
+            metadata_path,
+            finished,
+        ));
+
+        *active = Some(ActiveRecording {
+            recording: shared,
+            stop,
+            done,
+        });
+
+        Ok(recording)
+    }
+
+    async fn record(
+        frames: impl futures::Stream<Item = libvideo::Frame>,
+        mut writer: MjpegWriter,
+        shared: Arc<Mutex<Recording>>,
+        metadata_path: PathBuf,
+        finished: oneshot::Sender<Recording>,
+    ) {
+        let mut frames = Box::pin(frames);
+
+        while let Some(frame) = frames.next().await {
+            if let Err(e) = writer.write(&frame) {
+                warn!("Recording stopped by failed write: {}", e);
+                break;
+            }
+
+            let mut recording = shared.lock().unwrap();
+            recording.frames = writer.frames();
+            recording.duration_ms = writer.duration_ms();
+            recording.size_bytes = writer.size();
+        }
+
+        if let Err(e) = writer.finish() {
+            warn!("Failed to complete recording: {}", e);
+        }
+
+        let recording = {
+            let mut recording = shared.lock().unwrap();
+            recording.in_progress = false;
+            recording.clone()
+        };
+
+        match serde_json::to_vec_pretty(&recording) {
+            Ok(metadata) => {
+                if let Err(e) = fs::write(&metadata_path, metadata) {
+                    warn!("Failed to save {}: {}", metadata_path.display(), e);
+                }
+            }
+            Err(e) => warn!("Failed to describe recording {}: {}", recording.name, e),
+        }
+
+        info!("Recording {} completed.", recording.name);
+
+        let _ = finished.send(recording);
+    }
+
+    pub async fn stop(&self) -> Result<Recording> {
+        let active = self.active.lock().unwrap().take();
+        let active = active.ok_or(Error::NotRecording)?;
+
+        let _ = active.stop.send(());
+
+        active.done.await.map_err(|_| Error::Interrupted)
+    }
+
+    /// Stored recordings and the ongoing one, oldest first.
+    pub fn list(&self) -> Result<Vec<Recording>> {
+        let mut recordings = vec![];
+
+        if self.dir.is_dir() {
+            for entry in fs::read_dir(&self.dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|e| e == METADATA_EXTENSION) {
+                    match serde_json::from_slice::<Recording>(&fs::read(&path)?) {
+                        Ok(recording) => recordings.push(recording),
+                        Err(e) => warn!("Skipping {}: {}", path.display(), e),
+                    }
+                }
+            }
+        }
+
+        if let Some(ref active) = *self.active.lock().unwrap() {
+            recordings.push(active.recording.lock().unwrap().clone());
+        }
+
+        recordings.sort_by_key(|r| r.tags.timestamp_ms);
+
+        Ok(recordings)
+    }
+
+    /// Video file of completed recording with given name.
+    pub fn video_of(&self, name: &str) -> Result<Option<PathBuf>> {
+        let completed = self
+            .list()?
+            .into_iter()
+            .any(|r| r.name == name && !r.in_progress);
+
+        Ok(completed.then(|| self.path_of(name, VIDEO_EXTENSION)))
+    }
+
+    fn path_of(&self, name: &str, extension: &str) -> PathBuf {
+        self.dir.join(name).with_extension(extension)
+    }
+}
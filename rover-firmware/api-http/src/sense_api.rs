@@ -1,44 +1,140 @@
 use actix_web::{get, web, Responder};
 use log::{debug, trace};
+use utoipa::OpenApi;
 
+use libapi_http::api::{
+    Detection, DetectionReading, DistanceReading, SensorDescriptor, SensorKind, SensorPosition,
+    ValueResponse,
+};
+use libdriver::api;
 use libdriver::api::AsyncSensor;
 
 use crate::app;
 use crate::app::map_rover_result_to_response;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_obstacles)
+    cfg.service(get_sensors)
+        .service(get_obstacles)
         .service(get_lines)
         .service(get_distance);
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(get_sensors, get_obstacles, get_lines, get_distance))]
+pub struct Api;
+
+pub fn to_position(position: api::SensorPosition) -> SensorPosition {
+    match position {
+        api::SensorPosition::Left => SensorPosition::Left,
+        api::SensorPosition::Center => SensorPosition::Center,
+        api::SensorPosition::Right => SensorPosition::Right,
+    }
+}
+
+pub fn to_descriptor(descriptor: api::SensorDescriptor) -> SensorDescriptor {
+    SensorDescriptor {
+        kind: match descriptor.kind {
+            api::SensorKind::Obstacle => SensorKind::Obstacle,
+            api::SensorKind::Line => SensorKind::Line,
+            api::SensorKind::Distance => SensorKind::Distance,
+        },
+        position: to_position(descriptor.position),
+    }
+}
+
+pub fn to_detection_reading(reading: api::DetectionReading) -> DetectionReading {
+    DetectionReading {
+        timestamp: reading.timestamp.0,
+        detections: reading
+            .detections
+            .into_iter()
+            .map(|d| Detection {
+                position: to_position(d.position),
+                detected: d.detected,
+            })
+            .collect(),
+    }
+}
+
+pub fn to_distance_reading(reading: api::DistanceReading) -> DistanceReading {
+    DistanceReading {
+        timestamp: reading.timestamp.0,
+        distance_mm: reading.distance.0,
+        valid: reading.valid,
+    }
+}
+
+/// Lists sensors of the rover.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Sensors.",
+    body = ValueResponse<Vec<SensorDescriptor>>
+)))]
+#[get("/sensors")]
+pub async fn get_sensors(state: web::Data<app::State>) -> impl Responder {
+    debug!("Requested to list sensors.");
+
+    let sensors = state.rover.call(|client| async move { client.get_sensors().await }).await;
+    let r = map_rover_result_to_response(
+        sensors.map(|sensors| sensors.into_iter().map(to_descriptor).collect::<Vec<_>>()),
+    );
+
+    trace!("Returning {:#?}", r);
+
+    r
+}
+
+/// Reads obstacle sensors.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Obstacle sensor states.",
+    body = ValueResponse<DetectionReading>
+)))]
 #[get("/obstacles")]
 pub async fn get_obstacles(state: web::Data<app::State>) -> impl Responder {
     debug!("Requested to provide obstacles data.");
 
-    let r = map_rover_result_to_response(state.rover_client.lock().await.get_obstacles().await);
+    let obstacles = state.rover.call(|client| async move { client.get_obstacles().await }).await;
+    let r = map_rover_result_to_response(obstacles.map(to_detection_reading));
 
     trace!("Returning {:#?}", r);
 
     r
 }
 
+/// Reads line sensors.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Line sensor states.",
+    body = ValueResponse<DetectionReading>
+)))]
 #[get("/lines")]
 pub async fn get_lines(state: web::Data<app::State>) -> impl Responder {
     debug!("Requested to provide lines data.");
 
-    let r = map_rover_result_to_response(state.rover_client.lock().await.get_lines().await);
+    let lines = state.rover.call(|client| async move { client.get_lines().await }).await;
+    let r = map_rover_result_to_response(lines.map(to_detection_reading));
 
     trace!("Returning {:#?}", r);
 
     r
 }
 
+/// Measures distance with sonar.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Distance.",
+    body = ValueResponse<DistanceReading>
+)))]
 #[get("/distance")]
 pub async fn get_distance(state: web::Data<app::State>) -> impl Responder {
     debug!("Requested to provide sonar distance.");
 
-    let r = map_rover_result_to_response(state.rover_client.lock().await.scan_distance().await);
+    let distance = state
+        .rover
+        .call(|mut client| async move { client.scan_distance().await })
+        .await;
+    let r = map_rover_result_to_response(distance.map(to_distance_reading));
 
     trace!("Returning {:#?}", r);
 
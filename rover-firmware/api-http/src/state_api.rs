@@ -0,0 +1,72 @@
+use actix_web::{get, web, Responder};
+use log::{debug, trace};
+use utoipa::OpenApi;
+
+use libapi_http::api::{LookRequest, MoveRequest, MoveType, RoverState, ValueResponse};
+use libdriver::api;
+use libdriver::api::AsyncSensor;
+
+use crate::app;
+use crate::app::map_rover_result_to_response;
+use crate::sense_api::{to_detection_reading, to_distance_reading};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_state);
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_state))]
+pub struct Api;
+
+pub fn to_movement(move_type: api::MoveType) -> Option<MoveRequest> {
+    let (r#type, speed) = match move_type {
+        api::MoveType::Forward(speed) => (MoveType::Forward, speed),
+        api::MoveType::Backward(speed) => (MoveType::Backward, speed),
+        api::MoveType::SpinCW(speed) => (MoveType::CWSpin, speed),
+        api::MoveType::SpinCCW(speed) => (MoveType::CCWSpin, speed),
+        api::MoveType::Drive(left, right) => {
+            return Some(MoveRequest {
+                r#type: MoveType::Drive,
+                speed: 0,
+                left: Some(left),
+                right: Some(right),
+            })
+        }
+        api::MoveType::None => return None,
+    };
+
+    Some(MoveRequest {
+        r#type,
+        speed,
+        left: None,
+        right: None,
+    })
+}
+
+pub fn to_state(snapshot: api::RoverSnapshot) -> RoverState {
+    RoverState {
+        obstacles: to_detection_reading(snapshot.obstacles),
+        lines: to_detection_reading(snapshot.lines),
+        distance: to_distance_reading(snapshot.distance),
+        movement: snapshot.move_type.and_then(to_movement),
+        look: snapshot.look_direction.map(|(h, v)| LookRequest { h, v }),
+    }
+}
+
+/// Reads all sensors together.
+#[utoipa::path(responses((
+    status = 200,
+    description = "Rover state.",
+    body = ValueResponse<RoverState>
+)))]
+#[get("")]
+pub async fn get_state(state: web::Data<app::State>) -> impl Responder {
+    debug!("Requested to provide rover state.");
+
+    let snapshot = state.rover.call(|mut client| async move { client.snapshot().await }).await;
+    let r = map_rover_result_to_response(snapshot.map(to_state));
+
+    trace!("Returning {:#?}", r);
+
+    r
+}
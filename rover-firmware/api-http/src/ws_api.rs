@@ -1,22 +1,202 @@
-use actix::{Actor, StreamHandler};
-use actix_web::{Error, get, HttpRequest, HttpResponse, web};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use actix::{
+    Actor, ActorContext, ActorFutureExt, AsyncContext, SpawnHandle, StreamHandler, WrapFuture,
+};
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
 use actix_web_actors::ws::{Message, ProtocolError};
-use log::trace;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::StreamExt;
+use log::{debug, trace, warn};
+use utoipa::OpenApi;
+
+use libapi_http::api::{MoveType, WsCommand, WsEvent};
+#[cfg(not(feature = "mock_upstream"))]
+use libapi_net::client::Client;
+#[cfg(feature = "mock_upstream")]
+use libapi_net::client::mock::Client;
+use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor};
+
+use crate::app;
+use crate::state_api::to_state;
+
+// fastest rate rover state is pushed at
+const MIN_UPDATE_PERIOD_MS: u32 = 20;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(index);
 }
 
-struct WebSocket;
+// messages of web-sockets are not part of operations, but their schemas are worth having
+#[derive(OpenApi)]
+#[openapi(paths(index), components(schemas(WsCommand, WsEvent)))]
+pub struct Api;
+
+// rover call of a command, made once calls of commands before it are done
+type Call = Pin<Box<dyn Future<Output = Result<(), String>>>>;
+
+/// Result of a queued call.
+struct CallOutcome(Result<(), String>);
+
+/// Control channel of a single client. Rover left moving by it is stopped once the socket closes.
+struct WebSocket {
+    state: web::Data<app::State>,
+    calls: UnboundedSender<Call>,
+    /// Taken by the actor once started.
+    queue: Option<UnboundedReceiver<Call>>,
+    moving: bool,
+    updates: Option<SpawnHandle>,
+    update_pending: bool,
+}
+
 impl WebSocket {
-    pub fn new() -> Self {
-        WebSocket {}
+    pub fn new(state: web::Data<app::State>) -> Self {
+        let (calls, queue) = unbounded();
+
+        WebSocket {
+            state,
+            calls,
+            queue: Some(queue),
+            moving: false,
+            updates: None,
+            update_pending: false,
+        }
+    }
+
+    fn send(ctx: &mut ws::WebsocketContext<Self>, event: &WsEvent) {
+        match serde_json::to_string(event) {
+            Ok(text) => ctx.text(text),
+            Err(e) => warn!("Failed to encode web-socket event {:?}: {}", event, e),
+        }
+    }
+
+    fn send_error(ctx: &mut ws::WebsocketContext<Self>, message: String) {
+        Self::send(ctx, &WsEvent::Error { message });
+    }
+
+    fn handle_command(&mut self, command: WsCommand, ctx: &mut ws::WebsocketContext<Self>) {
+        debug!("Requested over web-socket to {:?}", command);
+
+        match command {
+            WsCommand::Move(req) => {
+                if req.r#type == MoveType::Drive && (req.left.is_none() || req.right.is_none()) {
+                    Self::send_error(ctx, "Drive requires both left and right speeds.".to_owned());
+                    return;
+                }
+
+                self.moving = true;
+                self.queue_call(move |mut client| async move {
+                    match req.r#type {
+                        MoveType::Forward => client.move_forward(req.speed).await,
+                        MoveType::Backward => client.move_backward(req.speed).await,
+                        MoveType::CWSpin => client.spin_right(req.speed).await,
+                        MoveType::CCWSpin => client.spin_left(req.speed).await,
+                        MoveType::Drive => {
+                            client.drive(req.left.unwrap_or(0), req.right.unwrap_or(0)).await
+                        }
+                    }
+                });
+            }
+            WsCommand::Stop => {
+                self.moving = false;
+                self.queue_call(|mut client| async move { client.stop().await });
+            }
+            WsCommand::Heartbeat => {
+                self.queue_call(|mut client| async move { client.heartbeat().await });
+            }
+            WsCommand::Look(req) => {
+                self.queue_call(move |mut client| async move { client.look_at(req.h, req.v).await });
+            }
+            WsCommand::Subscribe { period_ms } => {
+                let period = Duration::from_millis(period_ms.max(MIN_UPDATE_PERIOD_MS) as u64);
+
+                self.unsubscribe(ctx);
+                self.updates = Some(ctx.run_interval(period, Self::push_state));
+            }
+            WsCommand::Unsubscribe => self.unsubscribe(ctx),
+        }
+    }
+
+    /// Queues the call behind those of earlier commands, so the rover gets commands in order they
+    /// were sent.
+    fn queue_call<T, F, R>(&self, call: F)
+    where
+        F: FnOnce(Client) -> R + 'static,
+        R: Future<Output = Result<T, libapi_net::Error>> + 'static,
+    {
+        let state = self.state.clone();
+        let call = async move {
+            state
+                .rover
+                .call(call)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        };
+
+        // queue is gone only once the actor stops, when there is no one to answer anyway
+        let _ = self.calls.unbounded_send(Box::pin(call));
+    }
+
+    fn push_state(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        // slow rover skips updates instead of piling up requests
+        if self.update_pending {
+            return;
+        }
+        self.update_pending = true;
+
+        let state = self.state.clone();
+        let snapshot = async move {
+            state
+                .rover
+                .call(|mut client| async move { client.snapshot().await })
+                .await
+        };
+
+        ctx.spawn(snapshot.into_actor(self).map(|result, act, ctx| {
+            act.update_pending = false;
+
+            match result {
+                Ok(snapshot) => Self::send(ctx, &WsEvent::State(to_state(snapshot))),
+                Err(e) => Self::send_error(ctx, e.to_string()),
+            }
+        }));
+    }
+
+    fn unsubscribe(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        if let Some(updates) = self.updates.take() {
+            ctx.cancel_future(updates);
+        }
     }
 }
 
 impl Actor for WebSocket {
     type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if let Some(queue) = self.queue.take() {
+            ctx.add_stream(queue.then(|call| call).map(CallOutcome));
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        if !self.moving {
+            return;
+        }
+
+        debug!("Stopping the rover left moving by closed web-socket.");
+
+        let state = self.state.clone();
+        actix_rt::spawn(async move {
+            let stop = state.rover.call(|mut client| async move { client.stop().await });
+            if let Err(e) = stop.await {
+                warn!("Failed to stop the rover: {}", e);
+            }
+        });
+    }
 }
 
 impl StreamHandler<Result<Message, ProtocolError>> for WebSocket {
@@ -24,16 +204,47 @@ impl StreamHandler<Result<Message, ProtocolError>> for WebSocket {
         trace!("Handling web-socket item: {:?}", item);
         match item {
             Ok(Message::Ping(msg)) => ctx.pong(&msg),
-            Ok(Message::Text(text)) => ctx.text(text),
-            Ok(Message::Binary(bin)) => ctx.binary(bin),
+            Ok(Message::Text(text)) => match serde_json::from_str::<WsCommand>(&text) {
+                Ok(command) => self.handle_command(command, ctx),
+                Err(e) => Self::send_error(ctx, format!("Invalid command: {}", e)),
+            },
+            Ok(Message::Binary(_)) => Self::send_error(ctx, "Commands are JSON text.".to_owned()),
+            Ok(Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Err(e) => {
+                warn!("Web-socket failed: {}", e);
+                ctx.stop();
+            }
             _ => (),
         }
     }
 }
 
+impl StreamHandler<CallOutcome> for WebSocket {
+    fn handle(&mut self, item: CallOutcome, ctx: &mut Self::Context) {
+        if let CallOutcome(Err(message)) = item {
+            Self::send_error(ctx, message);
+        }
+    }
+
+    // queue ends along with the actor, not the other way around
+    fn finished(&mut self, _: &mut Self::Context) {}
+}
+
+/// Opens control channel web-socket.
+#[utoipa::path(responses((
+    status = 101,
+    description = "Client sends WsCommand messages, server pushes WsEvent messages."
+)))]
 #[get("")]
-pub async fn index(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
-    let ws_actor = WebSocket::new();
+pub async fn index(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<app::State>,
+) -> Result<HttpResponse, Error> {
+    let ws_actor = WebSocket::new(state.clone());
     let response = ws::start(ws_actor, &req, stream);
 
     trace!("Returning {:?}", response);
@@ -0,0 +1,96 @@
+use config::Config;
+use log::info;
+use serde::Deserialize;
+
+use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor};
+use libdriver::util::a_sync::AsyncRover;
+use libdriver::util::boxed::{
+    boxed_looker, boxed_mover, boxed_sensor, BoxedLooker, BoxedMover, BoxedSensor,
+};
+use libdriver_replay::{ReplayConfig, ReplayRover};
+use libdriver_robohat::{RobohatConfig, RobohatRover};
+use libdriver_sim::{SimConfig, SimRover};
+use libutil::app::get_optional;
+use libutil::sys::normalize_path;
+
+#[derive(Debug, Deserialize, PartialEq, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum DriverType {
+    Robohat,
+    Sim,
+    Replay,
+}
+
+/// Rover control implementation selected in configuration.
+pub struct Driver {
+    pub mover: BoxedMover,
+    pub looker: BoxedLooker,
+    pub sensor: BoxedSensor,
+}
+
+impl Driver {
+    pub fn from_settings(settings: &Config) -> Result<Driver, Box<dyn std::error::Error>> {
+        let driver_type = get_optional::<DriverType>(settings, "driver.type")?
+            .unwrap_or(DriverType::Robohat);
+
+        info!("Using {:?} rover driver.", driver_type);
+
+        match driver_type {
+            DriverType::Robohat => {
+                let mut robohat_config =
+                    get_optional::<RobohatConfig>(settings, "driver.robohat")?.unwrap_or_default();
+
+                let current_dir = std::env::current_dir()?;
+                robohat_config.calibration = robohat_config
+                    .calibration
+                    .map(|path| normalize_path(&path, &current_dir));
+
+                info!("Driving motors with {:?}.", robohat_config.motor_pwm);
+                info!("Driving servos with {:?}.", robohat_config.servo);
+
+                let async_rover: AsyncRover<RobohatRover> =
+                    RobohatRover::with_config(robohat_config)?.into();
+
+                Ok(Driver::from_rover(async_rover))
+            }
+            DriverType::Sim => {
+                let current_dir = std::env::current_dir()?;
+                let map_path = normalize_path(&settings.get_string("driver.sim.map")?, &current_dir);
+                let sim_config = get_optional::<SimConfig>(settings, "driver.sim")?
+                    .unwrap_or_default();
+
+                info!("Loading simulated world from {}.", map_path);
+
+                let async_rover: AsyncRover<SimRover> =
+                    SimRover::from_map(map_path, sim_config)?.into();
+
+                Ok(Driver::from_rover(async_rover))
+            }
+            DriverType::Replay => {
+                let current_dir = std::env::current_dir()?;
+                let session_path =
+                    normalize_path(&settings.get_string("driver.replay.session")?, &current_dir);
+                let replay_config = get_optional::<ReplayConfig>(settings, "driver.replay")?
+                    .unwrap_or_default();
+
+                info!("Replaying driver session from {}.", session_path);
+
+                let async_rover: AsyncRover<ReplayRover> =
+                    ReplayRover::from_file(session_path, replay_config)?.into();
+
+                Ok(Driver::from_rover(async_rover))
+            }
+        }
+    }
+
+    fn from_rover<T>(rover: T) -> Driver
+    where
+        T: AsyncMover + AsyncLooker + AsyncSensor + Clone + Send + Sync + 'static,
+    {
+        Driver {
+            mover: boxed_mover(rover.clone()),
+            looker: boxed_looker(rover.clone()),
+            sensor: boxed_sensor(rover),
+        }
+    }
+}
@@ -1,10 +1,16 @@
+use std::time::Duration;
+
 use log::info;
 
 use libapi_net::server::Server;
-use libdriver::util::a_sync::AsyncRover;
-use libdriver_robohat::RobohatRover;
+use libapi_net::telemetry::{Recorded, Recorder, TelemetryConfig};
+
+use libutil::app::{bootstrap, get_optional};
+use libutil::sys::normalize_path;
+
+use crate::driver::Driver;
 
-use libutil::app::bootstrap;
+mod driver;
 
 const CONFIG_FILE: &str = "Config.toml";
 
@@ -21,12 +27,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // create server
     let mut server = Server::new(&listen_addr).await?;
 
+    // record what is going on, if asked to
+    let recorder = match get_optional::<TelemetryConfig>(&settings, "telemetry")? {
+        Some(mut telemetry_config) => {
+            telemetry_config.path =
+                normalize_path(&telemetry_config.path, &std::env::current_dir()?);
+
+            Some(Recorder::start(telemetry_config)?)
+        }
+        None => None,
+    };
+
     // link api-net server with actual rover control implementation
-    let async_rover: AsyncRover<RobohatRover> = RobohatRover::new()?.into();
+    let driver = Driver::from_settings(&settings)?;
+
+    server.register_mover(Some(Recorded::new(driver.mover, recorder.clone())));
+    server.register_looker(Some(Recorded::new(driver.looker, recorder.clone())));
+    server.register_sensor(Some(Recorded::new(driver.sensor, recorder.clone())));
+    server.set_recorder(recorder);
 
-    server.register_mover(Some(async_rover.clone()));
-    server.register_looker(Some(async_rover.clone()));
-    server.register_sensor(Some(async_rover.clone()));
+    // stop the rover if its controller goes silent
+    let watchdog_timeout = get_optional::<u64>(&settings, "watchdog_timeout_ms")?;
+    server.set_watchdog_timeout(watchdog_timeout.map(Duration::from_millis));
 
     // start run loop
     server.serve().await?;
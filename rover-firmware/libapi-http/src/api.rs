@@ -1,34 +1,281 @@
 use serde::{Deserialize, Serialize};
 use strum_macros::Display as EnumDisplay;
+use utoipa::ToSchema;
 
-#[derive(Debug, EnumDisplay, Serialize, Deserialize, PartialEq, Copy, Clone)]
+#[derive(Debug, EnumDisplay, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
 pub enum MoveType {
     Forward,
     Backward,
     CWSpin,
     CCWSpin,
+    Drive,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
 pub struct MoveRequest {
     pub r#type: MoveType,
+
+    /// Speed of all move types except `Drive`.
+    #[serde(default)]
     pub speed: u8,
+
+    /// Left and right side speeds of `Drive` move type, in [-255; 255] range. Negative speeds
+    /// move backward.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left: Option<i16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right: Option<i16>,
 }
 
-#[derive(Debug, EnumDisplay, Serialize, Deserialize, PartialEq, Copy, Clone)]
+#[derive(Debug, EnumDisplay, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
 pub enum SenseType {
     Lines,
     Obstacles,
     Distance,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Where a sensor is mounted on the rover, as seen from behind.
+#[derive(Debug, EnumDisplay, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
+pub enum SensorPosition {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, EnumDisplay, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
+pub enum SensorKind {
+    Obstacle,
+    Line,
+    Distance,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
+pub struct SensorDescriptor {
+    pub kind: SensorKind,
+    pub position: SensorPosition,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
+pub struct Detection {
+    pub position: SensorPosition,
+    pub detected: bool,
+}
+
+/// States of all binary sensors of one kind, read together.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone, Default)]
+pub struct DetectionReading {
+    /// Monotonic rover time the reading was taken at, in ms.
+    pub timestamp: u64,
+    pub detections: Vec<Detection>,
+}
+
+impl DetectionReading {
+    /// State of the sensor at given position, if there is one.
+    pub fn at(&self, position: SensorPosition) -> Option<bool> {
+        self.detections
+            .iter()
+            .find(|d| d.position == position)
+            .map(|d| d.detected)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone, Default)]
+pub struct DistanceReading {
+    /// Monotonic rover time the reading was taken at, in ms.
+    pub timestamp: u64,
+    pub distance_mm: f32,
+
+    /// False if nothing was found within sensor range, `distance_mm` is the range then.
+    pub valid: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
 pub struct LookRequest {
     pub h: i16,
     pub v: i16,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// All sensor readings taken together, with the motion and look direction they were taken in.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone, Default)]
+pub struct RoverState {
+    pub obstacles: DetectionReading,
+    pub lines: DetectionReading,
+    pub distance: DistanceReading,
+
+    /// Current motion in the form it is requested in, absent while the rover stands still.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub movement: Option<MoveRequest>,
+
+    /// Absent if the rover cannot look around.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub look: Option<LookRequest>,
+}
+
+#[derive(Debug, EnumDisplay, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
+pub enum BehaviorType {
+    /// Drives around avoiding obstacles.
+    Wander,
+
+    /// Follows a line on the floor.
+    LineFollow,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
+pub struct BehaviorRequest {
+    pub r#type: BehaviorType,
+
+    /// Cruise speed, behavior's default one if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<u8>,
+}
+
+/// Command sent by client over web-socket control channel, as JSON text message tagged with
+/// `command` field, e.g. `{"command": "look", "h": 0, "v": 30}`.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum WsCommand {
+    Move(MoveRequest),
+    Stop,
+
+    /// Keeps the rover moving, must come more often than rover's watchdog timeout.
+    Heartbeat,
+    Look(LookRequest),
+
+    /// Requests rover state to be pushed with given period, replacing the previous request.
+    Subscribe { period_ms: u32 },
+    Unsubscribe,
+}
+
+/// Message pushed to client over web-socket control channel, as JSON text message tagged with
+/// `event` field.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum WsEvent {
+    State(RoverState),
+
+    /// Command could not be read or carried out.
+    Error { message: String },
+}
+
+/// Rover state a camera capture was taken in.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone, Default)]
+pub struct CaptureTags {
+    /// Capture time, in ms since UNIX epoch.
+    pub timestamp_ms: u64,
+
+    /// Absent while the rover stands still or if its motion could not be queried.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub movement: Option<MoveRequest>,
+
+    /// Absent if the rover cannot look around or its look direction could not be queried.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub look: Option<LookRequest>,
+}
+
+/// Camera video stored on the rover as MJPEG file.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
+pub struct Recording {
+    pub name: String,
+
+    /// Rover state at the start of the recording.
+    pub tags: CaptureTags,
+
+    pub frames: u32,
+    pub duration_ms: u64,
+    pub size_bytes: u64,
+
+    /// Set while the recording is still going on.
+    #[serde(default)]
+    pub in_progress: bool,
+}
+
+/// Camera frame pushed over video web-socket as binary message: capture time as big-endian
+/// ms since UNIX epoch followed by JPEG image.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CameraFrame<'a> {
+    pub timestamp_ms: u64,
+    pub jpeg: &'a [u8],
+}
+
+impl<'a> CameraFrame<'a> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.jpeg.len());
+        bytes.extend_from_slice(&self.timestamp_ms.to_be_bytes());
+        bytes.extend_from_slice(self.jpeg);
+        bytes
+    }
+
+    /// Absent if the message is too short to be a frame.
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<CameraFrame<'a>> {
+        let (timestamp, jpeg) = bytes.split_first_chunk::<8>()?;
+
+        Some(CameraFrame {
+            timestamp_ms: u64::from_be_bytes(*timestamp),
+            jpeg,
+        })
+    }
+}
+
+/// Kind of failed request, telling clients how to react to it.
+#[derive(Debug, EnumDisplay, Serialize, Deserialize, ToSchema, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Request is malformed or its values are out of range.
+    InvalidRequest,
+
+    /// Requested resource does not exist.
+    NotFound,
+
+    /// Request conflicts with current state, like starting what is already running.
+    Conflict,
+
+    /// Rover cannot do what is requested.
+    Unsupported,
+
+    /// Rover failed to do what is requested.
+    RoverFailure,
+
+    /// Rover or the device requested is not available at the moment, request may be retried.
+    Unavailable,
+
+    /// Failure of api-http itself.
+    Internal,
+}
+
+impl ErrorCode {
+    /// HTTP status failed requests are answered with.
+    pub fn status(&self) -> u16 {
+        match self {
+            ErrorCode::InvalidRequest => 400,
+            ErrorCode::NotFound => 404,
+            ErrorCode::Conflict => 409,
+            ErrorCode::Internal => 500,
+            ErrorCode::Unsupported => 501,
+            ErrorCode::RoverFailure => 502,
+            ErrorCode::Unavailable => 503,
+        }
+    }
+}
+
+/// Body of failed request response.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Clone)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+
+    /// Human readable explanation of the failure.
+    pub message: String,
+}
+
+impl ErrorResponse {
+    pub fn new<M: Into<String>>(code: ErrorCode, message: M) -> Self {
+        ErrorResponse {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValueResponse<T> {
     pub value: T,
 }
@@ -1,99 +1,358 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
 use either::Either;
 use futures::lock::Mutex;
-use futures::{FutureExt, SinkExt, StreamExt};
-use log::{error, trace};
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use log::{debug, error, trace};
 use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
 use tokio_serde_cbor::Codec;
 use tokio_util::codec::{Decoder, Framed};
 
 use async_trait::async_trait;
-use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor, MoveType};
+use libbehavior::{BehaviorConfig, BehaviorHost, Mission, MissionProgress};
+use libdriver::api::{
+    AsyncLooker, AsyncMover, AsyncSensor, DetectionReading, DistanceReading, LookLimits, MoveType,
+    RoverSnapshot, SensorDescriptor,
+};
 
 use crate::contract::data::{
-    LookData, ProtocolMessage, SenseRequestData, SenseResponseData,
-    StatusResponseData,
+    CapabilitiesData, ClientHelloData, ControlStatusData, Envelope, ErrorKind, LookData, NotificationData, ProtocolMessage, SenseRequestData,
+    RequestId, SenseResponseData, ServerHelloData, StatusResponseData, SubscriptionData, SubscriptionPolicy,
 };
+use crate::contract::PROTOCOL_VERSION;
 use crate::{Error, Result};
 
-type ChannelType = Framed<TcpStream, Codec<ProtocolMessage, ProtocolMessage>>;
+type ChannelType = Framed<TcpStream, Codec<Envelope, Envelope>>;
+
+/// Requests awaiting responses.
+// absent once the connection is closed, so that no request waits for response that never comes
+type PendingResponses =
+    Arc<std::sync::Mutex<Option<HashMap<RequestId, oneshot::Sender<Result<ProtocolMessage>>>>>>;
+
+// how many notifications are kept for slow receivers
+const NOTIFICATION_BUFFER_SIZE: usize = 64;
 
+const DEFAULT_CLIENT_NAME: &str = concat!("libapi-net/", env!("CARGO_PKG_VERSION"));
+
+/// Connection to api-net server. Clones share the same connection and may send requests
+/// concurrently.
+#[derive(Clone)]
 pub struct Client {
-    channel: Mutex<ChannelType>,
+    name: String,
+    connection: Arc<Connection>,
+    server: Arc<ServerHelloData>,
+    notifications: broadcast::Sender<NotificationData>,
+}
+
+struct Connection {
+    sink: Mutex<SplitSink<ChannelType, Envelope>>,
+    pending: PendingResponses,
+    last_request_id: AtomicU64,
+    receiver: JoinHandle<()>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.receiver.abort();
+        Client::fail_pending(&self.pending);
+    }
 }
 
 impl Client {
     pub async fn new<T: ToSocketAddrs>(net_api_address: T) -> Result<Client> {
+        Self::with_name(net_api_address, DEFAULT_CLIENT_NAME).await
+    }
+
+    /// Connects to the server introducing itself with given name.
+    pub async fn with_name<T: ToSocketAddrs>(net_api_address: T, name: &str) -> Result<Client> {
+        let (notifications, _) = broadcast::channel(NOTIFICATION_BUFFER_SIZE);
+        let connection = Self::connect(net_api_address, notifications.clone()).await?;
+        let server = Self::handshake(&connection, name).await?;
+
         Ok(Client {
-            channel: Mutex::new(Self::connect(net_api_address).await?),
+            name: name.to_owned(),
+            connection: Arc::new(connection),
+            server: Arc::new(server),
+            notifications,
         })
     }
 
+    /// Replaces connection of this client. Clones made before keep using the old one.
     pub async fn reconnect<T: ToSocketAddrs>(&mut self, net_api_address: T) -> Result<()> {
-        self.channel = Mutex::new(Self::connect(net_api_address).await?);
+        let connection = Self::connect(net_api_address, self.notifications.clone()).await?;
+        let server = Self::handshake(&connection, &self.name).await?;
+
+        self.connection = Arc::new(connection);
+        self.server = Arc::new(server);
 
         Ok(())
     }
 
-    async fn connect<T: ToSocketAddrs>(net_api_address: T) -> Result<ChannelType> {
+    async fn handshake(connection: &Connection, name: &str) -> Result<ServerHelloData> {
+        let msg = ProtocolMessage::HelloRequest(ClientHelloData {
+            protocol_version: PROTOCOL_VERSION,
+            name: name.to_owned(),
+            notifications: true,
+        });
+
+        let process_hello_response = |message| match message {
+            ProtocolMessage::HelloResponse(hello) if hello.protocol_version == PROTOCOL_VERSION => {
+                Either::Left(Ok(hello))
+            }
+            ProtocolMessage::HelloResponse(hello) => Either::Left(Err(Error::Incompatible(format!(
+                "server '{}' speaks protocol version {}, client speaks {}.",
+                hello.name, hello.protocol_version, PROTOCOL_VERSION
+            )))),
+            ProtocolMessage::StatusResponse(StatusResponseData::Error(_, e)) => {
+                Either::Left(Err(Error::Incompatible(e)))
+            }
+            _ => Either::Right(message),
+        };
+
+        let server = Self::exchange_over(connection, msg, process_hello_response).await?;
+
+        debug!("Connected to '{}': {:#?}", server.name, server.capabilities);
+
+        Ok(server)
+    }
+
+    /// Name of the server, as it introduced itself.
+    pub fn server_name(&self) -> &str {
+        &self.server.name
+    }
+
+    /// Whether the connection is still open. Requests over closed one fail with
+    /// [Error::Disconnected] until the client reconnects.
+    pub fn is_connected(&self) -> bool {
+        self.connection.pending.lock().unwrap().is_some()
+    }
+
+    /// Rover controls available through the server.
+    pub fn capabilities(&self) -> &CapabilitiesData {
+        &self.server.capabilities
+    }
+
+    async fn connect<T: ToSocketAddrs>(
+        net_api_address: T,
+        notifications: broadcast::Sender<NotificationData>,
+    ) -> Result<Connection> {
         let stream = TcpStream::connect(net_api_address).await?;
-        let remote_addr = stream.peer_addr().unwrap();
+        let remote_addr = stream.peer_addr()?;
 
         trace!("[{}] Connected.", remote_addr);
 
-        let codec: Codec<ProtocolMessage, ProtocolMessage> = Codec::new();
+        let codec: Codec<Envelope, Envelope> = Codec::new();
+        let (sink, stream) = codec.framed(stream).split();
+        let pending = PendingResponses::new(std::sync::Mutex::new(Some(HashMap::new())));
+
+        let receiver = tokio::spawn(Self::receive(stream, Arc::clone(&pending), notifications));
 
-        Ok(codec.framed(stream))
+        Ok(Connection {
+            sink: Mutex::new(sink),
+            pending,
+            last_request_id: AtomicU64::new(0),
+            receiver,
+        })
+    }
+
+    /// Routes incoming messages either to the requests awaiting responses or to notification
+    /// receivers.
+    async fn receive(
+        mut stream: SplitStream<ChannelType>,
+        pending: PendingResponses,
+        notifications: broadcast::Sender<NotificationData>,
+    ) {
+        while let Some(envelope) = stream.next().await {
+            match envelope {
+                Ok(Envelope {
+                    message: ProtocolMessage::Notification(notification),
+                    ..
+                }) => {
+                    trace!("Notification from api-net: {:#?}", notification);
+
+                    // no receivers is fine, nobody is interested
+                    let _ = notifications.send(notification);
+                }
+                Ok(Envelope { id, message }) => {
+                    let response = id.and_then(|id| {
+                        pending.lock().unwrap().as_mut().and_then(|p| p.remove(&id))
+                    });
+
+                    match response {
+                        // requester may have given up already
+                        Some(response) => {
+                            let _ = response.send(Ok(message));
+                        }
+                        None => debug!("Discarding unexpected message #{:?}: {:#?}", id, message),
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to receive message: {}", e);
+                    break;
+                }
+            }
+        }
+
+        error!("Connection closed.");
+
+        Self::fail_pending(&pending);
+    }
+
+    fn fail_pending(pending: &PendingResponses) {
+        let pending = pending.lock().unwrap().take();
+
+        for (_, response) in pending.into_iter().flatten() {
+            let _ = response.send(Err(Error::Disconnected));
+        }
     }
 
     async fn exchange<T, F>(&self, request: ProtocolMessage, response_processor: F) -> Result<T>
+    where
+        F: Fn(ProtocolMessage) -> Either<Result<T>, ProtocolMessage>,
+    {
+        Self::exchange_over(&self.connection, request, response_processor).await
+    }
+
+    async fn exchange_over<T, F>(
+        connection: &Connection,
+        request: ProtocolMessage,
+        response_processor: F,
+    ) -> Result<T>
     where
         F: Fn(ProtocolMessage) -> Either<Result<T>, ProtocolMessage>,
     {
         trace!("Request to api-net: {:#?}", request);
 
-        self.channel
-            .lock()
-            .then(|mut guard| async move { guard.send(request).await })
-            .await?;
-
-        let r = self
-            .channel
-            .lock()
-            .then(|mut guard| async move { guard.next().await })
-            .await;
-
-        match r {
-            Some(response) => match response {
-                Ok(message) => {
-                    trace!("Response from api-net: {:#?}", message);
-
-                    match response_processor(message) {
-                        Either::Left(value) => value,
-                        Either::Right(msg) => Err(Error::Protocol(msg)),
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to receive message: {}", e);
-                    Err(e.into())
-                }
-            },
-            None => {
-                error!("Connection closed.");
-                Err(Error::Disconnected)
+        let id = connection.last_request_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (response_sender, response) = oneshot::channel();
+
+        match connection.pending.lock().unwrap().as_mut() {
+            Some(pending) => pending.insert(id, response_sender),
+            None => return Err(Error::Disconnected),
+        };
+
+        let envelope = Envelope {
+            id: Some(id),
+            message: request,
+        };
+
+        if let Err(e) = connection.sink.lock().await.send(envelope).await {
+            if let Some(pending) = connection.pending.lock().unwrap().as_mut() {
+                pending.remove(&id);
             }
+
+            return Err(e.into());
         }
+
+        let message = response.await.map_err(|_| Error::Disconnected)??;
+
+        trace!("Response from api-net: {:#?}", message);
+
+        match response_processor(message) {
+            Either::Left(value) => value,
+            Either::Right(msg) => Err(Error::Protocol(msg)),
+        }
+    }
+
+    /// Receiver of messages pushed by the server, such as subscribed sensor values.
+    pub fn notifications(&self) -> broadcast::Receiver<NotificationData> {
+        self.notifications.subscribe()
+    }
+
+    /// Requests the server to push values of given sensors as notifications. Replaces any previous
+    /// subscription.
+    pub async fn subscribe(
+        &self,
+        sensors: Vec<SenseRequestData>,
+        policy: SubscriptionPolicy,
+    ) -> Result<()> {
+        let msg = ProtocolMessage::SubscribeRequest(SubscriptionData { sensors, policy });
+
+        self.exchange(msg, Self::process_status).await
+    }
+
+    pub async fn unsubscribe(&self) -> Result<()> {
+        self.exchange(ProtocolMessage::UnsubscribeRequest, Self::process_status).await
     }
 
     fn process_status(message: ProtocolMessage) -> Either<Result<()>, ProtocolMessage> {
         if let ProtocolMessage::StatusResponse(status) = message {
             Either::Left(match status {
                 StatusResponseData::Success => Ok(()),
-                StatusResponseData::Error(e) => Err(Error::Server(e)),
+                StatusResponseData::Error(kind, e) => Err(Error::Server(kind, e)),
             })
         } else {
             Either::Right(message)
         }
     }
+
+    /// Requests exclusive right to move the rover and to look around. Fails if other client
+    /// already has it. Note, the right is also acquired implicitly by the first move or look
+    /// request made while nobody controls the rover.
+    pub async fn request_control(&self) -> Result<()> {
+        self.exchange(ProtocolMessage::ControlRequest, Self::process_status).await
+    }
+
+    /// Gives up control of the rover. Rover controls are reset once released.
+    pub async fn release_control(&self) -> Result<()> {
+        self.exchange(ProtocolMessage::ControlReleaseRequest, Self::process_status).await
+    }
+
+    /// Acquires control of the rover even if other client has it.
+    pub async fn take_control(&self) -> Result<()> {
+        self.exchange(ProtocolMessage::ControlTakeoverRequest, Self::process_status).await
+    }
+
+    pub async fn get_control_status(&self) -> Result<ControlStatusData> {
+        let msg = ProtocolMessage::ControlStatusRequest;
+
+        let process_control_status_response = |message| {
+            match message {
+                ProtocolMessage::ControlStatusResponse(status) => Either::Left(Ok(status)),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
+                _ => Either::Right(message)
+            }
+        };
+
+        self.exchange(msg, process_control_status_response).await
+    }
+
+    /// Stores the mission on the rover, acquiring control if nobody else has it.
+    pub async fn upload_mission(&self, mission: Mission) -> Result<()> {
+        self.exchange(ProtocolMessage::MissionUploadRequest(mission), Self::process_status).await
+    }
+
+    /// Runs uploaded mission from the start, or resumes paused one.
+    pub async fn start_mission(&self) -> Result<()> {
+        self.exchange(ProtocolMessage::MissionStartRequest, Self::process_status).await
+    }
+
+    pub async fn pause_mission(&self) -> Result<()> {
+        self.exchange(ProtocolMessage::MissionPauseRequest, Self::process_status).await
+    }
+
+    pub async fn abort_mission(&self) -> Result<()> {
+        self.exchange(ProtocolMessage::MissionAbortRequest, Self::process_status).await
+    }
+
+    pub async fn get_mission_progress(&self) -> Result<Option<MissionProgress>> {
+        let msg = ProtocolMessage::MissionProgressRequest;
+
+        let process_mission_progress_response = |message| {
+            match message {
+                ProtocolMessage::MissionProgressResponse(progress) => Either::Left(Ok(progress)),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
+                _ => Either::Right(message)
+            }
+        };
+
+        self.exchange(msg, process_mission_progress_response).await
+    }
 }
 
 #[async_trait]
@@ -130,13 +389,23 @@ impl AsyncMover for Client {
         self.exchange(msg, Self::process_status).await
     }
 
+    async fn drive(&mut self, left: i16, right: i16) -> Result<()> {
+        let msg = ProtocolMessage::MoveRequest(MoveType::Drive(left, right));
+
+        self.exchange(msg, Self::process_status).await
+    }
+
+    async fn heartbeat(&mut self) -> Result<()> {
+        self.exchange(ProtocolMessage::HeartbeatRequest, Self::process_status).await
+    }
+
     async fn get_move_type(&self) -> std::result::Result<MoveType, Self::Error> {
         let msg = ProtocolMessage::MoveDirectionRequest;
 
         let process_move_direction_response = |message| {
             match message {
                 ProtocolMessage::MoveDirectionResponse(move_type) => Either::Left(Ok(move_type)),
-                ProtocolMessage::StatusResponse(StatusResponseData::Error(e)) => Either::Left(Err(Error::Server(e))),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
                 _ => Either::Right(message)
             }
         };
@@ -161,26 +430,46 @@ impl AsyncLooker for Client {
         let process_look_direction_response = |message| {
             match message {
                 ProtocolMessage::LookDirectionResponse(LookData { x, y }) => Either::Left(Ok((x, y))),
-                ProtocolMessage::StatusResponse(StatusResponseData::Error(e)) => Either::Left(Err(Error::Server(e))),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
                 _ => Either::Right(message)
             }
         };
 
         self.exchange(msg, process_look_direction_response).await
     }
+
+    async fn get_look_limits(&self) -> Result<LookLimits> {
+        self.server
+            .capabilities
+            .looker
+            .ok_or_else(|| {
+                Error::Server(ErrorKind::Unsupported, "Unsupported operation.".to_owned())
+            })
+    }
 }
 
 #[async_trait]
 impl AsyncSensor for Client {
     type Error = Error;
 
-    async fn get_obstacles(&self) -> Result<Vec<bool>> {
+    async fn get_sensors(&self) -> Result<Vec<SensorDescriptor>> {
+        self.server
+            .capabilities
+            .sensor
+            .as_ref()
+            .map(|sensor| sensor.sensors.clone())
+            .ok_or_else(|| {
+                Error::Server(ErrorKind::Unsupported, "Unsupported operation.".to_owned())
+            })
+    }
+
+    async fn get_obstacles(&self) -> Result<DetectionReading> {
         let msg = ProtocolMessage::SenseRequest(SenseRequestData::Obstacle);
 
         let process_sense_response = |message| {
             match message {
                 ProtocolMessage::SenseResponse(SenseResponseData::Obstacle(obstacle_data)) => Either::Left(Ok(obstacle_data)),
-                ProtocolMessage::StatusResponse(StatusResponseData::Error(e)) => Either::Left(Err(Error::Server(e))),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
                 _ => Either::Right(message)
             }
         };
@@ -188,13 +477,13 @@ impl AsyncSensor for Client {
         self.exchange(msg, process_sense_response).await
     }
 
-    async fn get_lines(&self) -> Result<Vec<bool>> {
+    async fn get_lines(&self) -> Result<DetectionReading> {
         let msg = ProtocolMessage::SenseRequest(SenseRequestData::Line);
 
         let process_sense_response = |message| {
             match message {
                 ProtocolMessage::SenseResponse(SenseResponseData::Line(line_data)) => Either::Left(Ok(line_data)),
-                ProtocolMessage::StatusResponse(StatusResponseData::Error(e)) => Either::Left(Err(Error::Server(e))),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
                 _ => Either::Right(message)
             }
         };
@@ -202,19 +491,61 @@ impl AsyncSensor for Client {
         self.exchange(msg, process_sense_response).await
     }
 
-    async fn scan_distance(&mut self) -> Result<f32> {
+    async fn scan_distance(&mut self) -> Result<DistanceReading> {
         let msg = ProtocolMessage::SenseRequest(SenseRequestData::Distance);
 
         let process_sense_response = |message| {
             match message {
                 ProtocolMessage::SenseResponse(SenseResponseData::Distance(distance)) => Either::Left(Ok(distance)),
-                ProtocolMessage::StatusResponse(StatusResponseData::Error(e)) => Either::Left(Err(Error::Server(e))),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
                 _ => Either::Right(message)
             }
         };
 
         self.exchange(msg, process_sense_response).await
     }
+
+    async fn snapshot(&mut self) -> Result<RoverSnapshot> {
+        let msg = ProtocolMessage::SnapshotRequest;
+
+        let process_snapshot_response = |message| {
+            match message {
+                ProtocolMessage::SnapshotResponse(snapshot) => Either::Left(Ok(snapshot)),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
+                _ => Either::Right(message)
+            }
+        };
+
+        self.exchange(msg, process_snapshot_response).await
+    }
+}
+
+#[async_trait]
+impl BehaviorHost for Client {
+    type Error = Error;
+
+    /// Has the rover driven by given behavior, acquiring control if nobody else has it.
+    async fn start_behavior(&mut self, behavior: BehaviorConfig) -> Result<()> {
+        self.exchange(ProtocolMessage::BehaviorStartRequest(behavior), Self::process_status).await
+    }
+
+    async fn stop_behavior(&mut self) -> Result<()> {
+        self.exchange(ProtocolMessage::BehaviorStopRequest, Self::process_status).await
+    }
+
+    async fn get_behavior(&self) -> Result<Option<BehaviorConfig>> {
+        let msg = ProtocolMessage::BehaviorStatusRequest;
+
+        let process_behavior_status_response = |message| {
+            match message {
+                ProtocolMessage::BehaviorStatusResponse(behavior) => Either::Left(Ok(behavior)),
+                ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => Either::Left(Err(Error::Server(kind, e))),
+                _ => Either::Right(message)
+            }
+        };
+
+        self.exchange(msg, process_behavior_status_response).await
+    }
 }
 
 #[cfg(feature = "mock_client")]
@@ -222,20 +553,145 @@ pub mod mock {
     use std::future;
     use rand::Rng;
     use async_trait::async_trait;
+    use libbehavior::{BehaviorConfig, BehaviorHost, Mission, MissionProgress};
     use tokio::net::ToSocketAddrs;
-    use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor};
+    use tokio::sync::broadcast;
+    use libdriver::api::{
+        AsyncLooker, AsyncMover, AsyncSensor, Detection, DetectionReading, DistanceReading,
+        LookLimits, Millimeters, MoveType, RoverSnapshot, SensorDescriptor, SensorKind,
+        SensorPosition,
+    };
+    use crate::contract::data::{
+        CapabilitiesData, ControlStatusData, NotificationData, SenseRequestData,
+        SensorCapabilitiesData, SubscriptionPolicy,
+    };
     use crate::Error;
 
-    pub struct Client {}
+    #[derive(Clone)]
+    pub struct Client {
+        notifications: broadcast::Sender<NotificationData>,
+        capabilities: CapabilitiesData,
+    }
+
+    const LOOK_LIMITS: LookLimits = LookLimits { h: (-90, 90), v: (-90, 90) };
+
+    const SENSORS: [SensorDescriptor; 5] = [
+        SensorDescriptor { kind: SensorKind::Obstacle, position: SensorPosition::Left },
+        SensorDescriptor { kind: SensorKind::Obstacle, position: SensorPosition::Right },
+        SensorDescriptor { kind: SensorKind::Line, position: SensorPosition::Left },
+        SensorDescriptor { kind: SensorKind::Line, position: SensorPosition::Right },
+        SensorDescriptor { kind: SensorKind::Distance, position: SensorPosition::Center },
+    ];
+
+    fn random_detections() -> DetectionReading {
+        let mut rng = rand::thread_rng();
+
+        DetectionReading::new(vec![
+            Detection { position: SensorPosition::Left, detected: rng.gen_bool(0.5) },
+            Detection { position: SensorPosition::Right, detected: rng.gen_bool(0.5) },
+        ])
+    }
 
     impl Client {
-        pub async fn new<T: ToSocketAddrs>(_net_api_address: T) -> crate::Result<Client> {
-            future::ready(Ok(Client {})).await
+        pub async fn new<T: ToSocketAddrs>(net_api_address: T) -> crate::Result<Client> {
+            Self::with_name(net_api_address, "mock").await
+        }
+
+        pub async fn with_name<T: ToSocketAddrs>(_net_api_address: T, _name: &str) -> crate::Result<Client> {
+            let (notifications, _) = broadcast::channel(1);
+            let capabilities = CapabilitiesData {
+                mover: true,
+                looker: Some(LOOK_LIMITS),
+                sensor: Some(SensorCapabilitiesData { sensors: SENSORS.to_vec() }),
+            };
+
+            future::ready(Ok(Client { notifications, capabilities })).await
+        }
+
+        pub fn server_name(&self) -> &str {
+            "mock"
+        }
+
+        pub fn is_connected(&self) -> bool {
+            true
+        }
+
+        pub fn capabilities(&self) -> &CapabilitiesData {
+            &self.capabilities
         }
 
         pub async fn reconnect<T: ToSocketAddrs>(&mut self, _net_api_address: T) -> crate::Result<()> {
             future::ready(Ok(())).await
         }
+
+        pub async fn request_control(&self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn release_control(&self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn take_control(&self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn get_control_status(&self) -> crate::Result<ControlStatusData> {
+            future::ready(Ok(ControlStatusData { controlled: true, in_control: true })).await
+        }
+
+        pub fn notifications(&self) -> broadcast::Receiver<NotificationData> {
+            self.notifications.subscribe()
+        }
+
+        pub async fn subscribe(
+            &self,
+            _sensors: Vec<SenseRequestData>,
+            _policy: SubscriptionPolicy,
+        ) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn unsubscribe(&self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn upload_mission(&self, _mission: Mission) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn start_mission(&self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn pause_mission(&self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn abort_mission(&self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        pub async fn get_mission_progress(&self) -> crate::Result<Option<MissionProgress>> {
+            future::ready(Ok(None)).await
+        }
+    }
+
+    #[async_trait]
+    impl BehaviorHost for Client {
+        type Error = Error;
+
+        async fn start_behavior(&mut self, _behavior: BehaviorConfig) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        async fn stop_behavior(&mut self) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        async fn get_behavior(&self) -> crate::Result<Option<BehaviorConfig>> {
+            future::ready(Ok(None)).await
+        }
     }
 
     #[async_trait]
@@ -261,6 +717,14 @@ pub mod mock {
         async fn spin_left(&mut self, _speed: u8) -> crate::Result<()> {
             future::ready(Ok(())).await
         }
+
+        async fn drive(&mut self, _left: i16, _right: i16) -> crate::Result<()> {
+            future::ready(Ok(())).await
+        }
+
+        async fn get_move_type(&self) -> crate::Result<MoveType> {
+            future::ready(Ok(MoveType::None)).await
+        }
     }
 
     #[async_trait]
@@ -270,28 +734,46 @@ pub mod mock {
         async fn look_at(&mut self, _h: i16, _v: i16) -> crate::Result<()> {
             future::ready(Ok(())).await
         }
+
+        async fn get_look_direction(&self) -> crate::Result<(i16, i16)> {
+            future::ready(Ok((0, 0))).await
+        }
+
+        async fn get_look_limits(&self) -> crate::Result<LookLimits> {
+            future::ready(Ok(LOOK_LIMITS)).await
+        }
     }
 
     #[async_trait]
     impl AsyncSensor for Client {
         type Error = Error;
 
-        async fn get_obstacles(&self) -> crate::Result<Vec<bool>> {
-            let mut rng = rand::thread_rng();
-            let r = vec![rng.gen_bool(0.5), rng.gen_bool(0.5)];
-            Ok(r)
+        async fn get_sensors(&self) -> crate::Result<Vec<SensorDescriptor>> {
+            future::ready(Ok(SENSORS.to_vec())).await
         }
 
-        async fn get_lines(&self) -> crate::Result<Vec<bool>> {
-            let mut rng = rand::thread_rng();
-            let r = vec![rng.gen_bool(0.5), rng.gen_bool(0.5)];
-            Ok(r)
+        async fn get_obstacles(&self) -> crate::Result<DetectionReading> {
+            Ok(random_detections())
+        }
+
+        async fn get_lines(&self) -> crate::Result<DetectionReading> {
+            Ok(random_detections())
         }
 
-        async fn scan_distance(&mut self) -> crate::Result<f32> {
+        async fn scan_distance(&mut self) -> crate::Result<DistanceReading> {
             let mut rng = rand::thread_rng();
-            let r: f32 = rng.gen();
-            Ok(r)
+            let r: f32 = rng.gen_range(0.0..4000.0);
+            Ok(DistanceReading::new(Millimeters(r), true))
+        }
+
+        async fn snapshot(&mut self) -> crate::Result<RoverSnapshot> {
+            Ok(RoverSnapshot {
+                obstacles: self.get_obstacles().await?,
+                lines: self.get_lines().await?,
+                distance: self.scan_distance().await?,
+                move_type: Some(MoveType::None),
+                look_direction: Some((0, 0)),
+            })
         }
     }
 }
\ No newline at end of file
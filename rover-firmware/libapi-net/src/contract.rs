@@ -1,9 +1,34 @@
+/// Version of the protocol implemented by this library. Peers speaking different versions refuse
+/// to work with each other.
+pub const PROTOCOL_VERSION: u16 = 7;
+
 pub mod data {
     use serde::{Deserialize, Serialize};
-    use libdriver::api::MoveType;
+    use libbehavior::{BehaviorConfig, Mission, MissionProgress};
+    use libdriver::api::{
+        DetectionReading, DistanceReading, LookLimits, MoveType, RoverSnapshot, SensorDescriptor,
+    };
+
+    pub type RequestId = u64;
 
+    /// Unit of data exchanged over the connection. Requests are processed in the order they were
+    /// sent, but client does not need to wait for response before sending next request.
     #[derive(Debug, Serialize, Deserialize)]
+    pub struct Envelope {
+        /// Id chosen by the client for the request, server puts the same one into the response.
+        /// Absent in notifications.
+        pub id: Option<RequestId>,
+        pub message: ProtocolMessage,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone)]
     pub enum ProtocolMessage {
+        /// Handshake request, must be the first one sent over the connection.
+        HelloRequest(ClientHelloData),
+
+        /// Response to the above.
+        HelloResponse(ServerHelloData),
+
         /// Request to move in given direction with given speed.
         MoveRequest(MoveType),
 
@@ -28,33 +53,201 @@ pub mod data {
         /// Response to the above.
         SenseResponse(SenseResponseData),
 
+        /// Request to read all sensors at once, along with current move and look directions.
+        SnapshotRequest,
+
+        /// Response to the above.
+        SnapshotResponse(RoverSnapshot),
+
         /// Response to requests that return no data (e.g. MoveRequest & LookRequest).
         StatusResponse(StatusResponseData),
+
+        /// Request to become the client controlling the rover, if no other client does.
+        ControlRequest,
+
+        /// Request to give up control of the rover.
+        ControlReleaseRequest,
+
+        /// Request to become the client controlling the rover, even if other client does.
+        ControlTakeoverRequest,
+
+        /// Request to see who controls the rover.
+        ControlStatusRequest,
+
+        /// Response to the above.
+        ControlStatusResponse(ControlStatusData),
+
+        /// Request to keep current motion going (see watchdog).
+        HeartbeatRequest,
+
+        /// Request to have sensor values pushed as notifications, replacing any previous subscription.
+        SubscribeRequest(SubscriptionData),
+
+        /// Request to stop pushing sensor values.
+        UnsubscribeRequest,
+
+        /// Request to have the rover driven by given behavior, replacing the running one. Behavior
+        /// runs without heartbeats until stopped, or until its client loses control.
+        BehaviorStartRequest(BehaviorConfig),
+
+        /// Request to stop running behavior and the rover.
+        BehaviorStopRequest,
+
+        /// Request to see which behavior is running.
+        BehaviorStatusRequest,
+
+        /// Response to the above, absent if none is.
+        BehaviorStatusResponse(Option<BehaviorConfig>),
+
+        /// Request to store given mission on the rover, replacing previous one unless it runs.
+        MissionUploadRequest(Mission),
+
+        /// Request to run uploaded mission from the start, or to resume it if paused. As with
+        /// behaviors, mission runs without heartbeats and is aborted by manual control.
+        MissionStartRequest,
+
+        /// Request to stop the rover and to hold running mission until started again.
+        MissionPauseRequest,
+
+        /// Request to abort running mission and to stop the rover.
+        MissionAbortRequest,
+
+        /// Request to see the progress of the latest mission run.
+        MissionProgressRequest,
+
+        /// Response to the above, absent if uploaded mission was never started.
+        MissionProgressResponse(Option<MissionProgress>),
+
+        /// Message sent by server on its own initiative, not in response to any request.
+        Notification(NotificationData),
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct ClientHelloData {
+        pub protocol_version: u16,
+        pub name: String,
+
+        /// Whether client accepts notifications.
+        pub notifications: bool,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct ServerHelloData {
+        pub protocol_version: u16,
+        pub name: String,
+        pub capabilities: CapabilitiesData,
+    }
+
+    /// Rover controls available through the server.
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct CapabilitiesData {
+        pub mover: bool,
+
+        /// Present if looker is available.
+        pub looker: Option<LookLimits>,
+
+        /// Present if sensor is available.
+        pub sensor: Option<SensorCapabilitiesData>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct SensorCapabilitiesData {
+        pub sensors: Vec<SensorDescriptor>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone)]
     pub struct LookData {
         pub(crate) x: i16,
         pub(crate) y: i16,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
     pub enum SenseRequestData {
         Obstacle,
         Line,
         Distance,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
     pub enum SenseResponseData {
-        Obstacle(Vec<bool>),
-        Line(Vec<bool>),
-        Distance(f32),
+        Obstacle(DetectionReading),
+        Line(DetectionReading),
+        Distance(DistanceReading),
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    impl SenseResponseData {
+        /// Whether both readings have the same values, regardless of when they were taken.
+        pub fn same_values(&self, other: &SenseResponseData) -> bool {
+            match (self, other) {
+                (SenseResponseData::Obstacle(a), SenseResponseData::Obstacle(b))
+                | (SenseResponseData::Line(a), SenseResponseData::Line(b)) => {
+                    a.detections == b.detections
+                }
+                (SenseResponseData::Distance(a), SenseResponseData::Distance(b)) => {
+                    a.distance == b.distance && a.valid == b.valid
+                }
+                _ => false,
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+    pub struct ControlStatusData {
+        /// Whether any client controls the rover.
+        pub controlled: bool,
+
+        /// Whether the requesting client controls the rover.
+        pub in_control: bool,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub enum NotificationData {
+        /// Rover has been stopped, with the reason.
+        MotionStopped(String),
+
+        /// Value of subscribed sensor.
+        Sense(SenseResponseData),
+
+        /// Running mission moved on to another step or changed status.
+        MissionProgress(MissionProgress),
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone)]
+    pub struct SubscriptionData {
+        /// Sensors to push values of.
+        pub sensors: Vec<SenseRequestData>,
+        pub policy: SubscriptionPolicy,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+    pub enum SubscriptionPolicy {
+        /// Push every sensor value read with given period, in ms.
+        Rate(u32),
+
+        /// Read sensors with given period, in ms, but push only values that differ from the
+        /// previously pushed ones.
+        OnChange(u32),
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone)]
     pub enum StatusResponseData {
         Success,
-        Error(String),
+        Error(ErrorKind, String),
+    }
+
+    /// Reason a request was refused, telling clients whether it is worth repeating.
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
+    pub enum ErrorKind {
+        /// Request is malformed or carries parameters that cannot be used.
+        Invalid,
+
+        /// Request does not fit current state, e.g. rover is controlled by another client.
+        Conflict,
+
+        /// Rover is not capable of requested operation.
+        Unsupported,
+
+        /// Rover failed to perform requested operation.
+        Failed,
     }
 }
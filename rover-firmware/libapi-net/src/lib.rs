@@ -1,10 +1,11 @@
 use thiserror::Error as LibError;
 
-use crate::contract::data::ProtocolMessage;
+use crate::contract::data::{ErrorKind, ProtocolMessage};
 
 pub mod client;
 pub mod contract;
 pub mod server;
+pub mod telemetry;
 
 #[derive(Debug, LibError)]
 pub enum Error {
@@ -14,6 +15,9 @@ pub enum Error {
     #[error("No connection to api-net server.")]
     Disconnected,
 
+    #[error("Incompatible api-net peer: {0}")]
+    Incompatible(String),
+
     #[error("IO error: {0:?}")]
     IO(#[from] std::io::Error),
 
@@ -23,8 +27,8 @@ pub enum Error {
     #[error("Serialization error: {0:?}")]
     Serialization(#[from] tokio_serde_cbor::Error),
 
-    #[error("Server error: {0:?}")]
-    Server(String),
+    #[error("Server error: {1:?}")]
+    Server(ErrorKind, String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
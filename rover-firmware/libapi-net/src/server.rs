@@ -1,13 +1,42 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::lock::Mutex;
 use futures::{SinkExt, StreamExt};
 use log::{debug, error, info, trace, warn};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
 use tokio_serde_cbor::Codec;
-use tokio_util::codec::Decoder;
+use tokio_util::codec::{Decoder, Framed};
 
-use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor, MoveType};
+use async_trait::async_trait;
+use libbehavior::runner::perform_move;
+use libbehavior::{BehaviorConfig, Command, Mission, MissionHandle, MissionRun, Rig};
+use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor, MoveType, RoverSnapshot};
 
+use crate::telemetry::{Direction, Recorder};
 use crate::{Error, Result};
-use crate::contract::data::{LookData, ProtocolMessage, SenseRequestData, SenseResponseData, StatusResponseData};
+use crate::contract::PROTOCOL_VERSION;
+use crate::contract::data::{
+    CapabilitiesData, ControlStatusData, Envelope, ErrorKind, LookData, NotificationData, ProtocolMessage, RequestId, SenseRequestData,
+    SenseResponseData, SensorCapabilitiesData, ServerHelloData, StatusResponseData,
+    SubscriptionData, SubscriptionPolicy,
+};
+
+type ConnectionId = u64;
+
+type ChannelType = Framed<TcpStream, Codec<Envelope, Envelope>>;
+
+const SERVER_NAME: &str = concat!("libapi-net/", env!("CARGO_PKG_VERSION"));
+
+// shortest period of watchdog checks
+const MIN_WATCHDOG_PERIOD: Duration = Duration::from_millis(10);
+
+// shortest period of sensor reads for subscriptions
+const MIN_SUBSCRIPTION_PERIOD: Duration = Duration::from_millis(20);
 
 pub struct Server<TMover, TLooker, TSensor>
 where
@@ -19,13 +48,15 @@ where
     mover: Option<TMover>,
     looker: Option<TLooker>,
     sensor: Option<TSensor>,
+    watchdog_timeout: Option<Duration>,
+    recorder: Option<Recorder>,
 }
 
 impl<TMover, TLooker, TSensor> Server<TMover, TLooker, TSensor>
 where
-    TMover: AsyncMover + Send,
-    TLooker: AsyncLooker + Send,
-    TSensor: AsyncSensor + Send,
+    TMover: AsyncMover + Send + 'static,
+    TLooker: AsyncLooker + Send + 'static,
+    TSensor: AsyncSensor + Send + 'static,
 {
     pub async fn new(listen_address: &str) -> Result<Server<TMover, TLooker, TSensor>> {
         info!("Launching api-net server on {}.", listen_address);
@@ -39,6 +70,8 @@ where
             mover: None,
             looker: None,
             sensor: None,
+            watchdog_timeout: None,
+            recorder: None,
         })
     }
 
@@ -54,22 +87,435 @@ where
         self.sensor = sensor;
     }
 
-    pub async fn serve(&mut self) -> Result<()> {
-        self.dispatch().await
+    /// Stops the rover if it keeps moving without move or heartbeat requests from controlling
+    /// client for longer than given timeout. Disabled if `None`.
+    pub fn set_watchdog_timeout(&mut self, timeout: Option<Duration>) {
+        self.watchdog_timeout = timeout;
+    }
+
+    /// Records protocol messages exchanged with clients. Disabled if `None`.
+    pub fn set_recorder(&mut self, recorder: Option<Recorder>) {
+        self.recorder = recorder;
+    }
+
+    pub async fn serve(self) -> Result<()> {
+        let rover =
+            Arc::new(Rover::new(self.mover, self.looker, self.sensor, self.recorder).await?);
+
+        if let Some(timeout) = self.watchdog_timeout {
+            info!("Starting motion watchdog with {:?} timeout.", timeout);
+
+            tokio::spawn(Self::watch(Arc::clone(&rover), timeout));
+        }
+
+        Self::dispatch(self.listener, rover).await
+    }
+
+    async fn watch(rover: Arc<Rover<TMover, TLooker, TSensor>>, timeout: Duration) {
+        let mover = match rover.mover {
+            Some(ref mover) => mover,
+            None => return,
+        };
+
+        let mut interval = tokio::time::interval((timeout / 4).max(MIN_WATCHDOG_PERIOD));
+
+        loop {
+            interval.tick().await;
+
+            if rover.last_command.lock().unwrap().elapsed() < timeout {
+                continue;
+            }
+
+            let mut mover = mover.lock().await;
+
+            // command could have arrived while waiting for the lock
+            if rover.last_command.lock().unwrap().elapsed() < timeout {
+                continue;
+            }
+
+            match mover.get_move_type().await {
+                Ok(MoveType::None) => {}
+                Ok(move_type) => {
+                    warn!(
+                        "No commands received for {:?} while moving ({:?}), stopping rover.",
+                        timeout, move_type
+                    );
+
+                    match mover.stop().await {
+                        Ok(_) => rover.notify_all(NotificationData::MotionStopped(format!(
+                            "No move or heartbeat requests received for {} ms.",
+                            timeout.as_millis()
+                        ))),
+                        Err(e) => error!("Watchdog failed to stop the rover: {}", e),
+                    }
+                }
+                Err(e) => error!("Watchdog failed to get rover move type: {}", e),
+            }
+        }
     }
 
-    async fn dispatch(&mut self) -> Result<()> {
+    async fn dispatch(
+        listener: TcpListener,
+        rover: Arc<Rover<TMover, TLooker, TSensor>>,
+    ) -> Result<()> {
         trace!("Starting dispatch loop.");
 
+        let mut last_connection_id: ConnectionId = 0;
+
         loop {
-            let (socket, _) = self.listener.accept().await?;
+            let (socket, _) = listener.accept().await?;
 
             trace!("New connection accepted.");
 
-            // single connection only
-            self.handle_connection(socket).await?;
+            last_connection_id += 1;
+            let session = Session::new(last_connection_id, Arc::clone(&rover));
+
+            tokio::spawn(async move {
+                let peer_address = session.peer_address(&socket);
+
+                if let Err(e) = session.handle_connection(socket).await {
+                    error!("[{}] Connection handling failed: {}", peer_address, e);
+                }
+            });
+
+            trace!("Connection handling started. Awaiting next.");
+        }
+    }
+}
+
+/// Rover controls shared between all client connections.
+struct Rover<TMover, TLooker, TSensor> {
+    mover: Option<Mutex<TMover>>,
+    looker: Option<Mutex<TLooker>>,
+    sensor: Option<Mutex<TSensor>>,
+
+    /// Connection that is allowed to move the rover and to look around.
+    controller: std::sync::Mutex<Option<ConnectionId>>,
+
+    /// Time of the last move or heartbeat request from controlling connection.
+    last_command: std::sync::Mutex<Instant>,
+
+    /// Queues of messages pushed to connected clients.
+    sessions: std::sync::Mutex<HashMap<ConnectionId, mpsc::UnboundedSender<ProtocolMessage>>>,
+
+    /// Task driving the rover on its own, with what it runs.
+    autopilot: std::sync::Mutex<Option<(Autopilot, JoinHandle<()>)>>,
+
+    /// Last uploaded mission, with the handle of its latest run.
+    mission: std::sync::Mutex<Option<(Mission, Option<MissionHandle>)>>,
+
+    recorder: Option<Recorder>,
+
+    capabilities: CapabilitiesData,
+}
+
+/// What drives the rover on its own.
+enum Autopilot {
+    Behavior(BehaviorConfig),
+    Mission(MissionHandle),
+}
+
+fn to_server_err<T: std::error::Error>(e: T) -> Error {
+    Error::Server(ErrorKind::Failed, e.to_string())
+}
+
+impl<TMover, TLooker, TSensor> Rover<TMover, TLooker, TSensor>
+where
+    TMover: AsyncMover + Send,
+    TLooker: AsyncLooker + Send,
+    TSensor: AsyncSensor + Send,
+{
+    async fn new(
+        mover: Option<TMover>,
+        looker: Option<TLooker>,
+        sensor: Option<TSensor>,
+        recorder: Option<Recorder>,
+    ) -> Result<Self> {
+        let mut rover = Rover {
+            mover: mover.map(Mutex::new),
+            looker: looker.map(Mutex::new),
+            sensor: sensor.map(Mutex::new),
+            controller: std::sync::Mutex::new(None),
+            last_command: std::sync::Mutex::new(Instant::now()),
+            sessions: std::sync::Mutex::new(HashMap::new()),
+            autopilot: std::sync::Mutex::new(None),
+            mission: std::sync::Mutex::new(None),
+            recorder,
+            capabilities: CapabilitiesData {
+                mover: false,
+                looker: None,
+                sensor: None,
+            },
+        };
+
+        trace!("Resetting rover controls.");
+
+        rover.reset().await?;
+
+        trace!("Detecting rover capabilities.");
+
+        rover.capabilities = rover.detect_capabilities().await?;
+
+        debug!("Rover capabilities: {:#?}", rover.capabilities);
+
+        Ok(rover)
+    }
+
+    async fn detect_capabilities(&self) -> Result<CapabilitiesData> {
+        let looker = match self.looker {
+            Some(ref looker) => {
+                Some(looker.lock().await.get_look_limits().await.map_err(to_server_err)?)
+            }
+            None => None,
+        };
+
+        let sensor = match self.sensor {
+            Some(ref sensor) => {
+                let sensor = sensor.lock().await;
+
+                Some(SensorCapabilitiesData {
+                    sensors: sensor.get_sensors().await.map_err(to_server_err)?,
+                })
+            }
+            None => None,
+        };
+
+        Ok(CapabilitiesData {
+            mover: self.mover.is_some(),
+            looker,
+            sensor,
+        })
+    }
+
+    async fn reset(&self) -> Result<()> {
+        self.stop_autopilot("Rover controls were reset.").await;
+
+        if let Some(ref mover) = self.mover {
+            mover.lock().await.reset().await.map_err(to_server_err)?;
+        }
+
+        if let Some(ref looker) = self.looker {
+            looker.lock().await.reset().await.map_err(to_server_err)?;
+        }
+
+        if let Some(ref sensor) = self.sensor {
+            sensor.lock().await.reset().await.map_err(to_server_err)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sensor snapshot completed with move and look directions if the sensor does not know them.
+    async fn snapshot(&self) -> std::result::Result<RoverSnapshot, String> {
+        let sensor = match self.sensor {
+            Some(ref sensor) => sensor,
+            None => return Err("Unsupported operation.".to_owned()),
+        };
+
+        let mut snapshot = sensor.lock().await.snapshot().await.map_err(|e| e.to_string())?;
+
+        if let (None, Some(mover)) = (snapshot.move_type, &self.mover) {
+            let move_type = mover.lock().await.get_move_type().await;
+            snapshot.move_type = Some(move_type.map_err(|e| e.to_string())?);
+        }
+
+        if let (None, Some(looker)) = (snapshot.look_direction, &self.looker) {
+            let look_direction = looker.lock().await.get_look_direction().await;
+            snapshot.look_direction = Some(look_direction.map_err(|e| e.to_string())?);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Interrupts running behavior or mission, if any, leaving the rover as is. Mission is
+    /// marked aborted for the given reason. Returns whether anything was running.
+    async fn stop_autopilot(&self, reason: &str) -> bool {
+        let autopilot = self.autopilot.lock().unwrap().take();
+
+        match autopilot {
+            Some((autopilot, task)) if !task.is_finished() => {
+                match autopilot {
+                    Autopilot::Behavior(config) => debug!("Stopping {:?} behavior.", config),
+                    Autopilot::Mission(handle) => {
+                        debug!("Aborting mission '{}'.", handle.progress().name);
+
+                        handle.abort(reason);
+                    }
+                }
+
+                task.abort();
+                // let the task go before anything else touches the rover
+                let _ = task.await;
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Stops the rover after its autopilot has been stopped.
+    async fn stop_motion(&self) -> std::result::Result<(), String> {
+        match self.mover {
+            Some(ref mover) => mover.lock().await.stop().await.map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    fn behavior_status(&self) -> Option<BehaviorConfig> {
+        match *self.autopilot.lock().unwrap() {
+            Some((Autopilot::Behavior(ref config), ref task)) if !task.is_finished() => {
+                Some(config.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle of the running mission, if it is the one driving the rover.
+    fn running_mission(&self) -> Option<MissionHandle> {
+        match *self.autopilot.lock().unwrap() {
+            Some((Autopilot::Mission(ref handle), ref task)) if !task.is_finished() => {
+                Some(handle.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Grants control to the given connection if nobody else has it.
+    fn acquire_control(&self, id: ConnectionId) -> bool {
+        let mut controller = self.controller.lock().unwrap();
+
+        match *controller {
+            Some(controller_id) => controller_id == id,
+            None => {
+                *controller = Some(id);
+                true
+            }
+        }
+    }
+
+    /// Takes control from the given connection, if it has it.
+    fn release_control(&self, id: ConnectionId) -> bool {
+        let mut controller = self.controller.lock().unwrap();
+
+        if *controller == Some(id) {
+            *controller = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Grants control to the given connection unconditionally, returns previous controller.
+    fn take_over_control(&self, id: ConnectionId) -> Option<ConnectionId> {
+        self.controller.lock().unwrap().replace(id)
+    }
+
+    fn keep_alive(&self) {
+        *self.last_command.lock().unwrap() = Instant::now();
+    }
+
+    fn notify(&self, id: ConnectionId, notification: NotificationData) {
+        if let Some(outbox) = self.sessions.lock().unwrap().get(&id) {
+            let _ = outbox.send(ProtocolMessage::Notification(notification));
+        }
+    }
+
+    fn notify_all(&self, notification: NotificationData) {
+        for outbox in self.sessions.lock().unwrap().values() {
+            // closed queue means connection is terminating, nothing to notify
+            let _ = outbox.send(ProtocolMessage::Notification(notification.clone()));
+        }
+    }
+
+    fn control_status(&self, id: ConnectionId) -> ControlStatusData {
+        let controller = self.controller.lock().unwrap();
+
+        ControlStatusData {
+            controlled: controller.is_some(),
+            in_control: *controller == Some(id),
+        }
+    }
+}
+
+/// Rover controls as seen by a running behavior.
+struct RoverRig<TMover, TLooker, TSensor>(Arc<Rover<TMover, TLooker, TSensor>>);
+
+#[async_trait]
+impl<TMover, TLooker, TSensor> Rig for RoverRig<TMover, TLooker, TSensor>
+where
+    TMover: AsyncMover + Send + 'static,
+    TLooker: AsyncLooker + Send + 'static,
+    TSensor: AsyncSensor + Send + 'static,
+{
+    async fn snapshot(&mut self) -> libbehavior::Result<RoverSnapshot> {
+        // behavior stands in for the controlling client, which does not need to send heartbeats
+        self.0.keep_alive();
+
+        self.0.snapshot().await.map_err(libbehavior::Error::Rover)
+    }
+
+    async fn perform(&mut self, command: Command) -> libbehavior::Result<()> {
+        let result = match command {
+            Command::Move(move_type) => match self.0.mover {
+                Some(ref mover) => perform_move(&mut *mover.lock().await, move_type)
+                    .await
+                    .map_err(|e| e.to_string()),
+                None => Err("Unsupported operation.".to_owned()),
+            },
+            Command::LookAt(h, v) => match self.0.looker {
+                Some(ref looker) => looker.lock().await.look_at(h, v).await.map_err(|e| e.to_string()),
+                None => Err("Unsupported operation.".to_owned()),
+            },
+        };
+
+        result.map_err(libbehavior::Error::Rover)
+    }
+}
+
+/// Single client connection.
+struct Session<TMover, TLooker, TSensor> {
+    id: ConnectionId,
+    rover: Arc<Rover<TMover, TLooker, TSensor>>,
+
+    /// Queue of messages pushed to the client.
+    outbox: mpsc::UnboundedSender<ProtocolMessage>,
+    inbox: Option<mpsc::UnboundedReceiver<ProtocolMessage>>,
+
+    /// Task pushing subscribed sensor values.
+    subscription: std::sync::Mutex<Option<JoinHandle<()>>>,
+
+    /// Whether client accepts notifications, as told in handshake.
+    notifications: bool,
+}
+
+impl<TMover, TLooker, TSensor> Session<TMover, TLooker, TSensor>
+where
+    TMover: AsyncMover + Send + 'static,
+    TLooker: AsyncLooker + Send + 'static,
+    TSensor: AsyncSensor + Send + 'static,
+{
+    fn new(id: ConnectionId, rover: Arc<Rover<TMover, TLooker, TSensor>>) -> Self {
+        let (outbox, inbox) = mpsc::unbounded_channel();
+
+        Session {
+            id,
+            rover,
+            outbox,
+            inbox: Some(inbox),
+            subscription: std::sync::Mutex::new(None),
+            notifications: false,
+        }
+    }
+
+    fn peer_address(&self, socket: &TcpStream) -> String {
+        socket
+            .peer_addr()
+            .map_or("unknown address".to_owned(), |addr| addr.to_string())
+    }
 
-            trace!("Connection handling finished. Awaiting next.");
+    fn record(&self, direction: Direction, id: Option<RequestId>, message: &ProtocolMessage) {
+        if let Some(ref recorder) = self.rover.recorder {
+            recorder.record_message(self.id, direction, id, message);
         }
     }
 
@@ -79,211 +525,1086 @@ where
     {
         ProtocolMessage::StatusResponse(match r {
             Ok(_) => StatusResponseData::Success,
-            Err(e) => StatusResponseData::Error(e.to_string()),
+            Err(e) => StatusResponseData::Error(ErrorKind::Failed, e.to_string()),
+        })
+    }
+
+    fn error_response(kind: ErrorKind, message: &str) -> ProtocolMessage {
+        ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, message.to_owned()))
+    }
+
+    async fn sense(
+        sensor: &mut TSensor,
+        what: SenseRequestData,
+    ) -> std::result::Result<SenseResponseData, TSensor::Error> {
+        Ok(match what {
+            SenseRequestData::Distance => SenseResponseData::Distance(sensor.scan_distance().await?),
+            SenseRequestData::Line => SenseResponseData::Line(sensor.get_lines().await?),
+            SenseRequestData::Obstacle => SenseResponseData::Obstacle(sensor.get_obstacles().await?),
         })
     }
 
-    async fn reset(&mut self) -> Result<()> {
-        fn to_server_err<T: std::error::Error>(e: T) -> Error {
-            Error::Server(e.to_string())
+    async fn start_behavior(&self, behavior: BehaviorConfig) {
+        self.rover.stop_autopilot("Replaced by behavior.").await;
+
+        let task = tokio::spawn(Self::run_behavior(Arc::clone(&self.rover), behavior.clone()));
+
+        *self.rover.autopilot.lock().unwrap() = Some((Autopilot::Behavior(behavior), task));
+    }
+
+    async fn run_behavior(rover: Arc<Rover<TMover, TLooker, TSensor>>, config: BehaviorConfig) {
+        let mut behavior = config.build();
+        let mut rig = RoverRig(Arc::clone(&rover));
+
+        let reason = match libbehavior::run(&mut *behavior, &mut rig).await {
+            Ok(_) => format!("Behavior '{}' finished.", behavior.name()),
+            Err(e) => format!("Behavior '{}' failed: {}", behavior.name(), e),
+        };
+
+        rover.notify_all(NotificationData::MotionStopped(reason));
+    }
+
+    /// Starts uploaded mission from its first step. Fails if there is none.
+    async fn start_mission(&self) -> std::result::Result<(), String> {
+        let mission = match *self.rover.mission.lock().unwrap() {
+            Some((ref mission, _)) => mission.clone(),
+            None => return Err("No mission uploaded.".to_owned()),
+        };
+
+        self.rover.stop_autopilot("Restarted.").await;
+
+        let (run, handle) = MissionRun::new(mission);
+        let task = tokio::spawn(Self::run_mission(Arc::clone(&self.rover), run, handle.clone()));
+
+        if let Some((_, ref mut last_run)) = *self.rover.mission.lock().unwrap() {
+            *last_run = Some(handle.clone());
+        }
+
+        *self.rover.autopilot.lock().unwrap() = Some((Autopilot::Mission(handle), task));
+
+        Ok(())
+    }
+
+    async fn run_mission(
+        rover: Arc<Rover<TMover, TLooker, TSensor>>,
+        mut run: MissionRun,
+        handle: MissionHandle,
+    ) {
+        let mut rig = RoverRig(Arc::clone(&rover));
+        let mut progress = handle.watch();
+
+        let running = libbehavior::run(&mut run, &mut rig);
+        tokio::pin!(running);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut running => break result,
+                Ok(_) = progress.changed() => {
+                    let update = progress.borrow_and_update().clone();
+
+                    rover.notify_all(NotificationData::MissionProgress(update));
+                }
+            }
+        };
+
+        if let Err(ref e) = result {
+            // rover failures are not reflected in progress by the run
+            handle.abort(&e.to_string());
         }
 
-        if let Some(ref mut mover) = self.mover {
-            mover.reset().await.map_err(to_server_err)?;
+        let progress = handle.progress();
+
+        rover.notify_all(NotificationData::MissionProgress(progress.clone()));
+
+        let reason = match result {
+            Ok(_) => format!("Mission '{}' is over: {:?}", progress.name, progress.status),
+            Err(e) => format!("Mission '{}' failed: {}", progress.name, e),
+        };
+
+        rover.notify_all(NotificationData::MotionStopped(reason));
+    }
+
+    fn subscribe(&self, subscription: SubscriptionData) {
+        let task = tokio::spawn(Self::push_sensor_values(
+            Arc::clone(&self.rover),
+            self.outbox.clone(),
+            subscription,
+        ));
+
+        if let Some(previous_task) = self.subscription.lock().unwrap().replace(task) {
+            previous_task.abort();
         }
+    }
 
-        if let Some(ref mut looker) = self.looker {
-            looker.reset().await.map_err(to_server_err)?;
+    fn unsubscribe(&self) {
+        if let Some(task) = self.subscription.lock().unwrap().take() {
+            task.abort();
         }
+    }
+
+    async fn push_sensor_values(
+        rover: Arc<Rover<TMover, TLooker, TSensor>>,
+        outbox: mpsc::UnboundedSender<ProtocolMessage>,
+        subscription: SubscriptionData,
+    ) {
+        let sensor = match rover.sensor {
+            Some(ref sensor) => sensor,
+            None => return,
+        };
+
+        let (period, on_change) = match subscription.policy {
+            SubscriptionPolicy::Rate(period) => (period, false),
+            SubscriptionPolicy::OnChange(period) => (period, true),
+        };
 
-        if let Some(ref mut sensor) = self.sensor {
-            sensor.reset().await.map_err(to_server_err)?;
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(period as u64).max(MIN_SUBSCRIPTION_PERIOD));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut last_values: Vec<Option<SenseResponseData>> = vec![None; subscription.sensors.len()];
+
+        loop {
+            interval.tick().await;
+
+            let mut sensor = sensor.lock().await;
+
+            for (what, last_value) in subscription.sensors.iter().zip(last_values.iter_mut()) {
+                match Self::sense(&mut sensor, *what).await {
+                    Ok(value) => {
+                        if on_change && last_value.as_ref().is_some_and(|last| last.same_values(&value)) {
+                            continue;
+                        }
+
+                        *last_value = Some(value.clone());
+
+                        let notification = ProtocolMessage::Notification(NotificationData::Sense(value));
+                        if outbox.send(notification).is_err() {
+                            // connection is gone
+                            return;
+                        }
+                    }
+                    Err(e) => warn!("Failed to read subscribed {:?} sensor: {}", what, e),
+                }
+            }
         }
+    }
 
-        Ok(())
+    /// Checks that client speaks the same protocol version and introduces the server to it.
+    /// Returns whether client can proceed.
+    async fn handshake(&mut self, peer_address: &str, channel: &mut ChannelType) -> Result<bool> {
+        let Envelope { id, message } = match channel.next().await {
+            Some(envelope) => envelope?,
+            None => return Ok(false),
+        };
+
+        self.record(Direction::Received, id, &message);
+
+        let (response, accepted) = match message {
+            ProtocolMessage::HelloRequest(hello) if hello.protocol_version == PROTOCOL_VERSION => {
+                info!("[{}] Client '{}' connected.", peer_address, hello.name);
+
+                self.notifications = hello.notifications;
+
+                let hello = ServerHelloData {
+                    protocol_version: PROTOCOL_VERSION,
+                    name: SERVER_NAME.to_owned(),
+                    capabilities: self.rover.capabilities.clone(),
+                };
+
+                (ProtocolMessage::HelloResponse(hello), true)
+            }
+            ProtocolMessage::HelloRequest(hello) => {
+                warn!(
+                    "[{}] Client '{}' speaks unsupported protocol version {}.",
+                    peer_address, hello.name, hello.protocol_version
+                );
+
+                let error = format!(
+                    "Unsupported protocol version {}, server speaks {}.",
+                    hello.protocol_version, PROTOCOL_VERSION
+                );
+
+                (Self::error_response(ErrorKind::Unsupported, &error), false)
+            }
+            message => {
+                warn!("[{}] Expected handshake, received: {:#?}", peer_address, message);
+
+                (Self::error_response(ErrorKind::Invalid, "Handshake expected."), false)
+            }
+        };
+
+        self.record(Direction::Sent, id, &response);
+
+        channel.send(Envelope { id, message: response }).await?;
+
+        Ok(accepted)
     }
 
-    async fn handle_connection(&mut self, socket: TcpStream) -> Result<()> {
-        let peer_address = socket
-            .peer_addr()
-            .map_or("unknown address".to_owned(), |addr| addr.to_string());
+    async fn handle_connection(mut self, socket: TcpStream) -> Result<()> {
+        let peer_address = self.peer_address(&socket);
 
         debug!("[{}] New connection received.", peer_address);
 
-        let codec: Codec<ProtocolMessage, ProtocolMessage> = Codec::new();
+        let codec: Codec<Envelope, Envelope> = Codec::new();
         let mut channel = codec.framed(socket);
 
-        trace!("Resetting rover controls.");
+        if !self.handshake(&peer_address, &mut channel).await? {
+            debug!("[{}] Handshake failed, connection terminated.", peer_address);
 
-        self.reset().await?;
+            return Ok(());
+        }
+
+        let mut inbox = self.inbox.take().expect("Session handles single connection");
+
+        if self.notifications {
+            self.rover.sessions.lock().unwrap().insert(self.id, self.outbox.clone());
+        }
 
         trace!("Ready to process protocol messages.");
 
-        while let Some(response) = channel.next().await {
-            match response {
-                Ok(message) => {
-                    match &message {
-                        ProtocolMessage::MoveRequest(move_type) => {
-                            trace!("[{}] Processing move request: {:#?}", peer_address, move_type);
-
-                            if let Some(ref mut mover) = self.mover {
-                                let opresult = match move_type {
-                                    MoveType::Forward(ref speed) => mover.move_forward(*speed).await,
-                                    MoveType::Backward(ref speed) => mover.move_backward(*speed).await,
-                                    MoveType::SpinCW(ref speed) => mover.spin_right(*speed).await,
-                                    MoveType::SpinCCW(ref speed) => mover.spin_left(*speed).await,
-                                    MoveType::None => mover.stop().await
-                                };
-
-                                channel
-                                    .send(Self::map_result_to_status_response(opresult))
-                                    .await?;
-                            } else {
-                                warn!("[{}] Requested operation is not implemented.", peer_address);
-
-                                channel
-                                    .send(ProtocolMessage::StatusResponse(StatusResponseData::Error(
-                                        "Unsupported operation.".to_owned(),
-                                    )))
-                                    .await?;
-                            }
-                        }
-                        ProtocolMessage::MoveDirectionRequest => {
-                            trace!("[{}] Processing move direction request", peer_address);
-
-                            if let Some(ref mut mover) = self.mover {
-                                let response = match mover.get_move_type().await {
-                                    Ok(move_type) => ProtocolMessage::MoveDirectionResponse(move_type),
-                                    Err(e) => ProtocolMessage::StatusResponse(StatusResponseData::Error(e.to_string()))
-                                };
-
-                                channel
-                                    .send(response)
-                                    .await?;
-                            } else {
-                                warn!("[{}] Requested operation is not implemented.", peer_address);
-
-                                channel
-                                    .send(ProtocolMessage::StatusResponse(StatusResponseData::Error(
-                                        "Unsupported operation.".to_owned(),
-                                    )))
-                                    .await?
-                            }
-                        }
-                        ProtocolMessage::LookRequest(r) => {
-                            trace!("[{}] Processing look request: {:#?}", peer_address, r);
-
-                            if let Some(ref mut looker) = self.looker {
-                                let opresult = looker.look_at(r.x, r.y).await;
-
-                                channel
-                                    .send(Self::map_result_to_status_response(opresult))
-                                    .await?;
-                            } else {
-                                warn!("[{}] Requested operation is not implemented.", peer_address);
-
-                                channel
-                                    .send(ProtocolMessage::StatusResponse(StatusResponseData::Error(
-                                        "Unsupported operation.".to_owned(),
-                                    )))
-                                    .await?
-                            }
-                        }
-                        ProtocolMessage::LookDirectionRequest => {
-                            trace!(
-                                "[{}] Processing look direction request.",
-                                peer_address
-                            );
-
-                            if let Some(ref mut looker) = self.looker {
-                                let response = match looker.get_look_direction().await {
-                                    Ok((h, v)) => ProtocolMessage::LookDirectionResponse(LookData { x: h, y: v }),
-                                    Err(e) => ProtocolMessage::StatusResponse(StatusResponseData::Error(e.to_string()))
-                                };
-
-                                channel
-                                    .send(response)
-                                    .await?;
-                            } else {
-                                warn!("[{}] Requested operation is not implemented.", peer_address);
-
-                                channel
-                                    .send(ProtocolMessage::StatusResponse(StatusResponseData::Error(
-                                        "Unsupported operation.".to_owned(),
-                                    )))
-                                    .await?
-                            }
-                        }
-                        ProtocolMessage::SenseRequest(r) => {
-                            trace!("[{}] Processing sense request: {:#?}", peer_address, r);
-
-                            if let Some(ref mut sensor) = self.sensor {
-                                match r {
-                                    SenseRequestData::Distance => {
-                                        let response = match sensor.scan_distance().await {
-                                            Ok(distance) => ProtocolMessage::SenseResponse(SenseResponseData::Distance(distance)),
-                                            Err(e) => ProtocolMessage::StatusResponse(StatusResponseData::Error(e.to_string())),
-                                        };
-
-                                        channel
-                                            .send(response)
-                                            .await?;
-                                    }
-                                    SenseRequestData::Line => {
-                                        let response = match sensor.get_lines().await {
-                                            Ok(line_states) => ProtocolMessage::SenseResponse(SenseResponseData::Line(line_states)),
-                                            Err(e) => ProtocolMessage::StatusResponse(StatusResponseData::Error(e.to_string())),
-                                        };
-
-                                        channel
-                                            .send(response)
-                                            .await?;
-                                    }
-                                    SenseRequestData::Obstacle => {
-                                        let response = match sensor.get_obstacles().await {
-                                            Ok(obstacle_states) =>
-                                                ProtocolMessage::SenseResponse(SenseResponseData::Obstacle(obstacle_states)),
-                                            Err(e) => ProtocolMessage::StatusResponse(StatusResponseData::Error(e.to_string())),
-                                        };
-
-                                        channel
-                                            .send(response)
-                                            .await?;
-                                    }
-                                }
-                            } else {
-                                warn!("[{}] Requested operation is not implemented.", peer_address);
-
-                                channel
-                                    .send(ProtocolMessage::StatusResponse(StatusResponseData::Error(
-                                        "Unsupported operation.".to_owned(),
-                                    )))
-                                    .await?
-                            }
+        let mut result = Ok(());
+
+        loop {
+            tokio::select! {
+                request = channel.next() => match request {
+                    Some(Ok(Envelope { id, message })) => {
+                        self.record(Direction::Received, id, &message);
+
+                        let response = Envelope {
+                            id,
+                            message: self.process(&peer_address, &message).await,
+                        };
+
+                        self.record(Direction::Sent, id, &response.message);
+
+                        if let Err(e) = channel.send(response).await {
+                            result = Err(e.into());
+                            break;
                         }
 
-                        _ => warn!(
-                            "[{}] Received unsupported request type: {:#?}",
-                            peer_address, message
-                        ),
+                        debug!(
+                            "[{}] Successfully processed message #{:?}: {:#?}",
+                            peer_address, id, message
+                        );
                     }
+                    Some(Err(e)) => {
+                        error!("[{}] Failed to receive message: {}", peer_address, e);
+                        result = Err(e.into());
+                        break;
+                    }
+                    None => break,
+                },
+                Some(notification) = inbox.recv() => {
+                    trace!("[{}] Sending notification: {:#?}", peer_address, notification);
 
-                    debug!(
-                        "[{}] Successfully processed message: {:#?}",
-                        peer_address, message
-                    );
-                }
-                Err(e) => {
-                    error!("[{}] Failed to receive message: {}", peer_address, e);
-                    return Err(e.into());
+                    self.record(Direction::Sent, None, &notification);
+
+                    let notification = Envelope { id: None, message: notification };
+
+                    if let Err(e) = channel.send(notification).await {
+                        result = Err(e.into());
+                        break;
+                    }
                 }
             }
         }
 
-        self.reset().await?;
+        self.unsubscribe();
+        self.rover.sessions.lock().unwrap().remove(&self.id);
+
+        if self.rover.release_control(self.id) {
+            debug!("[{}] Controlling client disconnected, resetting rover controls.", peer_address);
+
+            self.rover.reset().await?;
+        }
 
         debug!("[{}] Connection terminated.", peer_address);
 
-        Ok(())
+        result
+    }
+
+    async fn process(&self, peer_address: &str, message: &ProtocolMessage) -> ProtocolMessage {
+        match message {
+            ProtocolMessage::MoveRequest(move_type) => {
+                trace!("[{}] Processing move request: {:#?}", peer_address, move_type);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if let Some(ref mover) = self.rover.mover {
+                    // manual control overrides behaviors and missions
+                    if self.rover.stop_autopilot("Interrupted by move request.").await {
+                        info!("[{}] Autopilot interrupted by move request.", peer_address);
+                    }
+
+                    let mut mover = mover.lock().await;
+                    self.rover.keep_alive();
+
+                    let opresult = perform_move(&mut *mover, *move_type).await;
+
+                    Self::map_result_to_status_response(opresult)
+                } else {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                }
+            }
+            ProtocolMessage::MoveDirectionRequest => {
+                trace!("[{}] Processing move direction request", peer_address);
+
+                if let Some(ref mover) = self.rover.mover {
+                    match mover.lock().await.get_move_type().await {
+                        Ok(move_type) => ProtocolMessage::MoveDirectionResponse(move_type),
+                        Err(e) => Self::error_response(ErrorKind::Failed, &e.to_string())
+                    }
+                } else {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                }
+            }
+            ProtocolMessage::LookRequest(r) => {
+                trace!("[{}] Processing look request: {:#?}", peer_address, r);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if let Some(ref looker) = self.rover.looker {
+                    let opresult = looker.lock().await.look_at(r.x, r.y).await;
+
+                    Self::map_result_to_status_response(opresult)
+                } else {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                }
+            }
+            ProtocolMessage::LookDirectionRequest => {
+                trace!(
+                    "[{}] Processing look direction request.",
+                    peer_address
+                );
+
+                if let Some(ref looker) = self.rover.looker {
+                    match looker.lock().await.get_look_direction().await {
+                        Ok((h, v)) => ProtocolMessage::LookDirectionResponse(LookData { x: h, y: v }),
+                        Err(e) => Self::error_response(ErrorKind::Failed, &e.to_string())
+                    }
+                } else {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                }
+            }
+            ProtocolMessage::SenseRequest(r) => {
+                trace!("[{}] Processing sense request: {:#?}", peer_address, r);
+
+                if let Some(ref sensor) = self.rover.sensor {
+                    match Self::sense(&mut *sensor.lock().await, *r).await {
+                        Ok(value) => ProtocolMessage::SenseResponse(value),
+                        Err(e) => Self::error_response(ErrorKind::Failed, &e.to_string()),
+                    }
+                } else {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                }
+            }
+            ProtocolMessage::SnapshotRequest => {
+                trace!("[{}] Processing snapshot request.", peer_address);
+
+                if self.rover.sensor.is_some() {
+                    match self.rover.snapshot().await {
+                        Ok(snapshot) => ProtocolMessage::SnapshotResponse(snapshot),
+                        Err(e) => Self::error_response(ErrorKind::Failed, &e),
+                    }
+                } else {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                }
+            }
+            ProtocolMessage::ControlRequest => {
+                trace!("[{}] Processing control request.", peer_address);
+
+                if self.rover.acquire_control(self.id) {
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                } else {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                }
+            }
+            ProtocolMessage::ControlReleaseRequest => {
+                trace!("[{}] Processing control release request.", peer_address);
+
+                if self.rover.release_control(self.id) {
+                    Self::map_result_to_status_response(self.rover.reset().await)
+                } else {
+                    warn!("[{}] Client does not control the rover.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Client does not control the rover.")
+                }
+            }
+            ProtocolMessage::ControlTakeoverRequest => {
+                trace!("[{}] Processing control takeover request.", peer_address);
+
+                match self.rover.take_over_control(self.id) {
+                    Some(previous_id) if previous_id != self.id => {
+                        info!("[{}] Client took over rover control.", peer_address);
+
+                        let opresult = self.rover.reset().await;
+
+                        self.rover.notify(
+                            previous_id,
+                            NotificationData::MotionStopped(
+                                "Rover control was taken over by another client.".to_owned(),
+                            ),
+                        );
+
+                        Self::map_result_to_status_response(opresult)
+                    }
+                    _ => ProtocolMessage::StatusResponse(StatusResponseData::Success),
+                }
+            }
+            ProtocolMessage::HeartbeatRequest => {
+                trace!("[{}] Processing heartbeat request.", peer_address);
+
+                if self.rover.control_status(self.id).in_control {
+                    self.rover.keep_alive();
+                }
+
+                ProtocolMessage::StatusResponse(StatusResponseData::Success)
+            }
+            ProtocolMessage::SubscribeRequest(subscription) => {
+                trace!("[{}] Processing subscribe request: {:#?}", peer_address, subscription);
+
+                if self.rover.sensor.is_none() {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                } else if !self.notifications {
+                    warn!("[{}] Client does not accept notifications.", peer_address);
+
+                    Self::error_response(ErrorKind::Invalid, "Client does not accept notifications.")
+                } else if subscription.sensors.is_empty() {
+                    warn!("[{}] No sensors to subscribe to.", peer_address);
+
+                    Self::error_response(ErrorKind::Invalid, "No sensors to subscribe to.")
+                } else {
+                    self.subscribe(subscription.clone());
+
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                }
+            }
+            ProtocolMessage::UnsubscribeRequest => {
+                trace!("[{}] Processing unsubscribe request.", peer_address);
+
+                self.unsubscribe();
+
+                ProtocolMessage::StatusResponse(StatusResponseData::Success)
+            }
+            ProtocolMessage::BehaviorStartRequest(behavior) => {
+                trace!("[{}] Processing behavior start request: {:#?}", peer_address, behavior);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if self.rover.mover.is_none() || self.rover.sensor.is_none() {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                } else if let Err(e) = behavior.validate() {
+                    warn!("[{}] Behavior is invalid: {}", peer_address, e);
+
+                    Self::error_response(ErrorKind::Invalid, &e.to_string())
+                } else {
+                    self.start_behavior(behavior.clone()).await;
+
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                }
+            }
+            ProtocolMessage::BehaviorStopRequest => {
+                trace!("[{}] Processing behavior stop request.", peer_address);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if self.rover.behavior_status().is_none() {
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                } else {
+                    self.rover.stop_autopilot("Stopped by behavior stop request.").await;
+
+                    Self::map_result_to_status_response(self.rover.stop_motion().await)
+                }
+            }
+            ProtocolMessage::BehaviorStatusRequest => {
+                trace!("[{}] Processing behavior status request.", peer_address);
+
+                ProtocolMessage::BehaviorStatusResponse(self.rover.behavior_status())
+            }
+            ProtocolMessage::MissionUploadRequest(mission) => {
+                trace!("[{}] Processing mission upload request: {:#?}", peer_address, mission);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if self.rover.running_mission().is_some() {
+                    warn!("[{}] Mission is running.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Mission is running, abort it first.")
+                } else if let Err(e) = mission.validate() {
+                    warn!("[{}] Mission is invalid: {}", peer_address, e);
+
+                    Self::error_response(ErrorKind::Invalid, &e.to_string())
+                } else {
+                    *self.rover.mission.lock().unwrap() = Some((mission.clone(), None));
+
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                }
+            }
+            ProtocolMessage::MissionStartRequest => {
+                trace!("[{}] Processing mission start request.", peer_address);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if self.rover.mover.is_none() || self.rover.sensor.is_none() {
+                    warn!("[{}] Requested operation is not implemented.", peer_address);
+
+                    Self::error_response(ErrorKind::Unsupported, "Unsupported operation.")
+                } else if self.rover.running_mission().is_some_and(|mission| mission.resume()) {
+                    info!("[{}] Mission resumed.", peer_address);
+
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                } else if let Err(e) = self.start_mission().await {
+                    warn!("[{}] {}", peer_address, e);
+
+                    Self::error_response(ErrorKind::Conflict, &e)
+                } else {
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                }
+            }
+            ProtocolMessage::MissionPauseRequest => {
+                trace!("[{}] Processing mission pause request.", peer_address);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if self.rover.running_mission().is_some_and(|mission| mission.pause()) {
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                } else {
+                    Self::error_response(ErrorKind::Conflict, "No mission is running.")
+                }
+            }
+            ProtocolMessage::MissionAbortRequest => {
+                trace!("[{}] Processing mission abort request.", peer_address);
+
+                if !self.rover.acquire_control(self.id) {
+                    warn!("[{}] Rover is controlled by another client.", peer_address);
+
+                    Self::error_response(ErrorKind::Conflict, "Rover is controlled by another client.")
+                } else if self.rover.running_mission().is_none() {
+                    ProtocolMessage::StatusResponse(StatusResponseData::Success)
+                } else {
+                    self.rover.stop_autopilot("Aborted by client.").await;
+
+                    let opresult = self.rover.stop_motion().await;
+
+                    if opresult.is_ok() {
+                        self.rover.notify_all(NotificationData::MotionStopped(
+                            "Mission aborted.".to_owned(),
+                        ));
+                    }
+
+                    Self::map_result_to_status_response(opresult)
+                }
+            }
+            ProtocolMessage::MissionProgressRequest => {
+                trace!("[{}] Processing mission progress request.", peer_address);
+
+                let progress = match *self.rover.mission.lock().unwrap() {
+                    Some((_, Some(ref handle))) => Some(handle.progress()),
+                    _ => None,
+                };
+
+                ProtocolMessage::MissionProgressResponse(progress)
+            }
+            ProtocolMessage::ControlStatusRequest => {
+                trace!("[{}] Processing control status request.", peer_address);
+
+                ProtocolMessage::ControlStatusResponse(self.rover.control_status(self.id))
+            }
+
+            _ => {
+                warn!(
+                    "[{}] Received unsupported request type: {:#?}",
+                    peer_address, message
+                );
+
+                Self::error_response(ErrorKind::Unsupported, "Unsupported request.")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use libbehavior::{BehaviorHost, WanderConfig};
+    use libdriver::util::a_sync::AsyncRover;
+    use libdriver_sim::{SimRover, World};
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::client::Client;
+    use crate::contract::data::ClientHelloData;
+
+    type SimServer = Server<AsyncRover<SimRover>, AsyncRover<SimRover>, AsyncRover<SimRover>>;
+
+    const WAIT: Duration = Duration::from_secs(2);
+
+    async fn start(watchdog_timeout: Option<Duration>) -> SocketAddr {
+        let rover = AsyncRover::from(SimRover::new(World::default()));
+
+        let mut server: SimServer = Server::new("127.0.0.1:0").await.unwrap();
+        let address = server.listener.local_addr().unwrap();
+
+        server.register_mover(Some(rover.clone()));
+        server.register_looker(Some(rover.clone()));
+        server.register_sensor(Some(rover));
+        server.set_watchdog_timeout(watchdog_timeout);
+
+        tokio::spawn(server.serve());
+
+        address
+    }
+
+    async fn next_notification(
+        notifications: &mut broadcast::Receiver<NotificationData>,
+    ) -> NotificationData {
+        tokio::time::timeout(WAIT, notifications.recv())
+            .await
+            .expect("Notification expected")
+            .unwrap()
+    }
+
+    fn assert_conflict<T: std::fmt::Debug>(result: Result<T>) {
+        match result {
+            Err(Error::Server(ErrorKind::Conflict, _)) => {}
+            other => panic!("Conflict expected, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn second_client_is_refused_control() {
+        let address = start(None).await;
+        let mut first = Client::with_name(address, "first").await.unwrap();
+        let mut second = Client::with_name(address, "second").await.unwrap();
+
+        first.move_forward(100).await.unwrap();
+
+        assert_conflict(second.move_forward(100).await);
+        assert_conflict(second.request_control().await);
+        assert_eq!(
+            second.get_move_type().await.unwrap(),
+            MoveType::Forward(100)
+        );
+
+        let status = second.get_control_status().await.unwrap();
+        assert!(status.controlled);
+        assert!(!status.in_control);
+    }
+
+    #[tokio::test]
+    async fn release_hands_control_over() {
+        let address = start(None).await;
+        let mut first = Client::with_name(address, "first").await.unwrap();
+        let mut second = Client::with_name(address, "second").await.unwrap();
+
+        first.move_forward(100).await.unwrap();
+        first.release_control().await.unwrap();
+
+        // releasing control stops the rover
+        assert_eq!(first.get_move_type().await.unwrap(), MoveType::None);
+        assert_conflict(first.release_control().await);
+
+        second.move_forward(100).await.unwrap();
+        assert!(second.get_control_status().await.unwrap().in_control);
+        assert_conflict(first.request_control().await);
+    }
+
+    #[tokio::test]
+    async fn takeover_notifies_previous_controller() {
+        let address = start(None).await;
+        let mut first = Client::with_name(address, "first").await.unwrap();
+        let second = Client::with_name(address, "second").await.unwrap();
+        let mut notifications = first.notifications();
+
+        first.move_forward(100).await.unwrap();
+        second.take_control().await.unwrap();
+
+        match next_notification(&mut notifications).await {
+            NotificationData::MotionStopped(reason) => assert!(reason.contains("taken over")),
+            other => panic!("Motion stop expected, got {:?}", other),
+        }
+
+        assert_eq!(first.get_move_type().await.unwrap(), MoveType::None);
+        assert!(!first.get_control_status().await.unwrap().in_control);
+        assert!(second.get_control_status().await.unwrap().in_control);
+        assert_conflict(first.move_forward(100).await);
+    }
+
+    #[tokio::test]
+    async fn disconnect_frees_control() {
+        let address = start(None).await;
+        let mut first = Client::with_name(address, "first").await.unwrap();
+        let second = Client::with_name(address, "second").await.unwrap();
+
+        first.move_forward(100).await.unwrap();
+        drop(first);
+
+        tokio::time::timeout(WAIT, async {
+            while second.get_control_status().await.unwrap().controlled {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Control should be freed");
+
+        second.request_control().await.unwrap();
+        assert_eq!(second.get_move_type().await.unwrap(), MoveType::None);
+    }
+
+    #[tokio::test]
+    async fn watchdog_stops_unattended_motion() {
+        let address = start(Some(Duration::from_millis(100))).await;
+        let mut client = Client::new(address).await.unwrap();
+        let mut notifications = client.notifications();
+
+        client.move_forward(100).await.unwrap();
+
+        match next_notification(&mut notifications).await {
+            NotificationData::MotionStopped(reason) => {
+                assert_eq!(reason, "No move or heartbeat requests received for 100 ms.")
+            }
+            other => panic!("Motion stop expected, got {:?}", other),
+        }
+
+        assert_eq!(client.get_move_type().await.unwrap(), MoveType::None);
+    }
+
+    #[tokio::test]
+    async fn heartbeats_keep_motion_going() {
+        let address = start(Some(Duration::from_millis(100))).await;
+        let mut client = Client::new(address).await.unwrap();
+
+        client.move_forward(100).await.unwrap();
+
+        for _ in 0..6 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.heartbeat().await.unwrap();
+        }
+
+        assert_eq!(
+            client.get_move_type().await.unwrap(),
+            MoveType::Forward(100)
+        );
+    }
+
+    #[tokio::test]
+    async fn watchdog_leaves_running_behavior_alone() {
+        let address = start(Some(Duration::from_millis(100))).await;
+        let mut client = Client::new(address).await.unwrap();
+        let mut notifications = client.notifications();
+
+        // behavior keeps the rover alive once per step, which has to come well within the timeout
+        let wander = BehaviorConfig::Wander(WanderConfig {
+            period_ms: 20,
+            ..WanderConfig::default()
+        });
+
+        client.start_behavior(wander).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        assert!(client.get_behavior().await.unwrap().is_some());
+
+        while let Ok(notification) = notifications.try_recv() {
+            if let NotificationData::MotionStopped(reason) = notification {
+                panic!("Behavior was stopped: {}", reason);
+            }
+        }
+    }
+
+    async fn next_sense(
+        notifications: &mut broadcast::Receiver<NotificationData>,
+    ) -> SenseResponseData {
+        match next_notification(notifications).await {
+            NotificationData::Sense(value) => value,
+            other => panic!("Sensor value expected, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn subscriber_receives_sensor_values_until_unsubscribed() {
+        let address = start(None).await;
+        let client = Client::new(address).await.unwrap();
+        let mut notifications = client.notifications();
+
+        let sensors = vec![SenseRequestData::Obstacle, SenseRequestData::Distance];
+        client
+            .subscribe(sensors, SubscriptionPolicy::Rate(20))
+            .await
+            .unwrap();
+
+        for _ in 0..3 {
+            assert!(matches!(
+                next_sense(&mut notifications).await,
+                SenseResponseData::Obstacle(_)
+            ));
+            assert!(matches!(
+                next_sense(&mut notifications).await,
+                SenseResponseData::Distance(_)
+            ));
+        }
+
+        client.unsubscribe().await.unwrap();
+
+        // values read before unsubscribing may still be on the way
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        while notifications.try_recv().is_ok() {}
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(notifications.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn change_subscriber_receives_only_changed_values() {
+        let address = start(None).await;
+        let client = Client::new(address).await.unwrap();
+        let mut notifications = client.notifications();
+
+        let sensors = vec![SenseRequestData::Line];
+        client
+            .subscribe(sensors, SubscriptionPolicy::OnChange(20))
+            .await
+            .unwrap();
+
+        // rover stands still, so the first value is the only one
+        assert!(matches!(
+            next_sense(&mut notifications).await,
+            SenseResponseData::Line(_)
+        ));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(notifications.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn subscriptions_are_per_client() {
+        let address = start(None).await;
+        let subscriber = Client::new(address).await.unwrap();
+        let other = Client::new(address).await.unwrap();
+        let mut notifications = other.notifications();
+
+        let sensors = vec![SenseRequestData::Distance];
+        subscriber
+            .subscribe(sensors, SubscriptionPolicy::Rate(20))
+            .await
+            .unwrap();
+        next_sense(&mut subscriber.notifications()).await;
+
+        assert!(notifications.try_recv().is_err());
+    }
+
+    async fn connect_raw(address: SocketAddr) -> ChannelType {
+        let socket = TcpStream::connect(address).await.unwrap();
+        let codec: Codec<Envelope, Envelope> = Codec::new();
+
+        codec.framed(socket)
+    }
+
+    async fn receive_raw(channel: &mut ChannelType) -> Envelope {
+        tokio::time::timeout(WAIT, channel.next())
+            .await
+            .expect("Response expected")
+            .expect("Connection should stay open")
+            .unwrap()
+    }
+
+    fn hello(protocol_version: u16) -> ProtocolMessage {
+        ProtocolMessage::HelloRequest(ClientHelloData {
+            protocol_version,
+            name: "raw".to_owned(),
+            notifications: false,
+        })
+    }
+
+    #[tokio::test]
+    async fn pipelined_requests_are_answered_in_order_with_their_ids() {
+        let address = start(None).await;
+        let mut channel = connect_raw(address).await;
+
+        channel
+            .send(Envelope {
+                id: Some(7),
+                message: hello(PROTOCOL_VERSION),
+            })
+            .await
+            .unwrap();
+        assert_eq!(receive_raw(&mut channel).await.id, Some(7));
+
+        let requests = [
+            (42, ProtocolMessage::MoveRequest(MoveType::Forward(100))),
+            (3, ProtocolMessage::MoveDirectionRequest),
+            (1000, ProtocolMessage::ControlStatusRequest),
+        ];
+
+        for (id, message) in requests.iter().cloned() {
+            channel
+                .send(Envelope {
+                    id: Some(id),
+                    message,
+                })
+                .await
+                .unwrap();
+        }
+
+        let Envelope { id, message } = receive_raw(&mut channel).await;
+        assert_eq!(id, Some(42));
+        assert!(matches!(
+            message,
+            ProtocolMessage::StatusResponse(StatusResponseData::Success)
+        ));
+
+        let Envelope { id, message } = receive_raw(&mut channel).await;
+        assert_eq!(id, Some(3));
+        assert!(matches!(
+            message,
+            ProtocolMessage::MoveDirectionResponse(MoveType::Forward(100))
+        ));
+
+        let Envelope { id, message } = receive_raw(&mut channel).await;
+        assert_eq!(id, Some(1000));
+        assert!(matches!(
+            message,
+            ProtocolMessage::ControlStatusResponse(ControlStatusData {
+                in_control: true,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_get_own_responses_among_notifications() {
+        let address = start(None).await;
+        let client = Client::new(address).await.unwrap();
+
+        let sensors = vec![SenseRequestData::Distance];
+        client
+            .subscribe(sensors, SubscriptionPolicy::Rate(20))
+            .await
+            .unwrap();
+
+        let requests = (0..20).map(|i| {
+            let mut client = client.clone();
+
+            async move {
+                client.look_at(i, -i).await.unwrap();
+                client.get_control_status().await.unwrap()
+            }
+        });
+
+        for status in futures::future::join_all(requests).await {
+            assert!(status.in_control);
+        }
+    }
+
+    #[tokio::test]
+    async fn request_before_hello_is_refused() {
+        let address = start(None).await;
+        let mut channel = connect_raw(address).await;
+
+        let request = Envelope {
+            id: Some(1),
+            message: ProtocolMessage::MoveRequest(MoveType::Forward(100)),
+        };
+        channel.send(request).await.unwrap();
+
+        match receive_raw(&mut channel).await {
+            Envelope {
+                id: Some(1),
+                message: ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)),
+            } => {
+                assert_eq!(kind, ErrorKind::Invalid);
+                assert_eq!(e, "Handshake expected.");
+            }
+            other => panic!("Handshake error expected, got {:?}", other),
+        }
+
+        assert!(channel.next().await.is_none());
+
+        // refused request did not move the rover
+        let client = Client::new(address).await.unwrap();
+        assert_eq!(client.get_move_type().await.unwrap(), MoveType::None);
+    }
+
+    #[tokio::test]
+    async fn other_protocol_version_is_refused() {
+        let address = start(None).await;
+        let mut channel = connect_raw(address).await;
+
+        channel
+            .send(Envelope {
+                id: Some(1),
+                message: hello(PROTOCOL_VERSION + 1),
+            })
+            .await
+            .unwrap();
+
+        match receive_raw(&mut channel).await.message {
+            ProtocolMessage::StatusResponse(StatusResponseData::Error(kind, e)) => {
+                assert_eq!(kind, ErrorKind::Unsupported);
+                assert_eq!(
+                    e,
+                    format!(
+                        "Unsupported protocol version {}, server speaks {}.",
+                        PROTOCOL_VERSION + 1,
+                        PROTOCOL_VERSION
+                    )
+                );
+            }
+            other => panic!("Version error expected, got {:?}", other),
+        }
+
+        assert!(channel.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn hello_reports_capabilities() {
+        let address = start(None).await;
+        let client = Client::with_name(address, "test").await.unwrap();
+        let capabilities = client.capabilities();
+
+        assert_eq!(client.server_name(), SERVER_NAME);
+        assert!(capabilities.mover);
+
+        let limits = capabilities.looker.as_ref().expect("Looker expected");
+        assert_eq!(limits.h, (-90, 90));
+
+        let sensors = &capabilities
+            .sensor
+            .as_ref()
+            .expect("Sensor expected")
+            .sensors;
+        assert!(!sensors.is_empty());
     }
 }
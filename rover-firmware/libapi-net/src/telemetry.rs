@@ -0,0 +1,646 @@
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use libdriver::api::{
+    AsyncLooker, AsyncMover, AsyncSensor, DetectionReading, DistanceReading, LookLimits, MoveType,
+    RoverSnapshot, SensorDescriptor,
+};
+use libdriver::session::{CallRecord, DriverCall, DriverReply, Session};
+
+use crate::contract::data::{ProtocolMessage, RequestId};
+use crate::Result;
+
+/// Where and how much telemetry to keep.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Current log file, rotated ones get `.1`, `.2`, ... appended, the higher the older.
+    pub path: String,
+    pub max_file_size_kb: u64,
+
+    /// How many files to keep, including the current one.
+    pub max_files: usize,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            path: "telemetry/api-net.cbor".to_owned(),
+            max_file_size_kb: 1024,
+            max_files: 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
+pub enum Direction {
+    Received,
+    Sent,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Event {
+    /// Message exchanged with a client over given connection.
+    Message {
+        connection: u64,
+        direction: Direction,
+        id: Option<RequestId>,
+        message: ProtocolMessage,
+    },
+
+    /// Call of rover driver with its outcome.
+    Call {
+        call: DriverCall,
+        duration_us: u64,
+        result: std::result::Result<DriverReply, String>,
+    },
+}
+
+/// Single entry of telemetry log.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Record {
+    /// Wall clock time the event happened at, in ms since UNIX epoch.
+    pub time_ms: u64,
+    pub event: Event,
+}
+
+/// Appends records to telemetry log in the background. Clones write to the same log.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    outbox: mpsc::Sender<Record>,
+}
+
+impl Recorder {
+    pub fn start(config: TelemetryConfig) -> Result<Recorder> {
+        let mut log = LogFile::open(config)?;
+        let (outbox, inbox) = mpsc::channel::<Record>();
+
+        info!("Recording telemetry to {}.", log.path.display());
+
+        // blocking writes are kept away from async runtime
+        thread::spawn(move || {
+            for record in inbox {
+                if let Err(e) = log.append(&record) {
+                    error!("Failed to record telemetry: {}", e);
+                }
+            }
+        });
+
+        Ok(Recorder { outbox })
+    }
+
+    pub fn record(&self, event: Event) {
+        let time_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as u64);
+
+        // writer is gone only if it panicked, nothing to do about it
+        let _ = self.outbox.send(Record { time_ms, event });
+    }
+
+    pub fn record_message(
+        &self,
+        connection: u64,
+        direction: Direction,
+        id: Option<RequestId>,
+        message: &ProtocolMessage,
+    ) {
+        self.record(Event::Message {
+            connection,
+            direction,
+            id,
+            message: message.clone(),
+        });
+    }
+}
+
+/// Log file rotated once it grows too big.
+struct LogFile {
+    config: TelemetryConfig,
+    path: PathBuf,
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(config: TelemetryConfig) -> Result<LogFile> {
+        let path = PathBuf::from(&config.path);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(LogFile {
+            config,
+            path,
+            writer: BufWriter::new(file),
+            size,
+        })
+    }
+
+    fn append(&mut self, record: &Record) -> Result<()> {
+        let data = serde_cbor::to_vec(record).map_err(tokio_serde_cbor::Error::from)?;
+
+        if self.size > 0 && self.size + data.len() as u64 > self.config.max_file_size_kb * 1024 {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(&data)?;
+        // keep the log readable if the rover loses power
+        self.writer.flush()?;
+        self.size += data.len() as u64;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        let rotated = |i: usize| PathBuf::from(format!("{}.{}", self.path.display(), i));
+
+        for i in (1..self.config.max_files).rev() {
+            let from = if i == 1 { self.path.clone() } else { rotated(i - 1) };
+
+            if from.exists() {
+                fs::rename(&from, rotated(i))?;
+            }
+        }
+
+        if self.config.max_files <= 1 {
+            fs::remove_file(&self.path)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+/// Telemetry log files written with given current file path, oldest first.
+pub fn log_files<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
+    let path = path.as_ref();
+    let rotated = |i: usize| PathBuf::from(format!("{}.{}", path.display(), i));
+
+    let mut files: Vec<PathBuf> = (1..).map(rotated).take_while(|p| p.exists()).collect();
+    files.reverse();
+
+    if path.exists() {
+        files.push(path.to_owned());
+    }
+
+    files
+}
+
+/// Reads all records of the log file. Record cut short, e.g. by power loss, ends the log.
+pub fn read_log<P: AsRef<Path>>(path: P) -> Result<Vec<Record>> {
+    let reader = BufReader::new(File::open(&path)?);
+    let mut records = vec![];
+
+    for record in serde_cbor::Deserializer::from_reader(reader).into_iter::<Record>() {
+        match record {
+            Ok(record) => records.push(record),
+            Err(e) if e.is_eof() => {
+                warn!("Log {} ends with incomplete record.", path.as_ref().display());
+                break;
+            }
+            Err(e) => return Err(tokio_serde_cbor::Error::from(e).into()),
+        }
+    }
+
+    Ok(records)
+}
+
+/// Rover controls recording every call when given a recorder.
+pub struct Recorded<T> {
+    inner: T,
+    recorder: Option<Recorder>,
+}
+
+impl<T> Recorded<T> {
+    pub fn new(inner: T, recorder: Option<Recorder>) -> Recorded<T> {
+        Recorded { inner, recorder }
+    }
+}
+
+/// Driver session made of the calls found in telemetry records, timed from the first one.
+pub fn to_session(records: &[Record]) -> Session {
+    let calls: Vec<CallRecord> = records
+        .iter()
+        .filter_map(|record| match record.event {
+            Event::Call {
+                call, ref result, ..
+            } => Some(CallRecord {
+                time_ms: record.time_ms,
+                call,
+                result: result.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+
+    let start = calls.first().map_or(0, |record| record.time_ms);
+
+    Session {
+        calls: calls
+            .into_iter()
+            .map(|record| CallRecord {
+                time_ms: record.time_ms.saturating_sub(start),
+                ..record
+            })
+            .collect(),
+    }
+}
+
+async fn record_call<R, E, F>(
+    recorder: &Option<Recorder>,
+    call: DriverCall,
+    reply: fn(&R) -> DriverReply,
+    future: F,
+) -> std::result::Result<R, E>
+where
+    E: std::fmt::Display,
+    F: Future<Output = std::result::Result<R, E>>,
+{
+    let recorder = match recorder {
+        Some(recorder) => recorder,
+        None => return future.await,
+    };
+
+    let started = Instant::now();
+    let result = future.await;
+
+    recorder.record(Event::Call {
+        call,
+        duration_us: started.elapsed().as_micros() as u64,
+        result: match result {
+            Ok(ref value) => Ok(reply(value)),
+            Err(ref e) => Err(e.to_string()),
+        },
+    });
+
+    result
+}
+
+fn done(_: &()) -> DriverReply {
+    DriverReply::Done
+}
+
+#[async_trait]
+impl<T> AsyncMover for Recorded<T>
+where
+    T: AsyncMover + Send + Sync,
+{
+    type Error = T::Error;
+
+    async fn stop(&mut self) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.stop();
+        record_call(&self.recorder, DriverCall::Stop, done, future).await
+    }
+
+    async fn move_forward(&mut self, speed: u8) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.move_forward(speed);
+        record_call(&self.recorder, DriverCall::MoveForward(speed), done, future).await
+    }
+
+    async fn move_backward(&mut self, speed: u8) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.move_backward(speed);
+        record_call(&self.recorder, DriverCall::MoveBackward(speed), done, future).await
+    }
+
+    async fn spin_right(&mut self, speed: u8) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.spin_right(speed);
+        record_call(&self.recorder, DriverCall::SpinRight(speed), done, future).await
+    }
+
+    async fn spin_left(&mut self, speed: u8) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.spin_left(speed);
+        record_call(&self.recorder, DriverCall::SpinLeft(speed), done, future).await
+    }
+
+    async fn drive(&mut self, left: i16, right: i16) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.drive(left, right);
+        record_call(&self.recorder, DriverCall::Drive(left, right), done, future).await
+    }
+
+    async fn get_move_type(&self) -> std::result::Result<MoveType, Self::Error> {
+        let future = self.inner.get_move_type();
+        let reply = |move_type: &MoveType| DriverReply::MoveType(*move_type);
+        record_call(&self.recorder, DriverCall::GetMoveType, reply, future).await
+    }
+
+    async fn heartbeat(&mut self) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.heartbeat();
+        record_call(&self.recorder, DriverCall::Heartbeat, done, future).await
+    }
+
+    async fn reset(&mut self) -> std::result::Result<(), Self::Error> {
+        let future = AsyncMover::reset(&mut self.inner);
+        record_call(&self.recorder, DriverCall::ResetMover, done, future).await
+    }
+}
+
+#[async_trait]
+impl<T> AsyncLooker for Recorded<T>
+where
+    T: AsyncLooker + Send + Sync,
+{
+    type Error = T::Error;
+
+    async fn look_at(&mut self, h: i16, v: i16) -> std::result::Result<(), Self::Error> {
+        let future = self.inner.look_at(h, v);
+        record_call(&self.recorder, DriverCall::LookAt(h, v), done, future).await
+    }
+
+    async fn get_look_direction(&self) -> std::result::Result<(i16, i16), Self::Error> {
+        let future = self.inner.get_look_direction();
+        let reply = |direction: &(i16, i16)| DriverReply::LookDirection(*direction);
+        record_call(&self.recorder, DriverCall::GetLookDirection, reply, future).await
+    }
+
+    async fn get_look_limits(&self) -> std::result::Result<LookLimits, Self::Error> {
+        let future = self.inner.get_look_limits();
+        let reply = |limits: &LookLimits| DriverReply::LookLimits(*limits);
+        record_call(&self.recorder, DriverCall::GetLookLimits, reply, future).await
+    }
+
+    async fn reset(&mut self) -> std::result::Result<(), Self::Error> {
+        let future = AsyncLooker::reset(&mut self.inner);
+        record_call(&self.recorder, DriverCall::ResetLooker, done, future).await
+    }
+}
+
+#[async_trait]
+impl<T> AsyncSensor for Recorded<T>
+where
+    T: AsyncSensor + Send + Sync,
+{
+    type Error = T::Error;
+
+    async fn get_sensors(&self) -> std::result::Result<Vec<SensorDescriptor>, Self::Error> {
+        let future = self.inner.get_sensors();
+        let reply = |sensors: &Vec<SensorDescriptor>| DriverReply::Sensors(sensors.clone());
+        record_call(&self.recorder, DriverCall::GetSensors, reply, future).await
+    }
+
+    async fn get_obstacles(&self) -> std::result::Result<DetectionReading, Self::Error> {
+        let future = self.inner.get_obstacles();
+        let reply = |reading: &DetectionReading| DriverReply::Detections(reading.clone());
+        record_call(&self.recorder, DriverCall::GetObstacles, reply, future).await
+    }
+
+    async fn get_lines(&self) -> std::result::Result<DetectionReading, Self::Error> {
+        let future = self.inner.get_lines();
+        let reply = |reading: &DetectionReading| DriverReply::Detections(reading.clone());
+        record_call(&self.recorder, DriverCall::GetLines, reply, future).await
+    }
+
+    async fn scan_distance(&mut self) -> std::result::Result<DistanceReading, Self::Error> {
+        let future = self.inner.scan_distance();
+        let reply = |reading: &DistanceReading| DriverReply::Distance(*reading);
+        record_call(&self.recorder, DriverCall::ScanDistance, reply, future).await
+    }
+
+    async fn snapshot(&mut self) -> std::result::Result<RoverSnapshot, Self::Error> {
+        let future = self.inner.snapshot();
+        let reply = |snapshot: &RoverSnapshot| DriverReply::Snapshot(snapshot.clone());
+        record_call(&self.recorder, DriverCall::Snapshot, reply, future).await
+    }
+
+    async fn reset(&mut self) -> std::result::Result<(), Self::Error> {
+        let future = AsyncSensor::reset(&mut self.inner);
+        record_call(&self.recorder, DriverCall::ResetSensor, done, future).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn config(dir: &Path, max_files: usize) -> TelemetryConfig {
+        TelemetryConfig {
+            path: dir.join("api-net.cbor").display().to_string(),
+            max_file_size_kb: 1,
+            max_files,
+        }
+    }
+
+    fn message(id: RequestId) -> Record {
+        Record {
+            time_ms: 1000 + id,
+            event: Event::Message {
+                connection: 1,
+                direction: Direction::Received,
+                id: Some(id),
+                message: ProtocolMessage::ControlRequest,
+            },
+        }
+    }
+
+    fn ids(records: &[Record]) -> Vec<RequestId> {
+        records
+            .iter()
+            .filter_map(|record| match record.event {
+                Event::Message { id, .. } => id,
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn read_all(path: &str) -> Vec<Record> {
+        log_files(path)
+            .into_iter()
+            .flat_map(|file| read_log(file).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn rotates_files_by_size_keeping_the_newest() {
+        let dir = temp_dir("telemetry-rotation");
+        let config = config(&dir, 3);
+        let mut log = LogFile::open(config.clone()).unwrap();
+
+        for id in 0..200 {
+            log.append(&message(id)).unwrap();
+        }
+        drop(log);
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["api-net.cbor", "api-net.cbor.1", "api-net.cbor.2"]);
+
+        let files = log_files(&config.path);
+        assert_eq!(
+            files,
+            [
+                dir.join("api-net.cbor.2"),
+                dir.join("api-net.cbor.1"),
+                dir.join("api-net.cbor")
+            ]
+        );
+        for file in &files {
+            assert!(fs::metadata(file).unwrap().len() <= 1024);
+        }
+
+        // oldest records are gone, the rest is read back in order
+        let ids = ids(&read_all(&config.path));
+        assert!(ids.len() < 200);
+        assert_eq!(ids, (200 - ids.len() as u64..200).collect::<Vec<_>>());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn single_file_is_restarted() {
+        let dir = temp_dir("telemetry-single");
+        let config = config(&dir, 1);
+        let mut log = LogFile::open(config.clone()).unwrap();
+
+        for id in 0..100 {
+            log.append(&message(id)).unwrap();
+        }
+        drop(log);
+
+        assert_eq!(log_files(&config.path), [dir.join("api-net.cbor")]);
+        assert_eq!(ids(&read_all(&config.path)).last(), Some(&99));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopened_log_is_appended_to() {
+        let dir = temp_dir("telemetry-reopen");
+        let config = TelemetryConfig {
+            max_file_size_kb: 64,
+            ..config(&dir, 2)
+        };
+
+        for id in 0..3 {
+            LogFile::open(config.clone())
+                .unwrap()
+                .append(&message(id))
+                .unwrap();
+        }
+
+        assert_eq!(log_files(&config.path).len(), 1);
+        assert_eq!(ids(&read_all(&config.path)), [0, 1, 2]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn incomplete_record_ends_the_log() {
+        let dir = temp_dir("telemetry-cut");
+        let config = config(&dir, 1);
+        let mut log = LogFile::open(config.clone()).unwrap();
+
+        log.append(&message(0)).unwrap();
+        log.append(&message(1)).unwrap();
+        drop(log);
+
+        let data = fs::read(&config.path).unwrap();
+        fs::write(&config.path, &data[..data.len() - 3]).unwrap();
+
+        assert_eq!(ids(&read_log(&config.path).unwrap()), [0]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recorder_writes_records_in_background() {
+        let dir = temp_dir("telemetry-recorder");
+        let config = TelemetryConfig {
+            max_file_size_kb: 64,
+            ..config(&dir, 2)
+        };
+        let recorder = Recorder::start(config.clone()).unwrap();
+
+        recorder.record_message(7, Direction::Sent, None, &ProtocolMessage::ControlRequest);
+        recorder.record(Event::Call {
+            call: DriverCall::MoveForward(100),
+            duration_us: 250,
+            result: Ok(DriverReply::Done),
+        });
+
+        let mut records = vec![];
+        for _ in 0..200 {
+            records = read_log(&config.path).unwrap();
+            if records.len() == 2 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(records.len(), 2);
+        assert!(records[0].time_ms > 0);
+        assert!(matches!(
+            records[0].event,
+            Event::Message {
+                connection: 7,
+                direction: Direction::Sent,
+                id: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            records[1].event,
+            Event::Call {
+                call: DriverCall::MoveForward(100),
+                duration_us: 250,
+                result: Ok(DriverReply::Done),
+            }
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn session_is_timed_from_first_call() {
+        let call = |time_ms, call| Record {
+            time_ms,
+            event: Event::Call {
+                call,
+                duration_us: 10,
+                result: Ok(DriverReply::Done),
+            },
+        };
+        let records = [
+            message(0),
+            call(1500, DriverCall::MoveForward(100)),
+            message(1),
+            call(1750, DriverCall::Stop),
+        ];
+
+        let session = to_session(&records);
+
+        assert_eq!(session.calls.len(), 2);
+        assert_eq!(session.calls[0].time_ms, 0);
+        assert_eq!(session.calls[0].call, DriverCall::MoveForward(100));
+        assert_eq!(session.calls[1].time_ms, 250);
+        assert_eq!(session.calls[1].call, DriverCall::Stop);
+    }
+}
@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use libdriver::api::{MoveType, RoverSnapshot};
+use libdriver::RoverError;
+
+use crate::line::{LineFollow, LineFollowConfig};
+use crate::wander::{Wander, WanderConfig};
+use crate::{Error, Result};
+
+/// Action a behavior asks the rover to take.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Command {
+    Move(MoveType),
+    LookAt(i16, i16),
+}
+
+/// Outcome of a single behavior step.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Step {
+    /// Behavior goes on after the given commands are performed.
+    Continue(Vec<Command>),
+
+    /// Behavior has reached its goal, the rover is to be stopped.
+    Done,
+
+    /// Behavior cannot go on for the given reason, the rover is to be stopped.
+    Fail(String),
+}
+
+/// Closed-loop control of the rover. Behaviors only decide what to do based on sensor readings,
+/// see [`Rig`] for how the decisions are carried out.
+pub trait Behavior: Send {
+    fn name(&self) -> &str;
+
+    /// How often the behavior wants to look at sensors.
+    fn period(&self) -> Duration;
+
+    /// Decides what to do next given fresh readings. Readings' timestamps serve as the clock.
+    fn step(&mut self, snapshot: &RoverSnapshot) -> Step;
+}
+
+/// Rover controls a behavior is run against.
+#[async_trait]
+pub trait Rig: Send {
+    async fn snapshot(&mut self) -> Result<RoverSnapshot>;
+    async fn perform(&mut self, command: Command) -> Result<()>;
+}
+
+/// Something running behaviors in the background, e.g. api-net server through its client.
+#[async_trait]
+pub trait BehaviorHost {
+    type Error: RoverError;
+
+    /// Starts given behavior, replacing the running one.
+    async fn start_behavior(
+        &mut self,
+        behavior: BehaviorConfig,
+    ) -> std::result::Result<(), Self::Error>;
+
+    /// Stops running behavior and the rover.
+    async fn stop_behavior(&mut self) -> std::result::Result<(), Self::Error>;
+
+    /// Running behavior, if any.
+    async fn get_behavior(&self) -> std::result::Result<Option<BehaviorConfig>, Self::Error>;
+}
+
+/// Behaviors that can be started by name, with their parameters.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BehaviorConfig {
+    Wander(WanderConfig),
+    Line(LineFollowConfig),
+}
+
+impl BehaviorConfig {
+    pub fn build(&self) -> Box<dyn Behavior> {
+        match self {
+            BehaviorConfig::Wander(config) => Box::new(Wander::new(config.clone())),
+            BehaviorConfig::Line(config) => Box::new(LineFollow::new(config.clone())),
+        }
+    }
+
+    /// Checks parameters the behavior cannot run with, e.g. received from a client.
+    pub fn validate(&self) -> Result<()> {
+        let problem = match self {
+            BehaviorConfig::Wander(config) => config.problem(),
+            BehaviorConfig::Line(config) => config.problem(),
+        };
+
+        match problem {
+            Some(problem) => Err(Error::InvalidBehavior(problem.to_owned())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Time the latest of the readings was taken at, in ms.
+pub(crate) fn snapshot_time(snapshot: &RoverSnapshot) -> u64 {
+    snapshot
+        .obstacles
+        .timestamp
+        .max(snapshot.lines.timestamp)
+        .max(snapshot.distance.timestamp)
+        .0
+}
+
+/// Whether the parameter is a usable positive number.
+pub(crate) fn positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line::LineController;
+
+    use super::*;
+
+    #[test]
+    fn default_behaviors_are_valid() {
+        assert!(BehaviorConfig::Wander(WanderConfig::default())
+            .validate()
+            .is_ok());
+        assert!(BehaviorConfig::Line(LineFollowConfig::default())
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_unusable_parameters() {
+        let wander = |config| {
+            BehaviorConfig::Wander(config)
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+        let line = |config| {
+            BehaviorConfig::Line(config)
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            wander(WanderConfig {
+                period_ms: 0,
+                ..WanderConfig::default()
+            }),
+            "Invalid behavior: Period must be positive."
+        );
+        assert_eq!(
+            wander(WanderConfig {
+                min_distance: f32::NAN,
+                ..WanderConfig::default()
+            }),
+            "Invalid behavior: Minimum distance must be positive."
+        );
+        assert_eq!(
+            line(LineFollowConfig {
+                speed: 0,
+                ..LineFollowConfig::default()
+            }),
+            "Invalid behavior: Speeds must be positive."
+        );
+        assert_eq!(
+            line(LineFollowConfig {
+                controller: LineController::BangBang { turn: -0.5 },
+                ..LineFollowConfig::default()
+            }),
+            "Invalid behavior: Turn must be positive."
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use async_trait::async_trait;
+use log::debug;
+use tokio::task::JoinHandle;
+
+use libdriver::api::{
+    AsyncLooker, AsyncMover, AsyncSensor, DetectionReading, DistanceReading, LookLimits, MoveType,
+    RoverSnapshot, SensorDescriptor,
+};
+
+use crate::api::{BehaviorConfig, BehaviorHost};
+use crate::runner::{run, Controls};
+
+/// Runs behaviors in the background against a rover shared with its owner, for when there is no
+/// api-net server to run them. As with the server, move requests interrupt running behavior.
+pub struct LocalHost<T> {
+    rover: T,
+    behavior: Option<(BehaviorConfig, JoinHandle<()>)>,
+}
+
+impl<T> LocalHost<T>
+where
+    T: AsyncMover + AsyncLooker + AsyncSensor + Clone + Send + Sync + 'static,
+{
+    pub fn new(rover: T) -> LocalHost<T> {
+        LocalHost {
+            rover,
+            behavior: None,
+        }
+    }
+
+    /// Interrupts running behavior, if any, leaving the rover as is.
+    async fn interrupt(&mut self) {
+        if let Some((config, task)) = self.behavior.take() {
+            if !task.is_finished() {
+                debug!("Stopping {:?} behavior.", config);
+
+                task.abort();
+                let _ = task.await;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<T> BehaviorHost for LocalHost<T>
+where
+    T: AsyncMover + AsyncLooker + AsyncSensor + Clone + Send + Sync + 'static,
+{
+    type Error = <T as AsyncMover>::Error;
+
+    async fn start_behavior(&mut self, behavior: BehaviorConfig) -> Result<(), Self::Error> {
+        self.interrupt().await;
+
+        let mut controls = Controls {
+            mover: self.rover.clone(),
+            looker: Some(self.rover.clone()),
+            sensor: self.rover.clone(),
+        };
+        let mut running = behavior.build();
+
+        let task = tokio::spawn(async move {
+            // outcome is logged by the runner
+            let _ = run(&mut *running, &mut controls).await;
+        });
+
+        self.behavior = Some((behavior, task));
+
+        Ok(())
+    }
+
+    async fn stop_behavior(&mut self) -> Result<(), Self::Error> {
+        self.interrupt().await;
+
+        self.rover.stop().await
+    }
+
+    async fn get_behavior(&self) -> Result<Option<BehaviorConfig>, Self::Error> {
+        Ok(match self.behavior {
+            Some((ref config, ref task)) if !task.is_finished() => Some(config.clone()),
+            _ => None,
+        })
+    }
+}
+
+#[async_trait]
+impl<T> AsyncMover for LocalHost<T>
+where
+    T: AsyncMover + AsyncLooker + AsyncSensor + Clone + Send + Sync + 'static,
+{
+    type Error = <T as AsyncMover>::Error;
+
+    async fn stop(&mut self) -> Result<(), Self::Error> {
+        self.interrupt().await;
+        self.rover.stop().await
+    }
+
+    async fn move_forward(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.interrupt().await;
+        self.rover.move_forward(speed).await
+    }
+
+    async fn move_backward(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.interrupt().await;
+        self.rover.move_backward(speed).await
+    }
+
+    async fn spin_right(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.interrupt().await;
+        self.rover.spin_right(speed).await
+    }
+
+    async fn spin_left(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.interrupt().await;
+        self.rover.spin_left(speed).await
+    }
+
+    async fn drive(&mut self, left: i16, right: i16) -> Result<(), Self::Error> {
+        self.interrupt().await;
+        self.rover.drive(left, right).await
+    }
+
+    async fn get_move_type(&self) -> Result<MoveType, Self::Error> {
+        self.rover.get_move_type().await
+    }
+
+    async fn heartbeat(&mut self) -> Result<(), Self::Error> {
+        self.rover.heartbeat().await
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        self.interrupt().await;
+        AsyncMover::reset(&mut self.rover).await
+    }
+}
+
+#[async_trait]
+impl<T> AsyncLooker for LocalHost<T>
+where
+    T: AsyncMover + AsyncLooker + AsyncSensor + Clone + Send + Sync + 'static,
+{
+    type Error = <T as AsyncLooker>::Error;
+
+    async fn look_at(&mut self, h: i16, v: i16) -> Result<(), Self::Error> {
+        self.rover.look_at(h, v).await
+    }
+
+    async fn get_look_direction(&self) -> Result<(i16, i16), Self::Error> {
+        self.rover.get_look_direction().await
+    }
+
+    async fn get_look_limits(&self) -> Result<LookLimits, Self::Error> {
+        self.rover.get_look_limits().await
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncLooker::reset(&mut self.rover).await
+    }
+}
+
+#[async_trait]
+impl<T> AsyncSensor for LocalHost<T>
+where
+    T: AsyncMover + AsyncLooker + AsyncSensor + Clone + Send + Sync + 'static,
+{
+    type Error = <T as AsyncSensor>::Error;
+
+    async fn get_sensors(&self) -> Result<Vec<SensorDescriptor>, Self::Error> {
+        self.rover.get_sensors().await
+    }
+
+    async fn get_obstacles(&self) -> Result<DetectionReading, Self::Error> {
+        self.rover.get_obstacles().await
+    }
+
+    async fn get_lines(&self) -> Result<DetectionReading, Self::Error> {
+        self.rover.get_lines().await
+    }
+
+    async fn scan_distance(&mut self) -> Result<DistanceReading, Self::Error> {
+        self.rover.scan_distance().await
+    }
+
+    async fn snapshot(&mut self) -> Result<RoverSnapshot, Self::Error> {
+        self.rover.snapshot().await
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncSensor::reset(&mut self.rover).await
+    }
+}
@@ -0,0 +1,47 @@
+use thiserror::Error as LibError;
+
+pub mod api;
+pub mod host;
+pub mod line;
+pub mod mission;
+pub mod runner;
+pub mod wander;
+
+pub use api::{Behavior, BehaviorConfig, BehaviorHost, Command, Rig, Step};
+pub use host::LocalHost;
+pub use line::{LineController, LineFollow, LineFollowConfig};
+pub use mission::{
+    Condition, Mission, MissionHandle, MissionProgress, MissionRun, MissionStatus, MissionStep,
+    ScanResult, SpinDirection,
+};
+pub use runner::{run, Controls};
+pub use wander::{Wander, WanderConfig};
+
+#[derive(Debug, LibError)]
+pub enum Error {
+    #[error("Rover error: {0}")]
+    Rover(String),
+
+    #[error("{0}")]
+    Failed(String),
+
+    #[error("Input/output error: {0:?}")]
+    IO(#[from] std::io::Error),
+
+    #[error("Invalid behavior: {0}")]
+    InvalidBehavior(String),
+
+    #[error("Invalid mission: {0}")]
+    InvalidMission(String),
+
+    #[error("Invalid mission file: {0}")]
+    MissionToml(#[from] toml::de::Error),
+
+    #[error("Invalid mission file: {0}")]
+    MissionJson(#[from] serde_json::Error),
+
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,417 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use libdriver::api::{MoveType, RoverSnapshot, SensorPosition};
+
+use crate::api::{positive, snapshot_time, Behavior, Command, Step};
+
+/// How line position is turned into steering. Line position is 1 when the line is under or
+/// beyond the left sensor, -1 when under or beyond the right one and 0 while it passes between
+/// them. Steering of 1 spins the rover left in place, 0.5 pivots it on the left wheels, negative
+/// values turn right.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LineController {
+    /// Turns by fixed amount whenever the line is off center.
+    BangBang { turn: f32 },
+
+    /// Steering proportional to line position, its integral and derivative over seconds.
+    Pid {
+        kp: f32,
+        #[serde(default)]
+        ki: f32,
+        #[serde(default)]
+        kd: f32,
+        /// Bound of the integral, preventing it from winding up while the line is off center.
+        #[serde(default = "default_integral_limit")]
+        integral_limit: f32,
+    },
+}
+
+fn default_integral_limit() -> f32 {
+    1.0
+}
+
+impl Default for LineController {
+    fn default() -> Self {
+        LineController::Pid {
+            kp: 0.4,
+            ki: 0.0,
+            kd: 0.005,
+            integral_limit: default_integral_limit(),
+        }
+    }
+}
+
+/// Parameters of the line follow behavior, the line is expected to pass between the sensors.
+/// Neither sensor seeing the line thus means the rover is on track, unless the line was last
+/// seen by one of them: it is then taken to be beyond that sensor.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct LineFollowConfig {
+    pub speed: u8,
+    pub controller: LineController,
+
+    /// How long the line may stay beyond a sensor, seen by neither, before it is considered
+    /// lost.
+    pub lost_ms: u64,
+    /// Lost line is searched for by spinning to alternate sides, each sweep longer than the
+    /// previous one by this time.
+    pub search_sweep_ms: u64,
+    pub search_speed: u8,
+    /// How long to search before giving up.
+    pub search_timeout_ms: u64,
+
+    pub period_ms: u64,
+}
+
+impl Default for LineFollowConfig {
+    fn default() -> Self {
+        LineFollowConfig {
+            speed: 120,
+            controller: LineController::default(),
+            lost_ms: 1500,
+            search_sweep_ms: 400,
+            search_speed: 100,
+            search_timeout_ms: 8000,
+            period_ms: 20,
+        }
+    }
+}
+
+impl LineFollowConfig {
+    pub(crate) fn problem(&self) -> Option<&'static str> {
+        if self.period_ms == 0 {
+            Some("Period must be positive.")
+        } else if self.speed == 0 || self.search_speed == 0 {
+            Some("Speeds must be positive.")
+        } else if self.search_sweep_ms == 0 {
+            Some("Search sweep must be positive.")
+        } else {
+            match self.controller {
+                LineController::BangBang { turn } if !positive(turn) => {
+                    Some("Turn must be positive.")
+                }
+                LineController::Pid {
+                    kp,
+                    ki,
+                    kd,
+                    integral_limit,
+                } if ![kp, ki, kd, integral_limit].iter().all(|k| k.is_finite())
+                    || integral_limit < 0.0 =>
+                {
+                    Some("Gains must be finite and integral limit not negative.")
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum State {
+    Follow,
+    Search {
+        since: u64,
+        sweep: u64,
+        sweep_since: u64,
+    },
+}
+
+/// Follows a line with the pair of line sensors, searching for it by spinning once lost.
+pub struct LineFollow {
+    config: LineFollowConfig,
+    state: State,
+
+    /// Time and side (as line position) the line was last seen at, 0 for none.
+    last_seen: Option<(u64, f32)>,
+
+    /// Time and line position of the last controller step.
+    last_step: Option<(u64, f32)>,
+    integral: f32,
+}
+
+impl LineFollow {
+    pub fn new(config: LineFollowConfig) -> LineFollow {
+        LineFollow {
+            config,
+            state: State::Follow,
+            last_seen: None,
+            last_step: None,
+            integral: 0.0,
+        }
+    }
+
+    fn line_position(snapshot: &RoverSnapshot) -> Option<f32> {
+        let left = snapshot.lines.at(SensorPosition::Left).unwrap_or(false);
+        let right = snapshot.lines.at(SensorPosition::Right).unwrap_or(false);
+
+        match (left, right) {
+            (true, false) => Some(1.0),
+            (false, true) => Some(-1.0),
+            // crossing or junction
+            (true, true) => Some(0.0),
+            (false, false) => None,
+        }
+    }
+
+    fn steer(&mut self, now: u64, position: f32) -> f32 {
+        let dt = match self.last_step {
+            Some((last_time, _)) if now > last_time => (now - last_time) as f32 / 1000.0,
+            _ => 0.0,
+        };
+        let last_position = self
+            .last_step
+            .map_or(position, |(_, last_position)| last_position);
+        self.last_step = Some((now, position));
+
+        let turn = match self.config.controller {
+            LineController::BangBang { turn } => position * turn,
+            LineController::Pid {
+                kp,
+                ki,
+                kd,
+                integral_limit,
+            } => {
+                self.integral =
+                    (self.integral + position * dt).clamp(-integral_limit, integral_limit);
+                let derivative = if dt > 0.0 {
+                    (position - last_position) / dt
+                } else {
+                    0.0
+                };
+
+                kp * position + ki * self.integral + kd * derivative
+            }
+        };
+
+        turn.clamp(-1.0, 1.0)
+    }
+
+    /// Outer side keeps the speed while the inner one slows down, stops and reverses.
+    fn drive(speed: u8, turn: f32) -> MoveType {
+        let speed = speed as f32;
+        let inner = (speed * (1.0 - 2.0 * turn.abs())).round() as i16;
+        let outer = speed.round() as i16;
+
+        if turn > 0.0 {
+            MoveType::Drive(inner, outer)
+        } else {
+            MoveType::Drive(outer, inner)
+        }
+    }
+
+    fn search_spin(&self, sweep: u64) -> MoveType {
+        let side = self.last_seen.map_or(1.0, |(_, side)| side);
+
+        // even sweeps turn to the side the line was last seen at
+        if (side >= 0.0) == sweep.is_multiple_of(2) {
+            MoveType::SpinCCW(self.config.search_speed)
+        } else {
+            MoveType::SpinCW(self.config.search_speed)
+        }
+    }
+}
+
+impl Behavior for LineFollow {
+    fn name(&self) -> &str {
+        "line"
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_millis(self.config.period_ms)
+    }
+
+    fn step(&mut self, snapshot: &RoverSnapshot) -> Step {
+        let now = snapshot_time(snapshot);
+        let position = Self::line_position(snapshot);
+
+        if let Some(position) = position {
+            let side = match (position, self.last_seen) {
+                (p, _) if p != 0.0 => p,
+                (_, Some((_, side))) => side,
+                _ => 0.0,
+            };
+            self.last_seen = Some((now, side));
+        }
+
+        let (seen_at, side) = *self.last_seen.get_or_insert((now, 0.0));
+
+        // unseen line is between the sensors or beyond the one it was last seen by, in which
+        // case the correction towards it goes on
+        let estimate = position.unwrap_or(side);
+
+        let command = match self.state {
+            State::Follow
+                if position.is_none() && side != 0.0 && now - seen_at >= self.config.lost_ms =>
+            {
+                self.state = State::Search {
+                    since: now,
+                    sweep: 0,
+                    sweep_since: now,
+                };
+
+                Some(self.search_spin(0))
+            }
+            State::Follow => {
+                let turn = self.steer(now, estimate);
+
+                Some(Self::drive(self.config.speed, turn))
+            }
+            State::Search { .. } if position.is_some() => {
+                self.state = State::Follow;
+                self.last_step = None;
+                self.integral = 0.0;
+
+                let turn = self.steer(now, estimate);
+
+                Some(Self::drive(self.config.speed, turn))
+            }
+            State::Search { since, .. } if now - since >= self.config.search_timeout_ms => {
+                return Step::Fail("Line is lost.".to_owned());
+            }
+            State::Search {
+                since,
+                sweep,
+                sweep_since,
+            } if now - sweep_since >= (sweep + 1) * self.config.search_sweep_ms => {
+                self.state = State::Search {
+                    since,
+                    sweep: sweep + 1,
+                    sweep_since: now,
+                };
+
+                Some(self.search_spin(sweep + 1))
+            }
+            State::Search { .. } => None,
+        };
+
+        Step::Continue(command.map(Command::Move).into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdriver::api::{Detection, DetectionReading, DistanceReading, Millimeters, Timestamp};
+
+    use super::*;
+
+    const STRAIGHT: MoveType = MoveType::Drive(120, 120);
+    const LEFT: MoveType = MoveType::Drive(0, 120);
+    const RIGHT: MoveType = MoveType::Drive(120, 0);
+
+    fn line_follow() -> LineFollow {
+        LineFollow::new(LineFollowConfig {
+            controller: LineController::BangBang { turn: 0.5 },
+            ..LineFollowConfig::default()
+        })
+    }
+
+    fn snapshot(time_ms: u64, left: bool, right: bool) -> RoverSnapshot {
+        let detection = |position, detected| Detection { position, detected };
+
+        RoverSnapshot {
+            obstacles: DetectionReading {
+                timestamp: Timestamp(time_ms),
+                detections: vec![],
+            },
+            lines: DetectionReading {
+                timestamp: Timestamp(time_ms),
+                detections: vec![
+                    detection(SensorPosition::Left, left),
+                    detection(SensorPosition::Right, right),
+                ],
+            },
+            distance: DistanceReading {
+                timestamp: Timestamp(time_ms),
+                distance: Millimeters(1000.0),
+                valid: true,
+            },
+            move_type: None,
+            look_direction: None,
+        }
+    }
+
+    /// Steps through (time, left sensor, right sensor) readings, checking the move each results
+    /// in, if any.
+    fn check(line: &mut LineFollow, steps: &[(u64, bool, bool, Option<MoveType>)]) {
+        for &(time_ms, left, right, expected) in steps {
+            let expected = Step::Continue(expected.map(Command::Move).into_iter().collect());
+
+            assert_eq!(
+                line.step(&snapshot(time_ms, left, right)),
+                expected,
+                "at {} ms",
+                time_ms
+            );
+        }
+    }
+
+    #[test]
+    fn centered_line_is_followed_straight() {
+        let mut line = line_follow();
+
+        for time_ms in (0..5000).step_by(20) {
+            check(&mut line, &[(time_ms, false, false, Some(STRAIGHT))]);
+        }
+    }
+
+    #[test]
+    fn drift_is_corrected_until_line_is_seen_on_other_side() {
+        check(
+            &mut line_follow(),
+            &[
+                (0, false, false, Some(STRAIGHT)),
+                // line under the left sensor
+                (20, true, false, Some(LEFT)),
+                // and beyond it
+                (40, false, false, Some(LEFT)),
+                (1000, false, false, Some(LEFT)),
+                // crossing
+                (1020, true, true, Some(STRAIGHT)),
+                (1040, false, true, Some(RIGHT)),
+                (1060, false, false, Some(RIGHT)),
+            ],
+        );
+    }
+
+    #[test]
+    fn line_lost_beyond_sensor_is_searched_for() {
+        check(
+            &mut line_follow(),
+            &[
+                (0, false, true, Some(RIGHT)),
+                (20, false, false, Some(RIGHT)),
+                (1499, false, false, Some(RIGHT)),
+                // searching to the side the line was last seen at first
+                (1500, false, false, Some(MoveType::SpinCW(100))),
+                (1520, false, false, None),
+                (1900, false, false, Some(MoveType::SpinCCW(100))),
+                (2000, false, false, None),
+                (2700, false, false, Some(MoveType::SpinCW(100))),
+                // reacquired
+                (2720, true, false, Some(LEFT)),
+                (2740, false, false, Some(LEFT)),
+            ],
+        );
+    }
+
+    #[test]
+    fn search_gives_up_after_timeout() {
+        let mut line = line_follow();
+
+        check(
+            &mut line,
+            &[
+                (0, true, false, Some(LEFT)),
+                (1500, false, false, Some(MoveType::SpinCCW(100))),
+            ],
+        );
+
+        assert_eq!(
+            line.step(&snapshot(9500, false, false)),
+            Step::Fail("Line is lost.".to_owned())
+        );
+    }
+}
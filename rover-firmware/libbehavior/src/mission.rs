@@ -0,0 +1,825 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use libdriver::api::{Detection, DistanceReading, MoveType, RoverSnapshot, SensorPosition};
+
+use crate::api::{positive, snapshot_time, Behavior, Command, Step};
+use crate::{Error, Result};
+
+/// Condition on sensor readings.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "sensor", rename_all = "lowercase")]
+pub enum Condition {
+    /// Obstacle sensor at given position detects something, or not if `detected` is false.
+    /// Without position, any sensor counts.
+    Obstacle {
+        #[serde(default)]
+        position: Option<SensorPosition>,
+        #[serde(default = "default_detected")]
+        detected: bool,
+    },
+
+    /// Same as above for line sensors.
+    Line {
+        #[serde(default)]
+        position: Option<SensorPosition>,
+        #[serde(default = "default_detected")]
+        detected: bool,
+    },
+
+    /// Measured distance is within given bounds. Nothing in sensor range counts as far away.
+    Distance {
+        #[serde(default)]
+        below_mm: Option<f32>,
+        #[serde(default)]
+        above_mm: Option<f32>,
+    },
+}
+
+fn default_detected() -> bool {
+    true
+}
+
+impl Condition {
+    pub fn holds(&self, snapshot: &RoverSnapshot) -> bool {
+        match *self {
+            Condition::Obstacle { position, detected } => {
+                detects(&snapshot.obstacles.detections, position, detected)
+            }
+            Condition::Line { position, detected } => {
+                detects(&snapshot.lines.detections, position, detected)
+            }
+            Condition::Distance { below_mm, above_mm } => {
+                let distance = &snapshot.distance;
+                let below = below_mm.is_none_or(|mm| distance.valid && distance.distance.0 < mm);
+                let above = above_mm.is_none_or(|mm| !distance.valid || distance.distance.0 > mm);
+
+                below && above
+            }
+        }
+    }
+}
+
+fn detects(detections: &[Detection], position: Option<SensorPosition>, detected: bool) -> bool {
+    detections
+        .iter()
+        .filter(|d| position.is_none_or(|position| d.position == position))
+        .any(|d| d.detected)
+        == detected
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum SpinDirection {
+    CW,
+    CCW,
+}
+
+/// Single mission step. Motion steps last for `duration_ms`, until `until` condition holds,
+/// whichever comes first, or end right away leaving the rover moving if neither is given.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum MissionStep {
+    Forward {
+        speed: u8,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        until: Option<Condition>,
+    },
+
+    Backward {
+        speed: u8,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        until: Option<Condition>,
+    },
+
+    /// Spins in place, by given angle if `degrees` is set.
+    Spin {
+        direction: SpinDirection,
+        speed: u8,
+        #[serde(default)]
+        degrees: Option<f32>,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        until: Option<Condition>,
+    },
+
+    Drive {
+        left: i16,
+        right: i16,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        until: Option<Condition>,
+    },
+
+    Stop,
+
+    /// Keeps the rover as is.
+    Wait {
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        until: Option<Condition>,
+    },
+
+    /// Looks at given direction and lets the servos settle.
+    Look {
+        h: i16,
+        v: i16,
+    },
+
+    /// Records measured distance in mission progress.
+    Scan,
+
+    /// Fails the mission unless the condition holds.
+    Expect {
+        condition: Condition,
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+/// Scripted sequence of steps, read from TOML or JSON file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct Mission {
+    pub name: String,
+
+    /// Degrees per second the rover spins at full speed, slower spins scale it proportionally.
+    pub spin_rate: f32,
+
+    /// Time for servos to settle after looking elsewhere.
+    pub settle_ms: u64,
+
+    pub period_ms: u64,
+
+    pub steps: Vec<MissionStep>,
+}
+
+impl Default for Mission {
+    fn default() -> Self {
+        Mission {
+            name: "mission".to_owned(),
+            spin_rate: 240.0,
+            settle_ms: 300,
+            period_ms: 50,
+            steps: vec![],
+        }
+    }
+}
+
+impl Mission {
+    /// Reads mission from the file, JSON if it has `.json` extension, TOML otherwise.
+    pub fn load<P: AsRef<Path>>(mission_path: P) -> Result<Mission> {
+        let text = fs::read_to_string(&mission_path)?;
+
+        let mission = match mission_path.as_ref().extension() {
+            Some(extension) if extension == "json" => serde_json::from_str(&text)?,
+            _ => toml::from_str(&text)?,
+        };
+
+        Ok(mission)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(Error::InvalidMission(message.to_owned()));
+
+        if self.steps.is_empty() {
+            return invalid("Mission has no steps.");
+        }
+
+        if !positive(self.spin_rate) {
+            return invalid("Spin rate must be positive.");
+        }
+
+        if self.period_ms == 0 {
+            return invalid("Period must be positive.");
+        }
+
+        for (i, step) in self.steps.iter().enumerate() {
+            match *step {
+                MissionStep::Spin {
+                    degrees: Some(degrees),
+                    speed,
+                    duration_ms,
+                    ..
+                } => {
+                    if duration_ms.is_some() {
+                        return invalid(&format!("Step {} has both degrees and duration.", i + 1));
+                    }
+
+                    if speed == 0 || !degrees.is_finite() || degrees < 0.0 {
+                        return invalid(&format!("Step {} needs positive speed and angle.", i + 1));
+                    }
+                }
+                MissionStep::Wait {
+                    duration_ms: None,
+                    until: None,
+                } => {
+                    return invalid(&format!("Step {} waits for nothing.", i + 1));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Time to spin by given angle at given speed.
+    fn spin_ms(&self, degrees: f32, speed: u8) -> u64 {
+        let rate = self.spin_rate * speed as f32 / u8::MAX as f32;
+
+        (degrees / rate * 1000.0).round() as u64
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum MissionStatus {
+    Running,
+    Paused,
+    Finished,
+    Failed(String),
+    Aborted(String),
+}
+
+impl MissionStatus {
+    /// Whether the mission is over.
+    pub fn is_over(&self) -> bool {
+        !matches!(self, MissionStatus::Running | MissionStatus::Paused)
+    }
+}
+
+/// Distance measured by a scan step.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ScanResult {
+    /// Index of the step.
+    pub step: usize,
+    pub look_direction: Option<(i16, i16)>,
+    pub distance: DistanceReading,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct MissionProgress {
+    pub name: String,
+    pub status: MissionStatus,
+
+    /// Index of the current step, equals `steps` once all are done.
+    pub step: usize,
+    pub steps: usize,
+    pub scans: Vec<ScanResult>,
+}
+
+/// Handle to a mission run, shared between the run and those controlling it.
+#[derive(Debug, Clone)]
+pub struct MissionHandle {
+    progress: Arc<watch::Sender<MissionProgress>>,
+}
+
+impl MissionHandle {
+    pub fn progress(&self) -> MissionProgress {
+        self.progress.borrow().clone()
+    }
+
+    /// Receiver notified of every progress change.
+    pub fn watch(&self) -> watch::Receiver<MissionProgress> {
+        self.progress.subscribe()
+    }
+
+    /// Has the run stop the rover and wait. Returns whether the mission was running.
+    pub fn pause(&self) -> bool {
+        self.set_status(MissionStatus::Running, MissionStatus::Paused)
+    }
+
+    /// Has paused run go on. Returns whether the mission was paused.
+    pub fn resume(&self) -> bool {
+        self.set_status(MissionStatus::Paused, MissionStatus::Running)
+    }
+
+    /// Marks the mission aborted, unless it is already over. The run ends at its next step, but
+    /// whoever aborts it is expected to stop the run and the rover right away.
+    pub fn abort(&self, reason: &str) -> bool {
+        self.progress.send_if_modified(|progress| {
+            if progress.status.is_over() {
+                false
+            } else {
+                progress.status = MissionStatus::Aborted(reason.to_owned());
+                true
+            }
+        })
+    }
+
+    fn set_status(&self, from: MissionStatus, to: MissionStatus) -> bool {
+        self.progress.send_if_modified(|progress| {
+            if progress.status == from {
+                progress.status = to;
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    fn update<F: FnOnce(&mut MissionProgress)>(&self, modify: F) {
+        self.progress.send_modify(modify);
+    }
+}
+
+/// Behavior executing mission steps one after another.
+pub struct MissionRun {
+    mission: Mission,
+    handle: MissionHandle,
+
+    step: usize,
+
+    /// Whether current step has issued its command.
+    started: bool,
+
+    /// Time spent on current step, not counting pauses.
+    elapsed: u64,
+    last_time: Option<u64>,
+
+    /// Whether the rover has been stopped for a pause.
+    halted: bool,
+}
+
+impl MissionRun {
+    pub fn new(mission: Mission) -> (MissionRun, MissionHandle) {
+        let progress = MissionProgress {
+            name: mission.name.clone(),
+            status: MissionStatus::Running,
+            step: 0,
+            steps: mission.steps.len(),
+            scans: vec![],
+        };
+
+        let handle = MissionHandle {
+            progress: Arc::new(watch::Sender::new(progress)),
+        };
+
+        let run = MissionRun {
+            mission,
+            handle: handle.clone(),
+            step: 0,
+            started: false,
+            elapsed: 0,
+            last_time: None,
+            halted: false,
+        };
+
+        (run, handle)
+    }
+
+    fn start_command(&self, step: &MissionStep) -> Option<Command> {
+        match *step {
+            MissionStep::Forward { speed, .. } => Some(Command::Move(MoveType::Forward(speed))),
+            MissionStep::Backward { speed, .. } => Some(Command::Move(MoveType::Backward(speed))),
+            MissionStep::Spin {
+                direction: SpinDirection::CW,
+                speed,
+                ..
+            } => Some(Command::Move(MoveType::SpinCW(speed))),
+            MissionStep::Spin {
+                direction: SpinDirection::CCW,
+                speed,
+                ..
+            } => Some(Command::Move(MoveType::SpinCCW(speed))),
+            MissionStep::Drive { left, right, .. } => {
+                Some(Command::Move(MoveType::Drive(left, right)))
+            }
+            MissionStep::Stop => Some(Command::Move(MoveType::None)),
+            MissionStep::Look { h, v } => Some(Command::LookAt(h, v)),
+            MissionStep::Wait { .. } | MissionStep::Scan | MissionStep::Expect { .. } => None,
+        }
+    }
+
+    /// Whether current step is complete, error if the mission cannot go on.
+    fn is_complete(
+        &self,
+        step: &MissionStep,
+        snapshot: &RoverSnapshot,
+    ) -> std::result::Result<bool, String> {
+        let limited = |duration_ms: Option<u64>, until: &Option<Condition>| {
+            let timed_out = duration_ms.is_some_and(|duration_ms| self.elapsed >= duration_ms);
+            let reached = until.as_ref().is_some_and(|until| until.holds(snapshot));
+
+            timed_out || reached || (duration_ms.is_none() && until.is_none())
+        };
+
+        Ok(match *step {
+            MissionStep::Forward {
+                duration_ms,
+                ref until,
+                ..
+            }
+            | MissionStep::Backward {
+                duration_ms,
+                ref until,
+                ..
+            }
+            | MissionStep::Drive {
+                duration_ms,
+                ref until,
+                ..
+            }
+            | MissionStep::Wait {
+                duration_ms,
+                ref until,
+            } => limited(duration_ms, until),
+            MissionStep::Spin {
+                degrees,
+                speed,
+                duration_ms,
+                ref until,
+                ..
+            } => {
+                let duration_ms = degrees
+                    .map(|degrees| self.mission.spin_ms(degrees, speed))
+                    .or(duration_ms);
+
+                limited(duration_ms, until)
+            }
+            MissionStep::Look { .. } => self.elapsed >= self.mission.settle_ms,
+            MissionStep::Stop | MissionStep::Scan => true,
+            MissionStep::Expect {
+                ref condition,
+                ref message,
+            } => {
+                if !condition.holds(snapshot) {
+                    return Err(message.clone().unwrap_or_else(|| {
+                        format!("Step {} expectation not met.", self.step + 1)
+                    }));
+                }
+
+                true
+            }
+        })
+    }
+}
+
+impl Behavior for MissionRun {
+    fn name(&self) -> &str {
+        &self.mission.name
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_millis(self.mission.period_ms)
+    }
+
+    fn step(&mut self, snapshot: &RoverSnapshot) -> Step {
+        let now = snapshot_time(snapshot);
+        let last_time = self.last_time.replace(now);
+
+        match self.handle.progress.borrow().status {
+            MissionStatus::Running => {}
+            MissionStatus::Paused if self.halted => return Step::Continue(vec![]),
+            MissionStatus::Paused => {
+                self.halted = true;
+                return Step::Continue(vec![Command::Move(MoveType::None)]);
+            }
+            // aborted by the controller
+            _ => return Step::Done,
+        }
+
+        if self.halted {
+            // resume motion of the current step
+            self.halted = false;
+            self.started = false;
+        } else if let Some(last_time) = last_time {
+            self.elapsed += now.saturating_sub(last_time);
+        }
+
+        let mut commands = vec![];
+
+        // steps that take no time run within a single period
+        while let Some(step) = self.mission.steps.get(self.step) {
+            if !self.started {
+                self.started = true;
+                commands.extend(self.start_command(step));
+            }
+
+            match self.is_complete(step, snapshot) {
+                Ok(false) => return Step::Continue(commands),
+                Ok(true) => {}
+                Err(reason) => {
+                    self.handle
+                        .update(|progress| progress.status = MissionStatus::Failed(reason.clone()));
+
+                    return Step::Fail(reason);
+                }
+            }
+
+            if let MissionStep::Scan = step {
+                let scan = ScanResult {
+                    step: self.step,
+                    look_direction: snapshot.look_direction,
+                    distance: snapshot.distance,
+                };
+
+                self.handle.update(|progress| progress.scans.push(scan));
+            }
+
+            self.step += 1;
+            self.started = false;
+            self.elapsed = 0;
+
+            let step = self.step;
+            self.handle.update(|progress| progress.step = step);
+        }
+
+        self.handle
+            .update(|progress| progress.status = MissionStatus::Finished);
+
+        Step::Done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdriver::api::{DetectionReading, Millimeters, Timestamp};
+
+    use super::*;
+
+    fn detections(time_ms: u64, left: bool, right: bool) -> DetectionReading {
+        DetectionReading {
+            timestamp: Timestamp(time_ms),
+            detections: vec![
+                Detection {
+                    position: SensorPosition::Left,
+                    detected: left,
+                },
+                Detection {
+                    position: SensorPosition::Right,
+                    detected: right,
+                },
+            ],
+        }
+    }
+
+    fn distance(time_ms: u64, distance_mm: f32, valid: bool) -> DistanceReading {
+        DistanceReading {
+            timestamp: Timestamp(time_ms),
+            distance: Millimeters(distance_mm),
+            valid,
+        }
+    }
+
+    fn snapshot(time_ms: u64) -> RoverSnapshot {
+        RoverSnapshot {
+            obstacles: detections(time_ms, false, false),
+            lines: detections(time_ms, false, false),
+            distance: distance(time_ms, 1000.0, true),
+            move_type: None,
+            look_direction: Some((0, 0)),
+        }
+    }
+
+    fn mission(steps: Vec<MissionStep>) -> Mission {
+        Mission {
+            steps,
+            ..Mission::default()
+        }
+    }
+
+    fn forward(duration_ms: Option<u64>, until: Option<Condition>) -> MissionStep {
+        MissionStep::Forward {
+            speed: 150,
+            duration_ms,
+            until,
+        }
+    }
+
+    fn moves(commands: &[Command]) -> Step {
+        Step::Continue(commands.to_vec())
+    }
+
+    fn invalid(mission: &Mission) -> String {
+        match mission.validate() {
+            Err(Error::InvalidMission(message)) => message,
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn obstacle_and_line_conditions() {
+        let mut snapshot = snapshot(0);
+        snapshot.obstacles = detections(0, true, false);
+
+        let obstacle = |position, detected| Condition::Obstacle { position, detected };
+        assert!(obstacle(None, true).holds(&snapshot));
+        assert!(obstacle(Some(SensorPosition::Left), true).holds(&snapshot));
+        assert!(!obstacle(Some(SensorPosition::Right), true).holds(&snapshot));
+        assert!(obstacle(Some(SensorPosition::Right), false).holds(&snapshot));
+        assert!(!obstacle(None, false).holds(&snapshot));
+
+        let line = |position, detected| Condition::Line { position, detected };
+        assert!(!line(None, true).holds(&snapshot));
+        assert!(line(None, false).holds(&snapshot));
+    }
+
+    #[test]
+    fn distance_conditions() {
+        let between = |below_mm, above_mm, reading| {
+            let mut snapshot = snapshot(0);
+            snapshot.distance = reading;
+
+            Condition::Distance { below_mm, above_mm }.holds(&snapshot)
+        };
+
+        assert!(between(Some(300.0), None, distance(0, 250.0, true)));
+        assert!(!between(Some(300.0), None, distance(0, 350.0, true)));
+        assert!(between(None, Some(300.0), distance(0, 350.0, true)));
+        assert!(between(Some(400.0), Some(300.0), distance(0, 350.0, true)));
+        assert!(!between(Some(400.0), Some(300.0), distance(0, 450.0, true)));
+
+        // nothing in range is far away
+        assert!(!between(Some(300.0), None, distance(0, 4000.0, false)));
+        assert!(between(None, Some(300.0), distance(0, 4000.0, false)));
+    }
+
+    #[test]
+    fn runs_steps_in_sequence() {
+        let (mut run, handle) = MissionRun::new(mission(vec![
+            forward(Some(1000), None),
+            MissionStep::Look { h: 30, v: 0 },
+            MissionStep::Scan,
+            MissionStep::Spin {
+                direction: SpinDirection::CW,
+                speed: 255,
+                degrees: Some(90.0),
+                duration_ms: None,
+                until: None,
+            },
+            MissionStep::Stop,
+        ]));
+
+        let forward = Command::Move(MoveType::Forward(150));
+        assert_eq!(run.step(&snapshot(0)), moves(&[forward]));
+        assert_eq!(run.step(&snapshot(500)), moves(&[]));
+        assert_eq!(run.step(&snapshot(1000)), moves(&[Command::LookAt(30, 0)]));
+        assert_eq!(handle.progress().step, 1);
+
+        // servos settle, then scan and spin follow right away
+        let mut looking = snapshot(1300);
+        looking.look_direction = Some((30, 0));
+        looking.distance = distance(1300, 420.0, true);
+        assert_eq!(
+            run.step(&looking),
+            moves(&[Command::Move(MoveType::SpinCW(255))])
+        );
+        assert_eq!(handle.progress().step, 3);
+        assert_eq!(
+            handle.progress().scans,
+            [ScanResult {
+                step: 2,
+                look_direction: Some((30, 0)),
+                distance: distance(1300, 420.0, true),
+            }]
+        );
+
+        // 90 degrees at 240 degrees per second
+        assert_eq!(run.step(&snapshot(1674)), moves(&[]));
+        assert_eq!(run.step(&snapshot(1675)), Step::Done);
+
+        let progress = handle.progress();
+        assert_eq!(progress.status, MissionStatus::Finished);
+        assert_eq!(progress.step, 5);
+    }
+
+    #[test]
+    fn step_ends_when_condition_holds() {
+        let close = Condition::Distance {
+            below_mm: Some(300.0),
+            above_mm: None,
+        };
+        let (mut run, _) = MissionRun::new(mission(vec![
+            forward(Some(5000), Some(close)),
+            MissionStep::Stop,
+        ]));
+
+        assert_eq!(
+            run.step(&snapshot(0)),
+            moves(&[Command::Move(MoveType::Forward(150))])
+        );
+
+        let mut near_wall = snapshot(800);
+        near_wall.distance = distance(800, 250.0, true);
+        assert_eq!(run.step(&near_wall), Step::Done);
+    }
+
+    #[test]
+    fn pause_stops_rover_and_time() {
+        let (mut run, handle) = MissionRun::new(mission(vec![forward(Some(1000), None)]));
+        let forward = Command::Move(MoveType::Forward(150));
+
+        assert_eq!(run.step(&snapshot(0)), moves(&[forward]));
+        assert_eq!(run.step(&snapshot(400)), moves(&[]));
+
+        assert!(handle.pause());
+        assert!(!handle.pause());
+        assert_eq!(
+            run.step(&snapshot(500)),
+            moves(&[Command::Move(MoveType::None)])
+        );
+        assert_eq!(run.step(&snapshot(3000)), moves(&[]));
+        assert_eq!(handle.progress().status, MissionStatus::Paused);
+
+        // motion goes on for the rest of the step
+        assert!(handle.resume());
+        assert_eq!(run.step(&snapshot(5000)), moves(&[forward]));
+        assert_eq!(run.step(&snapshot(5599)), moves(&[]));
+        assert_eq!(run.step(&snapshot(5600)), Step::Done);
+    }
+
+    #[test]
+    fn abort_ends_run() {
+        let (mut run, handle) = MissionRun::new(mission(vec![forward(Some(1000), None)]));
+
+        run.step(&snapshot(0));
+        assert!(handle.abort("Stopped by user."));
+        assert!(!handle.abort("Stopped again."));
+
+        assert_eq!(run.step(&snapshot(100)), Step::Done);
+        assert_eq!(
+            handle.progress().status,
+            MissionStatus::Aborted("Stopped by user.".to_owned())
+        );
+    }
+
+    #[test]
+    fn unmet_expectation_fails_mission() {
+        let (mut run, handle) = MissionRun::new(mission(vec![
+            MissionStep::Stop,
+            MissionStep::Expect {
+                condition: Condition::Obstacle {
+                    position: None,
+                    detected: true,
+                },
+                message: None,
+            },
+        ]));
+
+        let reason = "Step 2 expectation not met.".to_owned();
+        assert_eq!(run.step(&snapshot(0)), Step::Fail(reason.clone()));
+        assert_eq!(handle.progress().status, MissionStatus::Failed(reason));
+    }
+
+    #[test]
+    fn rejects_invalid_missions() {
+        let spin = |degrees, duration_ms| {
+            mission(vec![MissionStep::Spin {
+                direction: SpinDirection::CCW,
+                speed: 100,
+                degrees: Some(degrees),
+                duration_ms,
+                until: None,
+            }])
+        };
+
+        assert!(spin(90.0, None).validate().is_ok());
+        assert_eq!(invalid(&mission(vec![])), "Mission has no steps.");
+
+        for spin_rate in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let spinless = Mission {
+                spin_rate,
+                ..spin(90.0, None)
+            };
+            assert_eq!(invalid(&spinless), "Spin rate must be positive.");
+        }
+
+        let timeless = Mission {
+            period_ms: 0,
+            ..spin(90.0, None)
+        };
+        assert_eq!(invalid(&timeless), "Period must be positive.");
+
+        for degrees in [-90.0, f32::NAN, f32::INFINITY] {
+            assert_eq!(
+                invalid(&spin(degrees, None)),
+                "Step 1 needs positive speed and angle."
+            );
+        }
+        assert_eq!(
+            invalid(&spin(90.0, Some(1000))),
+            "Step 1 has both degrees and duration."
+        );
+
+        let wait = MissionStep::Wait {
+            duration_ms: None,
+            until: None,
+        };
+        assert_eq!(
+            invalid(&mission(vec![MissionStep::Stop, wait])),
+            "Step 2 waits for nothing."
+        );
+    }
+}
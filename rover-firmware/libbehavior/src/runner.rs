@@ -0,0 +1,226 @@
+use async_trait::async_trait;
+use log::{info, trace, warn};
+use tokio::time::MissedTickBehavior;
+
+use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor, MoveType, RoverSnapshot};
+
+use crate::api::{Behavior, Command, Rig, Step};
+use crate::{Error, Result};
+
+fn to_rover_err<T: std::error::Error>(e: T) -> Error {
+    Error::Rover(e.to_string())
+}
+
+/// Sets the rover in given motion.
+pub async fn perform_move<T>(
+    mover: &mut T,
+    move_type: MoveType,
+) -> std::result::Result<(), T::Error>
+where
+    T: AsyncMover + Send + ?Sized,
+{
+    match move_type {
+        MoveType::Forward(speed) => mover.move_forward(speed).await,
+        MoveType::Backward(speed) => mover.move_backward(speed).await,
+        MoveType::SpinCW(speed) => mover.spin_right(speed).await,
+        MoveType::SpinCCW(speed) => mover.spin_left(speed).await,
+        MoveType::Drive(left, right) => mover.drive(left, right).await,
+        MoveType::None => mover.stop().await,
+    }
+}
+
+/// Runs the behavior until it is done, then stops the rover. The rover is stopped on errors
+/// too, but not if the returned future is dropped.
+pub async fn run(behavior: &mut dyn Behavior, rig: &mut dyn Rig) -> Result<()> {
+    info!("[{}] Behavior started.", behavior.name());
+
+    let mut interval = tokio::time::interval(behavior.period());
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let result = async {
+        loop {
+            interval.tick().await;
+
+            let snapshot = rig.snapshot().await?;
+
+            match behavior.step(&snapshot) {
+                Step::Continue(commands) => {
+                    for command in commands {
+                        trace!("[{}] Performing {:?}.", behavior.name(), command);
+
+                        rig.perform(command).await?;
+                    }
+                }
+                Step::Done => break Ok(()),
+                Step::Fail(reason) => break Err(Error::Failed(reason)),
+            }
+        }
+    }
+    .await;
+
+    if let Err(e) = rig.perform(Command::Move(MoveType::None)).await {
+        warn!("[{}] Failed to stop the rover: {}", behavior.name(), e);
+    }
+
+    match result {
+        Ok(_) => info!("[{}] Behavior finished.", behavior.name()),
+        Err(ref e) => warn!("[{}] Behavior failed: {}", behavior.name(), e),
+    }
+
+    result
+}
+
+/// Rig over rover controls owned by it, e.g. a simulated rover.
+pub struct Controls<TMover, TLooker, TSensor> {
+    pub mover: TMover,
+    pub looker: Option<TLooker>,
+    pub sensor: TSensor,
+}
+
+#[async_trait]
+impl<TMover, TLooker, TSensor> Rig for Controls<TMover, TLooker, TSensor>
+where
+    TMover: AsyncMover + Send + Sync,
+    TLooker: AsyncLooker + Send + Sync,
+    TSensor: AsyncSensor + Send + Sync,
+{
+    async fn snapshot(&mut self) -> Result<RoverSnapshot> {
+        let mut snapshot = self.sensor.snapshot().await.map_err(to_rover_err)?;
+
+        if snapshot.move_type.is_none() {
+            snapshot.move_type = Some(self.mover.get_move_type().await.map_err(to_rover_err)?);
+        }
+
+        if let (None, Some(looker)) = (snapshot.look_direction, &self.looker) {
+            snapshot.look_direction =
+                Some(looker.get_look_direction().await.map_err(to_rover_err)?);
+        }
+
+        Ok(snapshot)
+    }
+
+    async fn perform(&mut self, command: Command) -> Result<()> {
+        match command {
+            Command::Move(move_type) => perform_move(&mut self.mover, move_type)
+                .await
+                .map_err(to_rover_err),
+            Command::LookAt(h, v) => match self.looker {
+                Some(ref mut looker) => looker.look_at(h, v).await.map_err(to_rover_err),
+                None => Err(Error::Unsupported("Rover cannot look around.".to_owned())),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use libdriver::util::a_sync::AsyncRover;
+    use libdriver_sim::{Point, Pose, Segment, SimRover, World};
+
+    use crate::wander::{Wander, WanderConfig};
+
+    use super::*;
+
+    /// Rig passing everything on to another one, keeping what it saw and did.
+    struct Recorder<R> {
+        rig: R,
+        snapshots: Vec<RoverSnapshot>,
+        commands: Vec<Command>,
+    }
+
+    #[async_trait]
+    impl<R: Rig> Rig for Recorder<R> {
+        async fn snapshot(&mut self) -> Result<RoverSnapshot> {
+            let snapshot = self.rig.snapshot().await?;
+            self.snapshots.push(snapshot.clone());
+
+            Ok(snapshot)
+        }
+
+        async fn perform(&mut self, command: Command) -> Result<()> {
+            self.commands.push(command);
+
+            self.rig.perform(command).await
+        }
+    }
+
+    /// Square room of 2 m, the rover facing its right wall half a meter away.
+    fn room() -> SimRover {
+        let corners = [(0.0, 0.0), (2000.0, 0.0), (2000.0, 2000.0), (0.0, 2000.0)];
+        let walls = (0..corners.len())
+            .map(|i| {
+                let ((ax, ay), (bx, by)) = (corners[i], corners[(i + 1) % corners.len()]);
+                Segment {
+                    from: Point::new(ax, ay),
+                    to: Point::new(bx, by),
+                }
+            })
+            .collect();
+
+        SimRover::new(World {
+            start: Pose {
+                x: 1410.0,
+                y: 1000.0,
+                heading: 0.0,
+            },
+            walls,
+            ..World::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn wander_turns_away_from_wall() {
+        let rover = AsyncRover::from(room());
+        let mut rig = Recorder {
+            rig: Controls {
+                mover: rover.clone(),
+                looker: Some(rover.clone()),
+                sensor: rover,
+            },
+            snapshots: vec![],
+            commands: vec![],
+        };
+        let mut wander = Wander::new(WanderConfig {
+            speed: 255,
+            back_off_ms: 300,
+            scan_angles: vec![-60, 0, 60],
+            settle_ms: 100,
+            spin_ms_per_degree: 2.0,
+            period_ms: 20,
+            ..WanderConfig::default()
+        });
+
+        let result = tokio::time::timeout(Duration::from_secs(4), run(&mut wander, &mut rig)).await;
+        assert!(result.is_err(), "wander never finishes on its own");
+
+        let looking_ahead: Vec<_> = rig
+            .snapshots
+            .iter()
+            .filter(|s| s.look_direction == Some((0, 0)))
+            .map(|s| s.distance.distance.0)
+            .collect();
+        assert!(looking_ahead
+            .first()
+            .is_some_and(|&d| (d - 500.0).abs() < 20.0));
+        assert!(
+            looking_ahead.iter().all(|&d| d > 200.0),
+            "{:?}",
+            looking_ahead
+        );
+
+        let position = |command: Command| rig.commands.iter().position(|&c| c == command);
+        let backed_off = position(Command::Move(MoveType::Backward(255))).unwrap();
+        let scanned = position(Command::LookAt(60, 0)).unwrap();
+        assert!(backed_off < scanned);
+        let turned = scanned
+            + rig.commands[scanned..]
+                .iter()
+                .position(|c| {
+                    matches!(c, Command::Move(MoveType::SpinCW(_) | MoveType::SpinCCW(_)))
+                })
+                .unwrap();
+        assert!(rig.commands[turned..].contains(&Command::Move(MoveType::Forward(255))));
+    }
+}
@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use libdriver::api::{MoveType, RoverSnapshot};
+
+use crate::api::{positive, snapshot_time, Behavior, Command, Step};
+
+// heading change when there is no looker to find the clearest one with, in degrees
+const BLIND_TURN_DEGREES: i16 = 90;
+
+// heading change when nothing is clear around, in degrees
+const TURN_AROUND_DEGREES: i16 = 180;
+
+/// Parameters of the wander behavior. Distances are in mm, angles in degrees, positive ones to
+/// the left.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct WanderConfig {
+    pub speed: u8,
+    pub turn_speed: u8,
+
+    /// Sonar distance ahead at which the way is considered blocked.
+    pub min_distance: f32,
+    /// How long to back off from an obstacle.
+    pub back_off_ms: u64,
+
+    /// Looker pan angles swept to find the clearest heading.
+    pub scan_angles: Vec<i16>,
+    /// Time for the looker to turn before the sonar is read.
+    pub settle_ms: u64,
+
+    /// Spinning time per degree of heading change at `turn_speed`.
+    pub spin_ms_per_degree: f32,
+
+    pub period_ms: u64,
+}
+
+impl Default for WanderConfig {
+    fn default() -> Self {
+        WanderConfig {
+            speed: 150,
+            turn_speed: 150,
+            min_distance: 300.0,
+            back_off_ms: 600,
+            scan_angles: vec![-60, -30, 0, 30, 60],
+            settle_ms: 300,
+            spin_ms_per_degree: 5.0,
+            period_ms: 100,
+        }
+    }
+}
+
+impl WanderConfig {
+    pub(crate) fn problem(&self) -> Option<&'static str> {
+        if self.period_ms == 0 {
+            Some("Period must be positive.")
+        } else if self.speed == 0 || self.turn_speed == 0 {
+            Some("Speeds must be positive.")
+        } else if !positive(self.min_distance) {
+            Some("Minimum distance must be positive.")
+        } else if !positive(self.spin_ms_per_degree) {
+            Some("Spin time per degree must be positive.")
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum State {
+    Start,
+    Cruise,
+    BackOff {
+        until: u64,
+    },
+    Scan {
+        index: usize,
+        since: u64,
+        best: Option<(i16, f32)>,
+    },
+    Turn {
+        until: u64,
+    },
+}
+
+/// Drives forward until the way is blocked, then backs off, sweeps the looker to find the
+/// clearest heading and turns to it. Never finishes on its own.
+pub struct Wander {
+    config: WanderConfig,
+    state: State,
+
+    /// Direction of the next turn made without the looker.
+    blind_turn: i16,
+}
+
+impl Wander {
+    pub fn new(config: WanderConfig) -> Wander {
+        Wander {
+            config,
+            state: State::Start,
+            blind_turn: BLIND_TURN_DEGREES,
+        }
+    }
+
+    fn blocked(&self, snapshot: &RoverSnapshot) -> bool {
+        // sonar readings taken while looking aside tell nothing about the way ahead
+        let looking_ahead = snapshot.look_direction.is_none_or(|(h, _)| h == 0);
+
+        snapshot.obstacles.detections.iter().any(|d| d.detected)
+            || (looking_ahead
+                && snapshot.distance.valid
+                && snapshot.distance.distance.0 < self.config.min_distance)
+    }
+
+    fn look_ahead(snapshot: &RoverSnapshot, commands: &mut Vec<Command>) {
+        if snapshot.look_direction.is_some_and(|direction| direction != (0, 0)) {
+            commands.push(Command::LookAt(0, 0));
+        }
+    }
+
+    fn cruise(&mut self, snapshot: &RoverSnapshot) -> Vec<Command> {
+        self.state = State::Cruise;
+
+        let mut commands = vec![Command::Move(MoveType::Forward(self.config.speed))];
+        Self::look_ahead(snapshot, &mut commands);
+
+        commands
+    }
+
+    fn turn(&mut self, snapshot: &RoverSnapshot, degrees: i16) -> Vec<Command> {
+        if degrees == 0 {
+            return self.cruise(snapshot);
+        }
+
+        let spin_ms = (degrees.unsigned_abs() as f32 * self.config.spin_ms_per_degree) as u64;
+        self.state = State::Turn {
+            until: snapshot_time(snapshot) + spin_ms,
+        };
+
+        let spin = if degrees > 0 {
+            MoveType::SpinCCW(self.config.turn_speed)
+        } else {
+            MoveType::SpinCW(self.config.turn_speed)
+        };
+
+        let mut commands = vec![Command::Move(spin)];
+        Self::look_ahead(snapshot, &mut commands);
+
+        commands
+    }
+}
+
+impl Behavior for Wander {
+    fn name(&self) -> &str {
+        "wander"
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_millis(self.config.period_ms)
+    }
+
+    fn step(&mut self, snapshot: &RoverSnapshot) -> Step {
+        let now = snapshot_time(snapshot);
+
+        let commands = match self.state {
+            State::Start => self.cruise(snapshot),
+            State::Cruise if self.blocked(snapshot) => {
+                self.state = State::BackOff {
+                    until: now + self.config.back_off_ms,
+                };
+
+                vec![Command::Move(MoveType::Backward(self.config.speed))]
+            }
+            State::Cruise => vec![],
+            State::BackOff { until } if now < until => vec![],
+            State::BackOff { .. } => match self.config.scan_angles.first() {
+                Some(&angle) if snapshot.look_direction.is_some() => {
+                    self.state = State::Scan {
+                        index: 0,
+                        since: now,
+                        best: None,
+                    };
+
+                    vec![Command::Move(MoveType::None), Command::LookAt(angle, 0)]
+                }
+                _ => {
+                    let degrees = self.blind_turn;
+                    self.blind_turn = -degrees;
+
+                    self.turn(snapshot, degrees)
+                }
+            },
+            State::Scan { index, since, best } => {
+                let angle = self.config.scan_angles[index];
+                let settled = snapshot.look_direction.is_some_and(|(h, _)| h == angle)
+                    && snapshot.distance.timestamp.0 >= since + self.config.settle_ms;
+
+                if !settled {
+                    vec![]
+                } else {
+                    // invalid readings carry the sonar range, which is as clear as it gets
+                    let distance = snapshot.distance.distance.0;
+                    let best = match best {
+                        Some((_, best_distance)) if best_distance >= distance => best,
+                        _ => Some((angle, distance)),
+                    };
+
+                    match self.config.scan_angles.get(index + 1) {
+                        Some(&next) => {
+                            self.state = State::Scan {
+                                index: index + 1,
+                                since: now,
+                                best,
+                            };
+
+                            vec![Command::LookAt(next, 0)]
+                        }
+                        None => {
+                            let degrees = match best {
+                                Some((angle, distance)) if distance >= self.config.min_distance => {
+                                    angle
+                                }
+                                _ => TURN_AROUND_DEGREES,
+                            };
+
+                            self.turn(snapshot, degrees)
+                        }
+                    }
+                }
+            }
+            State::Turn { until } if now < until => vec![],
+            State::Turn { .. } => self.cruise(snapshot),
+        };
+
+        Step::Continue(commands)
+    }
+}
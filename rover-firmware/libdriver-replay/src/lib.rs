@@ -0,0 +1,91 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error as IOError};
+use std::path::Path;
+
+use thiserror::Error as LibError;
+
+use libdriver::session::{DriverCall, Session};
+
+pub use replay::{ReplayConfig, ReplayRover};
+
+mod replay;
+
+/// Command that did not go as recorded.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mismatch {
+    /// Session time the command was received at, in ms.
+    pub time_ms: u64,
+
+    /// Next recorded command, absent if the recording has no commands left.
+    pub expected: Option<DriverCall>,
+    pub actual: DriverCall,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.expected {
+            Some(expected) => write!(
+                f,
+                "{:?} at {} ms while {:?} was recorded next",
+                self.actual, self.time_ms, expected
+            ),
+            None => write!(
+                f,
+                "{:?} at {} ms after all recorded commands",
+                self.actual, self.time_ms
+            ),
+        }
+    }
+}
+
+#[derive(Debug, LibError)]
+pub enum Error {
+    #[error("Input/output error: {0:?}")]
+    IO(#[from] IOError),
+
+    #[error("Invalid session file: {0}")]
+    Cbor(#[from] serde_cbor::Error),
+
+    #[error("Invalid session file: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Nothing recorded for {0:?}")]
+    NotRecorded(DriverCall),
+
+    #[error("Recorded failure: {0}")]
+    Recorded(String),
+
+    #[error("Unexpected command {0}")]
+    Mismatch(Mismatch),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn is_json<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref().extension().is_some_and(|ext| ext == "json")
+}
+
+/// Reads session file, JSON if it has `.json` extension, CBOR otherwise.
+pub fn load_session<P: AsRef<Path>>(path: P) -> Result<Session> {
+    let reader = BufReader::new(File::open(&path)?);
+
+    if is_json(&path) {
+        Ok(serde_json::from_reader(reader)?)
+    } else {
+        Ok(serde_cbor::from_reader(reader)?)
+    }
+}
+
+/// Writes session file, JSON if it has `.json` extension, CBOR otherwise.
+pub fn save_session<P: AsRef<Path>>(session: &Session, path: P) -> Result<()> {
+    let writer = BufWriter::new(File::create(&path)?);
+
+    if is_json(&path) {
+        serde_json::to_writer_pretty(writer, session)?;
+    } else {
+        serde_cbor::to_writer(writer, session)?;
+    }
+
+    Ok(())
+}
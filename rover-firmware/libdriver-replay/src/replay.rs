@@ -0,0 +1,487 @@
+use std::cell::Cell;
+use std::path::Path;
+use std::time::Instant;
+
+use log::warn;
+use serde::Deserialize;
+
+use libdriver::api::{
+    DetectionReading, DistanceReading, LookLimits, MoveType, RoverSnapshot, SensorDescriptor,
+};
+use libdriver::session::{CallRecord, DriverCall, DriverReply, Session};
+use libdriver::{api, util};
+
+use crate::{load_session, Error, Mismatch, Result};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReplayConfig {
+    /// Fail commands that do not match the recording instead of only noting them.
+    pub strict: bool,
+
+    /// How much faster than recorded the session plays back.
+    pub speed: f32,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        ReplayConfig {
+            strict: true,
+            speed: 1.0,
+        }
+    }
+}
+
+/// Rover answering sensor calls from a recorded session and checking that it is commanded the
+/// same way as recorded.
+///
+/// Session time starts with the first call to the rover and runs at configured speed. Sensor
+/// calls get the last reply recorded for them by current session time. Each command is matched
+/// against the next recorded one and brings session time forward to the time it was recorded at,
+/// so that a slower client sees the same readings the recorded one did.
+pub struct ReplayRover {
+    config: ReplayConfig,
+    commands: Vec<CallRecord>,
+    readings: Vec<CallRecord>,
+    next_command: usize,
+    /// Session time and the moment it was reached at, unset until the first call.
+    clock: Cell<(u64, Option<Instant>)>,
+    mismatches: Vec<Mismatch>,
+    move_type: MoveType,
+    look_direction: (i16, i16),
+}
+
+impl ReplayRover {
+    pub fn new(session: Session, config: ReplayConfig) -> ReplayRover {
+        let mut calls = session.calls;
+        calls.sort_by_key(|record| record.time_ms);
+
+        let (commands, readings) = calls
+            .into_iter()
+            .partition(|record| record.call.is_command());
+
+        ReplayRover {
+            config,
+            commands,
+            readings,
+            next_command: 0,
+            clock: Cell::new((0, None)),
+            mismatches: vec![],
+            move_type: MoveType::None,
+            look_direction: (0, 0),
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(session_path: P, config: ReplayConfig) -> Result<ReplayRover> {
+        Ok(ReplayRover::new(load_session(session_path)?, config))
+    }
+
+    /// Current session time, in ms.
+    pub fn session_time(&self) -> u64 {
+        let now = Instant::now();
+
+        match self.clock.get() {
+            (time_ms, Some(since)) => {
+                let elapsed_ms = now.duration_since(since).as_secs_f32() * 1000.0;
+                time_ms + (elapsed_ms * self.config.speed) as u64
+            }
+            (time_ms, None) => {
+                self.clock.set((time_ms, Some(now)));
+                time_ms
+            }
+        }
+    }
+
+    /// Commands that did not match the recording so far, including the ones failed in strict mode.
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.mismatches
+    }
+
+    /// Whether all recorded commands were received and session time is past the last call.
+    pub fn is_finished(&self) -> bool {
+        let end_ms = self
+            .commands
+            .iter()
+            .chain(self.readings.iter())
+            .map(|record| record.time_ms)
+            .max()
+            .unwrap_or(0);
+
+        self.next_command >= self.commands.len() && self.session_time() >= end_ms
+    }
+
+    fn advance_clock(&self, time_ms: u64) {
+        let now_ms = self.session_time();
+        self.clock.set((now_ms.max(time_ms), Some(Instant::now())));
+    }
+
+    /// Matches command against the recording, returns the recorded outcome of it.
+    fn command(&mut self, call: DriverCall) -> Result<()> {
+        let time_ms = self.session_time();
+        let expected = self.commands.get(self.next_command);
+
+        if let Some(record) = expected.filter(|record| record.call == call) {
+            let (recorded_ms, result) = (record.time_ms, record.result.clone());
+
+            self.next_command += 1;
+            self.advance_clock(recorded_ms);
+
+            return result.map(|_| ()).map_err(Error::Recorded);
+        }
+
+        let mismatch = Mismatch {
+            time_ms,
+            expected: expected.map(|record| record.call),
+            actual: call,
+        };
+
+        self.mismatches.push(mismatch.clone());
+
+        if self.config.strict {
+            return Err(Error::Mismatch(mismatch));
+        }
+
+        warn!("Unexpected command {}.", mismatch);
+
+        // catch up if the command comes later in the recording, some were missed then
+        let skipped = self.commands[self.next_command..]
+            .iter()
+            .position(|record| record.call == call);
+
+        if let Some(skipped) = skipped {
+            self.next_command += skipped + 1;
+            self.advance_clock(self.commands[self.next_command - 1].time_ms);
+        }
+
+        Ok(())
+    }
+
+    /// Recorded reply to the call, the last one by current session time or the first one if the
+    /// call was not made yet by then.
+    fn reading(&self, call: DriverCall) -> Result<DriverReply> {
+        let time_ms = self.session_time();
+        let now = self
+            .readings
+            .partition_point(|record| record.time_ms <= time_ms);
+
+        let record = self.readings[..now]
+            .iter()
+            .rev()
+            .find(|record| record.call == call)
+            .or_else(|| {
+                self.readings[now..]
+                    .iter()
+                    .find(|record| record.call == call)
+            })
+            .ok_or(Error::NotRecorded(call))?;
+
+        record.result.clone().map_err(Error::Recorded)
+    }
+
+    fn unexpected_reply(call: DriverCall, reply: DriverReply) -> Error {
+        Error::Recorded(format!("{:?} replied to {:?}", reply, call))
+    }
+}
+
+impl api::Mover for ReplayRover {
+    type Error = Error;
+
+    fn stop(&mut self) -> Result<()> {
+        self.command(DriverCall::Stop)?;
+        self.move_type = MoveType::None;
+
+        Ok(())
+    }
+
+    fn move_forward(&mut self, speed: u8) -> Result<()> {
+        self.command(DriverCall::MoveForward(speed))?;
+        self.move_type = MoveType::Forward(speed);
+
+        Ok(())
+    }
+
+    fn move_backward(&mut self, speed: u8) -> Result<()> {
+        self.command(DriverCall::MoveBackward(speed))?;
+        self.move_type = MoveType::Backward(speed);
+
+        Ok(())
+    }
+
+    fn spin_right(&mut self, speed: u8) -> Result<()> {
+        self.command(DriverCall::SpinRight(speed))?;
+        self.move_type = MoveType::SpinCW(speed);
+
+        Ok(())
+    }
+
+    fn spin_left(&mut self, speed: u8) -> Result<()> {
+        self.command(DriverCall::SpinLeft(speed))?;
+        self.move_type = MoveType::SpinCCW(speed);
+
+        Ok(())
+    }
+
+    fn drive(&mut self, left: i16, right: i16) -> Result<()> {
+        self.command(DriverCall::Drive(left, right))?;
+        self.move_type = MoveType::Drive(left, right);
+
+        Ok(())
+    }
+
+    fn get_move_type(&self) -> Result<MoveType> {
+        Ok(self.move_type)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.move_type = MoveType::None;
+
+        Ok(())
+    }
+}
+
+impl api::Looker for ReplayRover {
+    type Error = Error;
+
+    fn look_at(&mut self, h: i16, v: i16) -> Result<()> {
+        self.command(DriverCall::LookAt(h, v))?;
+        self.look_direction = (h, v);
+
+        Ok(())
+    }
+
+    fn get_look_direction(&self) -> Result<(i16, i16)> {
+        Ok(self.look_direction)
+    }
+
+    fn get_look_limits(&self) -> Result<LookLimits> {
+        match self.reading(DriverCall::GetLookLimits)? {
+            DriverReply::LookLimits(limits) => Ok(limits),
+            reply => Err(Self::unexpected_reply(DriverCall::GetLookLimits, reply)),
+        }
+    }
+}
+
+impl api::Sensor for ReplayRover {
+    type Error = Error;
+
+    fn get_sensors(&self) -> Result<Vec<SensorDescriptor>> {
+        match self.reading(DriverCall::GetSensors)? {
+            DriverReply::Sensors(sensors) => Ok(sensors),
+            reply => Err(Self::unexpected_reply(DriverCall::GetSensors, reply)),
+        }
+    }
+
+    fn get_obstacles(&self) -> Result<DetectionReading> {
+        match self.reading(DriverCall::GetObstacles)? {
+            DriverReply::Detections(reading) => Ok(reading),
+            reply => Err(Self::unexpected_reply(DriverCall::GetObstacles, reply)),
+        }
+    }
+
+    fn get_lines(&self) -> Result<DetectionReading> {
+        match self.reading(DriverCall::GetLines)? {
+            DriverReply::Detections(reading) => Ok(reading),
+            reply => Err(Self::unexpected_reply(DriverCall::GetLines, reply)),
+        }
+    }
+
+    fn scan_distance(&mut self) -> Result<DistanceReading> {
+        match self.reading(DriverCall::ScanDistance)? {
+            DriverReply::Distance(reading) => Ok(reading),
+            reply => Err(Self::unexpected_reply(DriverCall::ScanDistance, reply)),
+        }
+    }
+
+    fn snapshot(&mut self) -> Result<RoverSnapshot> {
+        match self.reading(DriverCall::Snapshot) {
+            Ok(DriverReply::Snapshot(snapshot)) => Ok(snapshot),
+            Ok(reply) => Err(Self::unexpected_reply(DriverCall::Snapshot, reply)),
+            // sessions recorded by callers reading sensors one by one
+            Err(Error::NotRecorded(_)) => Ok(RoverSnapshot {
+                obstacles: self.get_obstacles()?,
+                lines: self.get_lines()?,
+                distance: self.scan_distance()?,
+                move_type: Some(self.move_type),
+                look_direction: Some(self.look_direction),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl util::splittable::SplittableRover for ReplayRover {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use libdriver::api::{Millimeters, Mover, Sensor, Timestamp};
+
+    use crate::save_session;
+
+    use super::*;
+
+    fn record(
+        time_ms: u64,
+        call: DriverCall,
+        result: std::result::Result<DriverReply, String>,
+    ) -> CallRecord {
+        CallRecord {
+            time_ms,
+            call,
+            result,
+        }
+    }
+
+    fn distance(time_ms: u64, distance: f32) -> CallRecord {
+        let reading = DistanceReading {
+            timestamp: Timestamp(time_ms),
+            distance: Millimeters(distance),
+            valid: true,
+        };
+
+        record(
+            time_ms,
+            DriverCall::ScanDistance,
+            Ok(DriverReply::Distance(reading)),
+        )
+    }
+
+    /// Client driving forward until an obstacle comes close, then backing off from it.
+    fn session() -> Session {
+        Session {
+            calls: vec![
+                distance(0, 800.0),
+                record(10, DriverCall::MoveForward(150), Ok(DriverReply::Done)),
+                distance(500, 250.0),
+                record(520, DriverCall::MoveBackward(150), Ok(DriverReply::Done)),
+                distance(900, 400.0),
+                record(910, DriverCall::Stop, Ok(DriverReply::Done)),
+            ],
+        }
+    }
+
+    fn replay(strict: bool) -> ReplayRover {
+        ReplayRover::new(
+            session(),
+            ReplayConfig {
+                strict,
+                ..ReplayConfig::default()
+            },
+        )
+    }
+
+    fn scan(rover: &mut ReplayRover) -> f32 {
+        rover.scan_distance().unwrap().distance.0
+    }
+
+    /// Plays back the recorded client, which must see what it saw when recorded.
+    fn drive_as_recorded(rover: &mut ReplayRover) {
+        assert_eq!(scan(rover), 800.0);
+        rover.move_forward(150).unwrap();
+        rover.move_backward(150).unwrap();
+        assert_eq!(scan(rover), 250.0);
+        rover.stop().unwrap();
+    }
+
+    #[test]
+    fn replays_recorded_session() {
+        let mut rover = replay(true);
+
+        drive_as_recorded(&mut rover);
+
+        assert!(rover.mismatches().is_empty());
+        assert!(rover.session_time() >= 910);
+        assert!(rover.is_finished());
+        assert_eq!(rover.get_move_type().unwrap(), MoveType::None);
+    }
+
+    #[test]
+    fn replays_saved_session() {
+        for extension in ["json", "cbor"] {
+            let path = std::env::temp_dir().join(format!(
+                "replay-session-{}.{}",
+                std::process::id(),
+                extension
+            ));
+            save_session(&session(), &path).unwrap();
+
+            let mut rover = ReplayRover::from_file(&path, ReplayConfig::default()).unwrap();
+            fs::remove_file(&path).unwrap();
+
+            drive_as_recorded(&mut rover);
+            assert!(rover.mismatches().is_empty());
+        }
+    }
+
+    #[test]
+    fn strict_replay_fails_unexpected_commands() {
+        let mut rover = replay(true);
+
+        match rover.move_forward(100) {
+            Err(Error::Mismatch(mismatch)) => {
+                assert_eq!(mismatch.expected, Some(DriverCall::MoveForward(150)));
+                assert_eq!(mismatch.actual, DriverCall::MoveForward(100));
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(rover.get_move_type().unwrap(), MoveType::None);
+
+        // the recorded command is still expected next
+        rover.move_forward(150).unwrap();
+        assert_eq!(rover.mismatches().len(), 1);
+        assert!(!rover.is_finished());
+    }
+
+    #[test]
+    fn lenient_replay_catches_up_with_recording() {
+        let mut rover = replay(false);
+
+        // forward command is missed, backward one is further in the recording
+        assert_eq!(scan(&mut rover), 800.0);
+        rover.move_backward(150).unwrap();
+        assert_eq!(scan(&mut rover), 250.0);
+        rover.stop().unwrap();
+        rover.stop().unwrap();
+
+        let mismatches: Vec<_> = rover
+            .mismatches()
+            .iter()
+            .map(|mismatch| (mismatch.expected, mismatch.actual))
+            .collect();
+        assert_eq!(
+            mismatches,
+            [
+                (
+                    Some(DriverCall::MoveForward(150)),
+                    DriverCall::MoveBackward(150)
+                ),
+                (None, DriverCall::Stop),
+            ]
+        );
+        assert!(rover.is_finished());
+    }
+
+    #[test]
+    fn replays_recorded_failures() {
+        let mut rover = ReplayRover::new(
+            Session {
+                calls: vec![record(
+                    0,
+                    DriverCall::MoveForward(150),
+                    Err("Motor failure.".to_owned()),
+                )],
+            },
+            ReplayConfig::default(),
+        );
+
+        assert!(matches!(
+            rover.move_forward(150),
+            Err(Error::Recorded(message)) if message == "Motor failure."
+        ));
+        assert!(matches!(
+            rover.get_lines(),
+            Err(Error::NotRecorded(DriverCall::GetLines))
+        ));
+    }
+}
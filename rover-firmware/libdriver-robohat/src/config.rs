@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use rppal::pwm::Channel;
+use serde::{Deserialize, Serialize};
+
+use crate::motor::MotorPwm;
+use crate::servo::ServoDriver;
+use crate::sonar::SonarConfig;
+use crate::{Error, Result};
+
+/// Board wiring and calibration of a Robohat rover.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RobohatConfig {
+    pub pins: PinConfig,
+    pub motor_pwm: MotorPwm,
+
+    /// Swaps forward and backward of a motor wired in reverse.
+    pub invert_left_motor: bool,
+    pub invert_right_motor: bool,
+
+    pub sonar: SonarConfig,
+
+    pub servo: ServoDriver,
+    pub pan: ServoCalibration,
+    pub tilt: ServoCalibration,
+
+    /// Servo calibration file written by `ux-console calibrate`, overrides `pan` and `tilt`.
+    pub calibration: Option<String>,
+}
+
+impl Default for RobohatConfig {
+    fn default() -> Self {
+        RobohatConfig {
+            pins: PinConfig::default(),
+            motor_pwm: MotorPwm::default(),
+            invert_left_motor: false,
+            invert_right_motor: false,
+            sonar: SonarConfig::default(),
+            servo: ServoDriver::default(),
+            pan: ServoCalibration::pan(),
+            tilt: ServoCalibration::tilt(),
+            calibration: None,
+        }
+    }
+}
+
+/// GPIO pins in BCM numbering.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PinConfig {
+    pub ir_left: u8,
+    pub ir_right: u8,
+    pub line_left: u8,
+    pub line_right: u8,
+    pub sonar: u8,
+
+    /// H-bridge inputs of the motors. With hardware PWM the second input of each motor has to
+    /// be routed to a PWM channel.
+    pub motor_left: (u8, u8),
+    pub motor_right: (u8, u8),
+}
+
+impl Default for PinConfig {
+    fn default() -> Self {
+        PinConfig {
+            ir_left: 4,
+            ir_right: 17,
+            line_left: 5,
+            line_right: 27,
+            sonar: 20,
+            motor_left: (16, 19),
+            motor_right: (13, 12),
+        }
+    }
+}
+
+/// Servo position and the pulse width it is reached with, in degrees and us.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ServoPoint {
+    pub degrees: i16,
+    pub pulse_width: i16,
+}
+
+/// Servo endpoints and center, positions outside of endpoints are cut. Servos mounted in reverse
+/// have `max` pulse width below the `min` one.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ServoCalibration {
+    pub min: ServoPoint,
+    pub max: ServoPoint,
+    pub center: ServoPoint,
+}
+
+impl ServoCalibration {
+    pub fn pan() -> ServoCalibration {
+        ServoCalibration {
+            min: ServoPoint { degrees: -90, pulse_width: 550 },
+            max: ServoPoint { degrees: 90, pulse_width: 2200 },
+            center: ServoPoint { degrees: 0, pulse_width: 1380 },
+        }
+    }
+
+    pub fn tilt() -> ServoCalibration {
+        ServoCalibration {
+            min: ServoPoint { degrees: -90, pulse_width: 650 },
+            max: ServoPoint { degrees: 80, pulse_width: 2100 },
+            center: ServoPoint { degrees: 0, pulse_width: 1380 },
+        }
+    }
+
+    pub fn limits(&self) -> (i16, i16) {
+        (self.min.degrees, self.max.degrees)
+    }
+
+    pub fn pulse_width(&self, degrees: i16) -> i16 {
+        let deg_span = self.max.degrees - self.min.degrees;
+
+        let pw_lo = self.min.pulse_width.min(self.max.pulse_width);
+        let pw_hi = self.min.pulse_width.max(self.max.pulse_width);
+        let pw_span = self.max.pulse_width - self.min.pulse_width;
+
+        let cvt_coef = pw_span as f32 / deg_span as f32;
+
+        let pw = self.center.pulse_width as f32 + ((degrees - self.center.degrees) as f32 * cvt_coef);
+
+        if pw > pw_hi as f32 {
+            pw_hi
+        } else if pw < pw_lo as f32 {
+            pw_lo
+        } else {
+            pw.round() as i16
+        }
+    }
+
+    fn validate(&self, servo: &str) -> Result<()> {
+        if !(self.min.degrees < self.center.degrees && self.center.degrees < self.max.degrees) {
+            return Err(Error::Config(format!(
+                "{} center must lie between its endpoints.",
+                servo
+            )));
+        }
+
+        if [self.min, self.max, self.center].iter().any(|p| p.pulse_width <= 0) {
+            return Err(Error::Config(format!(
+                "{} pulse widths must be positive.",
+                servo
+            )));
+        }
+
+        let pw_lo = self.min.pulse_width.min(self.max.pulse_width);
+        let pw_hi = self.min.pulse_width.max(self.max.pulse_width);
+        if !(pw_lo < self.center.pulse_width && self.center.pulse_width < pw_hi) {
+            return Err(Error::Config(format!(
+                "{} center pulse width must lie between endpoint ones.",
+                servo
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Servo calibration table as stored in a calibration file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub pan: ServoCalibration,
+    pub tilt: ServoCalibration,
+}
+
+impl Calibration {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Calibration> {
+        let path = path.as_ref();
+        let table = fs::read_to_string(path)?;
+
+        toml::from_str(&table)
+            .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.pan.validate("Pan")?;
+        self.tilt.validate("Tilt")
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let table = toml::to_string(self).map_err(|e| Error::Config(e.to_string()))?;
+
+        fs::write(path, table)?;
+
+        Ok(())
+    }
+}
+
+/// Hardware PWM channel a pin can be routed to.
+pub(crate) fn pwm_channel(pin: u8) -> Option<Channel> {
+    match pin {
+        12 | 18 => Some(Channel::Pwm0),
+        13 | 19 => Some(Channel::Pwm1),
+        _ => None,
+    }
+}
+
+impl RobohatConfig {
+    /// Replaces servo calibration with the one from calibration file, if it is set.
+    pub fn load_calibration(&mut self) -> Result<()> {
+        if let Some(path) = &self.calibration {
+            let calibration = Calibration::load(path)?;
+
+            self.pan = calibration.pan;
+            self.tilt = calibration.tilt;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that no pin is assigned twice and that calibration is consistent.
+    pub fn validate(&self) -> Result<()> {
+        let pins = &self.pins;
+        let mut assignments = vec![
+            ("left IR sensor", pins.ir_left),
+            ("right IR sensor", pins.ir_right),
+            ("left line sensor", pins.line_left),
+            ("right line sensor", pins.line_right),
+            ("sonar", pins.sonar),
+            ("left motor input 1", pins.motor_left.0),
+            ("left motor input 2", pins.motor_left.1),
+            ("right motor input 1", pins.motor_right.0),
+            ("right motor input 2", pins.motor_right.1),
+        ];
+
+        if let ServoDriver::Gpio { pan, tilt } = self.servo {
+            assignments.push(("pan servo", pan));
+            assignments.push(("tilt servo", tilt));
+        }
+
+        let mut used: HashMap<u8, &str> = HashMap::new();
+        for (function, pin) in assignments {
+            if let Some(other) = used.insert(pin, function) {
+                return Err(Error::Config(format!(
+                    "GPIO{} is assigned to both {} and {}.",
+                    pin, other, function
+                )));
+            }
+        }
+
+        if let MotorPwm::Hardware { .. } = self.motor_pwm {
+            let left = pwm_channel(pins.motor_left.1);
+            let right = pwm_channel(pins.motor_right.1);
+
+            if left.is_none() || right.is_none() {
+                return Err(Error::Config(
+                    "Hardware PWM needs second motor inputs on GPIO12, 13, 18 or 19.".to_string(),
+                ));
+            }
+            if left == right {
+                return Err(Error::Config(
+                    "Hardware PWM needs second motor inputs on different PWM channels.".to_string(),
+                ));
+            }
+        }
+
+        self.pan.validate("Pan")?;
+        self.tilt.validate("Tilt")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(config: &RobohatConfig) -> String {
+        match config.validate() {
+            Err(Error::Config(message)) => message,
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    fn hardware_pwm() -> RobohatConfig {
+        RobohatConfig {
+            motor_pwm: MotorPwm::Hardware { frequency: 1000.0 },
+            ..RobohatConfig::default()
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(RobohatConfig::default().validate().is_ok());
+        assert!(hardware_pwm().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_pin_assigned_twice() {
+        let mut config = RobohatConfig::default();
+        config.pins.sonar = config.pins.ir_left;
+
+        assert_eq!(
+            error(&config),
+            "GPIO4 is assigned to both left IR sensor and sonar."
+        );
+    }
+
+    #[test]
+    fn rejects_servo_pin_used_by_motor() {
+        let config = RobohatConfig {
+            servo: ServoDriver::Gpio { pan: 25, tilt: 12 },
+            ..RobohatConfig::default()
+        };
+
+        assert_eq!(
+            error(&config),
+            "GPIO12 is assigned to both right motor input 2 and tilt servo."
+        );
+    }
+
+    #[test]
+    fn hardware_pwm_needs_pwm_capable_pins() {
+        let mut config = hardware_pwm();
+        config.pins.motor_left = (16, 26);
+
+        assert_eq!(
+            error(&config),
+            "Hardware PWM needs second motor inputs on GPIO12, 13, 18 or 19."
+        );
+
+        // software PWM drives any pin
+        config.motor_pwm = MotorPwm::default();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn hardware_pwm_needs_separate_channels() {
+        let mut config = hardware_pwm();
+        config.pins.motor_left = (16, 18);
+
+        assert_eq!(
+            error(&config),
+            "Hardware PWM needs second motor inputs on different PWM channels."
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_calibration() {
+        let mut config = RobohatConfig::default();
+        config.pan.center.degrees = 100;
+
+        assert_eq!(error(&config), "Pan center must lie between its endpoints.");
+
+        let mut config = RobohatConfig::default();
+        config.tilt.center.pulse_width = 3000;
+
+        assert_eq!(
+            error(&config),
+            "Tilt center pulse width must lie between endpoint ones."
+        );
+    }
+}
@@ -1,13 +1,22 @@
 use anyhow::Error as GenError;
-use libutil::softpwm::Error as PWMError;
+use libutil::pwm::Error as PWMError;
 use rppal::gpio::Error as GPIOError;
 use std::io::Error as IOError;
-use std::time::SystemTimeError;
 use thiserror::Error as LibError;
 
+pub use config::{Calibration, PinConfig, RobohatConfig, ServoCalibration, ServoPoint};
+pub use motor::MotorPwm;
 pub use robohat::RobohatRover;
+pub use servo::{
+    FakeServoController, GpioServos, ServoBlaster, ServoChannels, ServoController, ServoDriver,
+};
+pub use sonar::SonarConfig;
 
+mod config;
+mod motor;
 mod robohat;
+mod servo;
+mod sonar;
 
 #[derive(Debug, LibError)]
 pub enum Error {
@@ -20,11 +29,26 @@ pub enum Error {
     #[error("Input/output error: {0:?}")]
     IO(#[from] IOError),
 
-    #[error("Software PWM error: {0:?}")]
+    #[error("PWM error: {0:?}")]
     PWM(#[from] PWMError),
 
     #[error("GPIO usage error: {0:?}")]
     GPIO(GPIOError),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Servo controller is unavailable: {0}")]
+    ServoUnavailable(String),
+
+    #[error("Servo channel {0} is not configured")]
+    ServoChannel(u8),
+
+    #[error("Sonar echo was not received")]
+    SonarTimeout,
+
+    #[error("Obstacle is out of sonar range")]
+    OutOfRange,
 }
 
 impl From<GPIOError> for Error {
@@ -40,10 +64,4 @@ impl From<GPIOError> for Error {
     }
 }
 
-impl From<SystemTimeError> for Error {
-    fn from(error: SystemTimeError) -> Self {
-        Error::System(GenError::from(error))
-    }
-}
-
 type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,116 @@
+use rppal::gpio::OutputPin;
+use serde::Deserialize;
+
+use libutil::Pwm;
+
+use crate::Result;
+
+/// PWM implementation driving the motors.
+#[derive(Debug, Deserialize, PartialEq, Copy, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MotorPwm {
+    /// Software PWM on every motor pin, frequency is in Hz.
+    Soft { frequency: f32 },
+
+    /// Hardware PWM on the second input of each motor, frequency is in Hz. Needs PWM channels
+    /// routed to those pins, e.g. with `dtoverlay=pwm-2chan,pin=12,func=4,pin2=19,func2=2` for
+    /// the default wiring.
+    Hardware { frequency: f32 },
+}
+
+impl Default for MotorPwm {
+    fn default() -> Self {
+        MotorPwm::Soft { frequency: 10.0 }
+    }
+}
+
+pub(crate) trait Motor: Send {
+    fn set_speed(&mut self, speed: u8, forward: bool) -> Result<()>;
+}
+
+/// Motor wired in reverse.
+pub(crate) struct InvertedMotor {
+    motor: Box<dyn Motor>,
+}
+
+impl InvertedMotor {
+    pub fn new(motor: Box<dyn Motor>) -> InvertedMotor {
+        InvertedMotor { motor }
+    }
+}
+
+impl Motor for InvertedMotor {
+    fn set_speed(&mut self, speed: u8, forward: bool) -> Result<()> {
+        self.motor.set_speed(speed, !forward)
+    }
+}
+
+fn to_duty_cycle(speed: u8) -> f32 {
+    speed as f32 / u8::MAX as f32
+}
+
+/// Motor with both H-bridge inputs modulated.
+pub(crate) struct PwmPairMotor<P: Pwm> {
+    inputs: (P, P),
+}
+
+impl<P: Pwm> PwmPairMotor<P> {
+    pub fn new(input1: P, input2: P) -> PwmPairMotor<P> {
+        PwmPairMotor {
+            inputs: (input1, input2),
+        }
+    }
+}
+
+impl<P: Pwm> Motor for PwmPairMotor<P> {
+    fn set_speed(&mut self, speed: u8, forward: bool) -> Result<()> {
+        let duty_cycle = to_duty_cycle(speed);
+
+        if speed == 0 {
+            self.inputs.0.set_duty_cycle(0.0)?;
+            self.inputs.1.set_duty_cycle(0.0)?;
+        } else if forward {
+            self.inputs.1.set_duty_cycle(0.0)?;
+            self.inputs.0.set_duty_cycle(duty_cycle)?;
+        } else {
+            self.inputs.0.set_duty_cycle(0.0)?;
+            self.inputs.1.set_duty_cycle(duty_cycle)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Motor with second H-bridge input modulated and the first one selecting direction.
+pub(crate) struct PwmDirectionMotor<P: Pwm> {
+    direction: OutputPin,
+    speed: P,
+}
+
+impl<P: Pwm> PwmDirectionMotor<P> {
+    pub fn new(mut direction: OutputPin, speed: P) -> PwmDirectionMotor<P> {
+        direction.set_low();
+
+        PwmDirectionMotor { direction, speed }
+    }
+}
+
+impl<P: Pwm> Motor for PwmDirectionMotor<P> {
+    fn set_speed(&mut self, speed: u8, forward: bool) -> Result<()> {
+        let duty_cycle = to_duty_cycle(speed);
+
+        if speed == 0 {
+            self.speed.set_duty_cycle(0.0)?;
+            self.direction.set_low();
+        } else if forward {
+            // motor is driven while second input is low and brakes while it is high
+            self.direction.set_high();
+            self.speed.set_duty_cycle(1.0 - duty_cycle)?;
+        } else {
+            self.direction.set_low();
+            self.speed.set_duty_cycle(duty_cycle)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,180 +1,118 @@
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::thread;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
-use rppal::gpio::{Gpio, InputPin, IoPin, Level, Mode};
+use rppal::gpio::{Gpio, InputPin, Level};
 
 use libdriver::{api, util};
-use libdriver::api::MoveType;
-use libutil::SoftPwm;
-
+use libdriver::api::{
+    Detection, DetectionReading, DistanceReading, LookLimits, Millimeters, MoveType,
+    RoverSnapshot, SensorDescriptor, SensorKind, SensorPosition,
+};
+use libutil::{HardPwm, SoftPwm};
+
+use crate::config::{pwm_channel, RobohatConfig};
+use crate::motor::{InvertedMotor, Motor, MotorPwm, PwmDirectionMotor, PwmPairMotor};
+use crate::servo::ServoController;
+use crate::sonar::Sonar;
 use crate::{Error, Result};
 
-// sensor input pins in BCM numbering
-const GPIO_IR_L: u8 = 4;
-const GPIO_IR_R: u8 = 17;
-const GPIO_LINE_L: u8 = 5;
-const GPIO_LINE_R: u8 = 27;
-
-const GPIO_SONAR: u8 = 20;
-const SOUND_SPEED: u32 = 343000; // in mm/s
-
-// motors control pins in BCM numbering
-const GPIO_MOTOR_L1: u8 = 16;
-const GPIO_MOTOR_L2: u8 = 19;
-const GPIO_MOTOR_R1: u8 = 13;
-const GPIO_MOTOR_R2: u8 = 12;
-
-// pan/tilt servo control pins in BCM numbering
-#[allow(dead_code)]
-const GPIO_PAN_SERVO: u8 = 25;
-#[allow(dead_code)]
-const GPIO_TILT_SERVO: u8 = 24;
-
-// pan limits
-const PAN_L_CUT_DEGREES: i16 = 90;
-const PAN_R_CUT_DEGREES: i16 = -90;
-const PAN_C_DEGREES: i16 = 0;
-const PAN_L_CUT_PWIDTH: i16 = 220;
-const PAN_R_CUT_PWIDTH: i16 = 55;
-const PAN_C_PWIDTH: i16 = 138;
-
-// tilt limits
-const TILT_U_CUT_DEGREES: i16 = -90;
-const TILT_D_CUT_DEGREES: i16 = 80;
-const TILT_C_DEGREES: i16 = 0;
-const TILT_U_CUT_PWIDTH: i16 = 65;
-const TILT_D_CUT_PWIDTH: i16 = 210;
-const TILT_C_PWIDTH: i16 = 138;
-
-// Servoblaster control
-const SERVOBLASTER: &str = "/extdev/servoblaster";
-
 pub struct RobohatRover {
-    sonar_pin: IoPin,
+    sonar: Sonar,
     left_ir_pin: InputPin,
     right_ir_pin: InputPin,
     left_line_pin: InputPin,
     right_line_pin: InputPin,
-    left_motor: (SoftPwm, SoftPwm),
-    right_motor: (SoftPwm, SoftPwm),
+    left_motor: Box<dyn Motor>,
+    right_motor: Box<dyn Motor>,
+    servo: Box<dyn ServoController>,
+    config: RobohatConfig,
     move_type: MoveType,
     look_direction: (i16, i16)
 }
 
 impl RobohatRover {
     pub fn new() -> Result<RobohatRover> {
-        let gpio = Gpio::new()?;
+        RobohatRover::with_config(RobohatConfig::default())
+    }
 
-        let sonar_pin = gpio.get(GPIO_SONAR)?.into_io(Mode::Output);
+    pub fn with_config(mut config: RobohatConfig) -> Result<RobohatRover> {
+        config.load_calibration()?;
+        config.validate()?;
 
-        let left_ir_pin = gpio.get(GPIO_IR_L)?.into_input();
-        let right_ir_pin = gpio.get(GPIO_IR_R)?.into_input();
+        let servo = config.servo.controller()?;
 
-        let left_line_pin = gpio.get(GPIO_LINE_L)?.into_input();
-        let right_line_pin = gpio.get(GPIO_LINE_R)?.into_input();
+        RobohatRover::with_servo_controller(config, servo)
+    }
 
-        let pin_motor_l1 = gpio.get(GPIO_MOTOR_L1)?.into_output();
-        let pin_motor_l2 = gpio.get(GPIO_MOTOR_L2)?.into_output();
-        let left_motor = (
-            SoftPwm::new(pin_motor_l1, 10.0, 0.0),
-            SoftPwm::new(pin_motor_l2, 10.0, 0.0),
-        );
+    pub fn with_servo_controller(
+        config: RobohatConfig,
+        servo: Box<dyn ServoController>,
+    ) -> Result<RobohatRover> {
+        config.validate()?;
 
-        let pin_motor_r1 = gpio.get(GPIO_MOTOR_R1)?.into_output();
-        let pin_motor_r2 = gpio.get(GPIO_MOTOR_R2)?.into_output();
-        let right_motor = (
-            SoftPwm::new(pin_motor_r1, 10.0, 0.0),
-            SoftPwm::new(pin_motor_r2, 10.0, 0.0),
-        );
+        let gpio = Gpio::new()?;
+        let pins = &config.pins;
+
+        let sonar = Sonar::new(&gpio, pins.sonar, config.sonar.clone())?;
+
+        let left_ir_pin = gpio.get(pins.ir_left)?.into_input();
+        let right_ir_pin = gpio.get(pins.ir_right)?.into_input();
+
+        let left_line_pin = gpio.get(pins.line_left)?.into_input();
+        let right_line_pin = gpio.get(pins.line_right)?.into_input();
+
+        let motor = |(input1, input2): (u8, u8)| -> Result<Box<dyn Motor>> {
+            match config.motor_pwm {
+                MotorPwm::Soft { frequency } => {
+                    let soft_pwm = |pin: u8| -> Result<SoftPwm> {
+                        Ok(SoftPwm::new(gpio.get(pin)?.into_output(), frequency, 0.0))
+                    };
+
+                    Ok(Box::new(PwmPairMotor::new(soft_pwm(input1)?, soft_pwm(input2)?)))
+                }
+                MotorPwm::Hardware { frequency } => {
+                    // validated to be routable
+                    let channel = pwm_channel(input2).unwrap();
+
+                    Ok(Box::new(PwmDirectionMotor::new(
+                        gpio.get(input1)?.into_output(),
+                        HardPwm::new(channel, frequency, 0.0)?,
+                    )))
+                }
+            }
+        };
+        let inverted = |motor: Box<dyn Motor>, invert: bool| -> Box<dyn Motor> {
+            if invert {
+                Box::new(InvertedMotor::new(motor))
+            } else {
+                motor
+            }
+        };
+
+        let left_motor = inverted(motor(pins.motor_left)?, config.invert_left_motor);
+        let right_motor = inverted(motor(pins.motor_right)?, config.invert_right_motor);
 
         let move_type = MoveType::None;
         let look_direction = (0, 0);
 
         Ok(RobohatRover {
-            sonar_pin,
+            sonar,
             left_ir_pin,
             right_ir_pin,
             left_line_pin,
             right_line_pin,
             left_motor,
             right_motor,
+            servo,
+            config,
             move_type,
             look_direction
         })
     }
 
-    fn set_motor_speed(motor: &mut (SoftPwm, SoftPwm), speed: u8, forward: bool) -> Result<()> {
-        let frequency = 10_f32;
-        let duty_cycle = speed as f32 / 255.0;
-
-        if speed == 0 {
-            motor.0.set_duty_cycle(0.0)?;
-            motor.1.set_duty_cycle(0.0)?;
-        } else if forward {
-            motor.0.set_duty_cycle(duty_cycle)?;
-            motor.0.set_frequency(frequency)?;
-            motor.1.set_duty_cycle(0.0)?;
-        } else {
-            motor.0.set_duty_cycle(0.0)?;
-            motor.1.set_frequency(frequency)?;
-            motor.1.set_duty_cycle(duty_cycle)?;
-        }
-
-        Ok(())
-    }
-
-    fn map_degrees_to_pulse_width(h: i16, v: i16) -> (i16, i16) {
-        let deg_to_pw = |deg: i16,
-                         e1_deg: i16,
-                         e2_deg: i16,
-                         mid_deg: i16,
-                         e1_pw: i16,
-                         e2_pw: i16,
-                         mid_pw: i16|
-         -> i16 {
-            let deg_lo = e1_deg.min(e2_deg);
-            let deg_hi = e1_deg.max(e2_deg);
-            let deg_span = deg_hi - deg_lo;
-
-            let pw_lo = e1_pw.min(e2_pw);
-            let pw_hi = e1_pw.max(e2_pw);
-            let pw_span = pw_hi - pw_lo;
-
-            let cvt_coef = pw_span as f32 / deg_span as f32;
-
-            let pw = mid_pw as f32 + ((deg - mid_deg) as f32 * cvt_coef);
-
-            if pw > pw_hi as f32 {
-                pw_hi
-            } else if pw < pw_lo as f32 {
-                pw_lo
-            } else {
-                pw.round() as i16
-            }
-        };
+    fn set_motor_velocity(motor: &mut dyn Motor, velocity: i16) -> Result<()> {
+        let speed = velocity.unsigned_abs().min(u8::MAX as u16) as u8;
 
-        let pan_pw = deg_to_pw(
-            h,
-            PAN_R_CUT_DEGREES,
-            PAN_L_CUT_DEGREES,
-            PAN_C_DEGREES,
-            PAN_R_CUT_PWIDTH,
-            PAN_L_CUT_PWIDTH,
-            PAN_C_PWIDTH,
-        );
-        let tilt_pw = deg_to_pw(
-            v,
-            TILT_D_CUT_DEGREES,
-            TILT_U_CUT_DEGREES,
-            TILT_C_DEGREES,
-            TILT_D_CUT_PWIDTH,
-            TILT_U_CUT_PWIDTH,
-            TILT_C_PWIDTH,
-        );
-
-        (pan_pw, tilt_pw)
+        motor.set_speed(speed, velocity >= 0)
     }
 }
 
@@ -182,8 +120,8 @@ impl api::Mover for RobohatRover {
     type Error = Error;
 
     fn stop(&mut self) -> Result<()> {
-        RobohatRover::set_motor_speed(&mut self.left_motor, 0, false)?;
-        RobohatRover::set_motor_speed(&mut self.right_motor, 0, false)?;
+        self.left_motor.set_speed(0, false)?;
+        self.right_motor.set_speed(0, false)?;
 
         self.move_type = MoveType::None;
 
@@ -191,8 +129,8 @@ impl api::Mover for RobohatRover {
     }
 
     fn move_forward(&mut self, speed: u8) -> Result<()> {
-        RobohatRover::set_motor_speed(&mut self.left_motor, speed, true)?;
-        RobohatRover::set_motor_speed(&mut self.right_motor, speed, true)?;
+        self.left_motor.set_speed(speed, true)?;
+        self.right_motor.set_speed(speed, true)?;
 
         self.move_type = MoveType::Forward(speed);
 
@@ -200,8 +138,8 @@ impl api::Mover for RobohatRover {
     }
 
     fn move_backward(&mut self, speed: u8) -> Result<()> {
-        RobohatRover::set_motor_speed(&mut self.left_motor, speed, false)?;
-        RobohatRover::set_motor_speed(&mut self.right_motor, speed, false)?;
+        self.left_motor.set_speed(speed, false)?;
+        self.right_motor.set_speed(speed, false)?;
 
         self.move_type = MoveType::Backward(speed);
 
@@ -209,8 +147,8 @@ impl api::Mover for RobohatRover {
     }
 
     fn spin_right(&mut self, speed: u8) -> Result<()> {
-        RobohatRover::set_motor_speed(&mut self.left_motor, speed, true)?;
-        RobohatRover::set_motor_speed(&mut self.right_motor, speed, false)?;
+        self.left_motor.set_speed(speed, true)?;
+        self.right_motor.set_speed(speed, false)?;
 
         self.move_type = MoveType::SpinCW(speed);
 
@@ -218,14 +156,23 @@ impl api::Mover for RobohatRover {
     }
 
     fn spin_left(&mut self, speed: u8) -> Result<()> {
-        RobohatRover::set_motor_speed(&mut self.left_motor, speed, false)?;
-        RobohatRover::set_motor_speed(&mut self.right_motor, speed, true)?;
+        self.left_motor.set_speed(speed, false)?;
+        self.right_motor.set_speed(speed, true)?;
 
         self.move_type = MoveType::SpinCCW(speed);
 
         Ok(())
     }
 
+    fn drive(&mut self, left: i16, right: i16) -> Result<()> {
+        RobohatRover::set_motor_velocity(self.left_motor.as_mut(), left)?;
+        RobohatRover::set_motor_velocity(self.right_motor.as_mut(), right)?;
+
+        self.move_type = MoveType::Drive(left, right);
+
+        Ok(())
+    }
+
     fn get_move_type(&self) -> std::result::Result<MoveType, Self::Error> {
         Ok(self.move_type)
     }
@@ -235,21 +182,31 @@ impl api::Mover for RobohatRover {
     }
 }
 
+/// Turns pan and tilt servos to given angles, as calibrated.
+fn turn_servos(
+    servo: &mut dyn ServoController,
+    config: &RobohatConfig,
+    h: i16,
+    v: i16,
+) -> Result<()> {
+    let hpw = config.pan.pulse_width(h);
+    let vpw = config.tilt.pulse_width(v);
+    let channels = config.servo.channels();
+
+    servo.set_pulse_width(channels.pan, Duration::from_micros(hpw as u64))?;
+    servo.set_pulse_width(channels.tilt, Duration::from_micros(vpw as u64))?;
+
+    Ok(())
+}
+
 impl api::Looker for RobohatRover {
     type Error = Error;
 
     fn look_at(&mut self, h: i16, v: i16) -> Result<()> {
-        let (hpw, vpw) = RobohatRover::map_degrees_to_pulse_width(h, v);
+        // servos stop at calibrated endpoints
+        let (h, v) = self.get_look_limits()?.clamp(h, v);
 
-        //        println!("Converted coordinates: [{}; {}]", hpw, vpw);
-
-        let mut servo_ctl = OpenOptions::new()
-            .write(true)
-            .open(SERVOBLASTER)
-            .expect("Failed to open Servoblaster device.");
-
-        servo_ctl.write(format!("7={}\n", hpw).as_bytes())?;
-        servo_ctl.write(format!("6={}\n", vpw).as_bytes())?;
+        turn_servos(&mut *self.servo, &self.config, h, v)?;
 
         self.look_direction = (h, v);
 
@@ -259,55 +216,136 @@ impl api::Looker for RobohatRover {
     fn get_look_direction(&self) -> Result<(i16, i16)> {
         Ok(self.look_direction)
     }
+
+    fn get_look_limits(&self) -> Result<LookLimits> {
+        Ok(LookLimits {
+            h: self.config.pan.limits(),
+            v: self.config.tilt.limits(),
+        })
+    }
 }
 
 impl api::Sensor for RobohatRover {
     type Error = Error;
 
-    fn get_obstacles(&self) -> Result<Vec<bool>> {
-        Ok(vec![
-            self.left_ir_pin.read() == Level::Low,
-            self.right_ir_pin.read() == Level::Low,
-        ])
-    }
+    fn get_sensors(&self) -> Result<Vec<SensorDescriptor>> {
+        let sensor = |kind, position| SensorDescriptor { kind, position };
 
-    fn get_lines(&self) -> Result<Vec<bool>> {
         Ok(vec![
-            self.left_line_pin.read() == Level::Low,
-            self.right_line_pin.read() == Level::Low,
+            sensor(SensorKind::Obstacle, SensorPosition::Left),
+            sensor(SensorKind::Obstacle, SensorPosition::Right),
+            sensor(SensorKind::Line, SensorPosition::Left),
+            sensor(SensorKind::Line, SensorPosition::Right),
+            sensor(SensorKind::Distance, SensorPosition::Center),
         ])
     }
 
-    fn scan_distance(&mut self) -> Result<f32> {
-        self.sonar_pin.set_mode(Mode::Output);
-        self.sonar_pin.set_high();
-        thread::sleep(Duration::from_micros(10));
-        self.sonar_pin.set_low();
+    fn get_obstacles(&self) -> Result<DetectionReading> {
+        Ok(DetectionReading::new(vec![
+            Detection {
+                position: SensorPosition::Left,
+                detected: self.left_ir_pin.read() == Level::Low,
+            },
+            Detection {
+                position: SensorPosition::Right,
+                detected: self.right_ir_pin.read() == Level::Low,
+            },
+        ]))
+    }
 
-        self.sonar_pin.set_mode(Mode::Input);
+    fn get_lines(&self) -> Result<DetectionReading> {
+        Ok(DetectionReading::new(vec![
+            Detection {
+                position: SensorPosition::Left,
+                detected: self.left_line_pin.read() == Level::Low,
+            },
+            Detection {
+                position: SensorPosition::Right,
+                detected: self.right_line_pin.read() == Level::Low,
+            },
+        ]))
+    }
 
-        let timeout = Duration::from_millis(100);
-        let mut timeout_guard = SystemTime::now();
-        let mut pulse_start = timeout_guard.clone();
-        while self.sonar_pin.read() == Level::Low && timeout_guard.elapsed()? < timeout {
-            pulse_start = SystemTime::now();
+    fn scan_distance(&mut self) -> Result<DistanceReading> {
+        match self.sonar.measure() {
+            Ok(distance) => Ok(DistanceReading::new(Millimeters(distance), true)),
+            Err(Error::OutOfRange) => Ok(DistanceReading::new(
+                Millimeters(self.config.sonar.max_range),
+                false,
+            )),
+            Err(e) => Err(e),
         }
+    }
 
-        timeout_guard = SystemTime::now();
-        let mut pulse_end = timeout_guard.clone();
-        while self.sonar_pin.read() == Level::High && timeout_guard.elapsed()? < timeout {
-            pulse_end = SystemTime::now();
-        }
+    fn snapshot(&mut self) -> Result<RoverSnapshot> {
+        Ok(RoverSnapshot {
+            obstacles: self.get_obstacles()?,
+            lines: self.get_lines()?,
+            distance: self.scan_distance()?,
+            move_type: Some(self.move_type),
+            look_direction: Some(self.look_direction),
+        })
+    }
+}
 
-        let pulse_width = pulse_end.duration_since(pulse_start)?;
+impl util::splittable::SplittableRover for RobohatRover {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servo::{FakeServoController, ServoDriver};
 
-        let pulse_width_f32 =
-            pulse_width.as_secs() as f32 + pulse_width.subsec_nanos() as f32 / 1000000000.0;
+    fn micros(us: u64) -> Option<Duration> {
+        Some(Duration::from_micros(us))
+    }
 
-        let distance = SOUND_SPEED as f32 * pulse_width_f32;
+    #[test]
+    fn servos_turn_to_calibrated_pulse_widths() {
+        let config = RobohatConfig::default();
+        let servo = FakeServoController::new();
 
-        Ok(distance / 2.0)
+        turn_servos(&mut servo.clone(), &config, 0, 0).unwrap();
+        assert_eq!(servo.pulse_width(7), micros(1380));
+        assert_eq!(servo.pulse_width(6), micros(1380));
+
+        turn_servos(&mut servo.clone(), &config, 90, -90).unwrap();
+        assert_eq!(servo.pulse_width(7), micros(2200));
+        assert_eq!(servo.pulse_width(6), micros(650));
     }
-}
 
-impl util::splittable::SplittableRover for RobohatRover {}
+    #[test]
+    fn servos_stop_at_endpoints() {
+        let config = RobohatConfig::default();
+        let servo = FakeServoController::new();
+
+        turn_servos(&mut servo.clone(), &config, -120, 120).unwrap();
+        assert_eq!(servo.pulse_width(7), micros(550));
+        assert_eq!(servo.pulse_width(6), micros(2100));
+    }
+
+    #[test]
+    fn look_limits_are_calibrated_endpoints() {
+        let config = RobohatConfig::default();
+        let limits = LookLimits {
+            h: config.pan.limits(),
+            v: config.tilt.limits(),
+        };
+
+        assert_eq!(limits.clamp(30, -10), (30, -10));
+        assert_eq!(limits.clamp(-120, 120), (-90, 80));
+    }
+
+    #[test]
+    fn servos_use_configured_channels() {
+        let config = RobohatConfig {
+            servo: ServoDriver::Gpio { pan: 25, tilt: 24 },
+            ..RobohatConfig::default()
+        };
+        let servo = FakeServoController::new();
+
+        turn_servos(&mut servo.clone(), &config, 0, 0).unwrap();
+        assert_eq!(servo.pulse_width(25), micros(1380));
+        assert_eq!(servo.pulse_width(24), micros(1380));
+        assert_eq!(servo.pulse_width(7), None);
+    }
+}
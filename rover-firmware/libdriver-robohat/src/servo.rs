@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rppal::gpio::{Gpio, OutputPin};
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+// standard hobby servo control period
+const SERVO_PERIOD: Duration = Duration::from_millis(20);
+
+const SERVOBLASTER_DEVICE: &str = "/extdev/servoblaster";
+const SERVOBLASTER_PAN_CHANNEL: u8 = 7;
+const SERVOBLASTER_TILT_CHANNEL: u8 = 6;
+
+// pan/tilt servo control pins in BCM numbering
+const GPIO_PAN_SERVO: u8 = 25;
+const GPIO_TILT_SERVO: u8 = 24;
+
+/// Sink of servo control pulses.
+pub trait ServoController: Send {
+    /// Sets width of pulses sent on `channel`.
+    fn set_pulse_width(&mut self, channel: u8, pulse_width: Duration) -> Result<()>;
+}
+
+/// Controller channels the pan and tilt servos are attached to.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ServoChannels {
+    pub pan: u8,
+    pub tilt: u8,
+}
+
+/// Servo controller implementation driving the pan/tilt servos.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ServoDriver {
+    /// ServoBlaster daemon device, `pan` and `tilt` are its channel numbers.
+    ServoBlaster {
+        #[serde(default = "default_servoblaster_device")]
+        device: String,
+        #[serde(default = "default_servoblaster_pan")]
+        pan: u8,
+        #[serde(default = "default_servoblaster_tilt")]
+        tilt: u8,
+    },
+
+    /// Software generated pulses, `pan` and `tilt` are GPIO pins in BCM numbering.
+    Gpio {
+        #[serde(default = "default_gpio_pan")]
+        pan: u8,
+        #[serde(default = "default_gpio_tilt")]
+        tilt: u8,
+    },
+}
+
+fn default_servoblaster_device() -> String {
+    SERVOBLASTER_DEVICE.to_string()
+}
+
+fn default_servoblaster_pan() -> u8 {
+    SERVOBLASTER_PAN_CHANNEL
+}
+
+fn default_servoblaster_tilt() -> u8 {
+    SERVOBLASTER_TILT_CHANNEL
+}
+
+fn default_gpio_pan() -> u8 {
+    GPIO_PAN_SERVO
+}
+
+fn default_gpio_tilt() -> u8 {
+    GPIO_TILT_SERVO
+}
+
+impl Default for ServoDriver {
+    fn default() -> Self {
+        ServoDriver::ServoBlaster {
+            device: default_servoblaster_device(),
+            pan: SERVOBLASTER_PAN_CHANNEL,
+            tilt: SERVOBLASTER_TILT_CHANNEL,
+        }
+    }
+}
+
+impl ServoDriver {
+    pub fn channels(&self) -> ServoChannels {
+        match *self {
+            ServoDriver::ServoBlaster { pan, tilt, .. } | ServoDriver::Gpio { pan, tilt } => {
+                ServoChannels { pan, tilt }
+            }
+        }
+    }
+
+    pub fn controller(&self) -> Result<Box<dyn ServoController>> {
+        match self {
+            ServoDriver::ServoBlaster { device, .. } => Ok(Box::new(ServoBlaster::open(device)?)),
+            ServoDriver::Gpio { pan, tilt } => {
+                Ok(Box::new(GpioServos::new(&Gpio::new()?, &[*pan, *tilt])?))
+            }
+        }
+    }
+}
+
+/// Writes pulse widths to the ServoBlaster daemon device.
+pub struct ServoBlaster {
+    device: File,
+}
+
+impl ServoBlaster {
+    pub fn open(path: &str) -> Result<ServoBlaster> {
+        let device = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| Error::ServoUnavailable(format!("{}: {}", path, e)))?;
+
+        Ok(ServoBlaster { device })
+    }
+}
+
+impl ServoController for ServoBlaster {
+    fn set_pulse_width(&mut self, channel: u8, pulse_width: Duration) -> Result<()> {
+        self.device
+            .write_all(format!("{}={}us\n", channel, pulse_width.as_micros()).as_bytes())?;
+        self.device.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Generates software pulses on GPIO pins, channels are pin numbers in BCM numbering.
+pub struct GpioServos {
+    pins: HashMap<u8, OutputPin>,
+}
+
+impl GpioServos {
+    pub fn new(gpio: &Gpio, pins: &[u8]) -> Result<GpioServos> {
+        let pins = pins
+            .iter()
+            .map(|&pin| Ok((pin, gpio.get(pin)?.into_output_low())))
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(GpioServos { pins })
+    }
+}
+
+impl ServoController for GpioServos {
+    fn set_pulse_width(&mut self, channel: u8, pulse_width: Duration) -> Result<()> {
+        let pin = self
+            .pins
+            .get_mut(&channel)
+            .ok_or(Error::ServoChannel(channel))?;
+
+        pin.set_pwm(SERVO_PERIOD, pulse_width)?;
+
+        Ok(())
+    }
+}
+
+/// Keeps pulse widths in memory. Clones share the state, so one can be handed to the rover and
+/// another inspected.
+#[derive(Debug, Default, Clone)]
+pub struct FakeServoController {
+    pulse_widths: Arc<Mutex<HashMap<u8, Duration>>>,
+}
+
+impl FakeServoController {
+    pub fn new() -> FakeServoController {
+        FakeServoController::default()
+    }
+
+    /// Pulse width last set on `channel`.
+    pub fn pulse_width(&self, channel: u8) -> Option<Duration> {
+        self.pulse_widths.lock().unwrap().get(&channel).copied()
+    }
+}
+
+impl ServoController for FakeServoController {
+    fn set_pulse_width(&mut self, channel: u8, pulse_width: Duration) -> Result<()> {
+        self.pulse_widths.lock().unwrap().insert(channel, pulse_width);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn temp_device(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        File::create(&path).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn servoblaster_writes_channel_commands() {
+        let path = temp_device("servoblaster");
+        let mut servo = ServoBlaster::open(path.to_str().unwrap()).unwrap();
+
+        servo
+            .set_pulse_width(7, Duration::from_micros(1380))
+            .unwrap();
+        servo
+            .set_pulse_width(6, Duration::from_micros(650))
+            .unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "7=1380us\n6=650us\n");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_servoblaster_is_error() {
+        let result = ServoBlaster::open("/nonexistent/servoblaster");
+
+        assert!(matches!(result, Err(Error::ServoUnavailable(_))));
+    }
+
+    #[test]
+    fn fake_controller_clones_share_pulse_widths() {
+        let servo = FakeServoController::new();
+        let mut handed_out = servo.clone();
+
+        assert_eq!(servo.pulse_width(1), None);
+
+        handed_out
+            .set_pulse_width(1, Duration::from_micros(1500))
+            .unwrap();
+        assert_eq!(servo.pulse_width(1), Some(Duration::from_micros(1500)));
+    }
+
+    #[test]
+    fn driver_channels() {
+        assert_eq!(
+            ServoDriver::default().channels(),
+            ServoChannels { pan: 7, tilt: 6 }
+        );
+
+        let driver: ServoDriver = toml::from_str(r#"type = "gpio""#).unwrap();
+        assert_eq!(driver, ServoDriver::Gpio { pan: 25, tilt: 24 });
+
+        let driver: ServoDriver = toml::from_str(
+            r#"
+            type = "servoblaster"
+            pan = 2
+            tilt = 3
+            "#,
+        )
+        .unwrap();
+        assert_eq!(driver.channels(), ServoChannels { pan: 2, tilt: 3 });
+    }
+}
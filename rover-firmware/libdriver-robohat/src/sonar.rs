@@ -0,0 +1,187 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rppal::gpio::{Gpio, InputPin, Level, Trigger};
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+const SOUND_SPEED: f32 = 343000.0; // in mm/s
+
+const TRIGGER_PULSE: Duration = Duration::from_micros(10);
+
+// sonar sends its burst and raises echo well within this time after the trigger pulse
+const ECHO_START_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Ranging and filtering parameters of the sonar. Distances are in mm.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SonarConfig {
+    pub max_range: f32,
+
+    /// Pings per measurement, the result is the median of successful ones.
+    pub samples: usize,
+    /// Pause between pings letting the previous echo fade out.
+    pub sample_interval_ms: u64,
+    /// Pings further than this from the median are discarded as outliers.
+    pub max_deviation: f32,
+}
+
+impl Default for SonarConfig {
+    fn default() -> Self {
+        SonarConfig {
+            max_range: 4000.0,
+            samples: 1,
+            sample_interval_ms: 60,
+            max_deviation: 100.0,
+        }
+    }
+}
+
+/// Single pin ultrasonic sonar, the pin both triggers the ping and receives the echo.
+pub(crate) struct Sonar {
+    gpio: Gpio,
+    pin: u8,
+    config: SonarConfig,
+}
+
+impl Sonar {
+    pub fn new(gpio: &Gpio, pin: u8, config: SonarConfig) -> Result<Sonar> {
+        // check that the pin is available
+        gpio.get(pin)?;
+
+        Ok(Sonar {
+            gpio: gpio.clone(),
+            pin,
+            config,
+        })
+    }
+
+    fn ping(&mut self) -> Result<f32> {
+        let mut trigger = self.gpio.get(self.pin)?.into_output_low();
+        trigger.set_high();
+        thread::sleep(TRIGGER_PULSE);
+        trigger.set_low();
+        drop(trigger);
+
+        // echo is listened for only after the trigger as the pin is shared; edges are queued from
+        // the moment the interrupt is set, well before the sonar is done sending its burst
+        let mut echo = self.gpio.get(self.pin)?.into_input();
+        echo.set_interrupt(Trigger::Both)?;
+
+        let echo_timeout = Duration::from_secs_f32(2.0 * self.config.max_range / SOUND_SPEED);
+
+        let pulse_start =
+            wait_for(&mut echo, Level::High, ECHO_START_TIMEOUT)?.ok_or(Error::SonarTimeout)?;
+        let pulse_end = wait_for(&mut echo, Level::Low, echo_timeout)?.ok_or(Error::OutOfRange)?;
+
+        let distance = SOUND_SPEED * pulse_end.duration_since(pulse_start).as_secs_f32() / 2.0;
+
+        if distance > self.config.max_range {
+            return Err(Error::OutOfRange);
+        }
+
+        Ok(distance)
+    }
+
+    /// Pings configured number of times and filters out failed pings and outliers.
+    pub fn measure(&mut self) -> Result<f32> {
+        let mut distances = Vec::with_capacity(self.config.samples);
+        let mut last_error = None;
+
+        for sample in 0..self.config.samples.max(1) {
+            if sample > 0 {
+                thread::sleep(Duration::from_millis(self.config.sample_interval_ms));
+            }
+
+            match self.ping() {
+                Ok(distance) => distances.push(distance),
+                Err(e @ (Error::SonarTimeout | Error::OutOfRange)) => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        filter(distances, self.config.max_deviation).ok_or_else(|| last_error.unwrap())
+    }
+}
+
+/// Waits for the pin to change to given level, returns when it did.
+fn wait_for(pin: &mut InputPin, level: Level, timeout: Duration) -> Result<Option<Instant>> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        match pin.poll_interrupt(false, Some(remaining))? {
+            Some(edge) if edge == level => return Ok(Some(Instant::now())),
+            Some(_) => continue,
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Median of the distances that are close to the median of them all, absent if there are none.
+fn filter(mut distances: Vec<f32>, max_deviation: f32) -> Option<f32> {
+    let rough = median(&mut distances)?;
+
+    let mut inliers: Vec<f32> = distances
+        .into_iter()
+        .filter(|d| (d - rough).abs() <= max_deviation)
+        .collect();
+
+    Some(median(&mut inliers).unwrap_or(rough))
+}
+
+fn median(values: &mut [f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(f32::total_cmp);
+
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(median(&mut []), None);
+        assert_eq!(median(&mut [5.0]), Some(5.0));
+        assert_eq!(median(&mut [9.0, 1.0, 5.0]), Some(5.0));
+        assert_eq!(median(&mut [9.0, 1.0, 5.0, 3.0]), Some(4.0));
+    }
+
+    #[test]
+    fn filter_without_pings() {
+        assert_eq!(filter(vec![], 100.0), None);
+    }
+
+    #[test]
+    fn filter_single_ping() {
+        assert_eq!(filter(vec![420.0], 100.0), Some(420.0));
+    }
+
+    #[test]
+    fn filter_drops_outliers() {
+        // stray echoes from the floor pull the median down
+        let distances = vec![510.0, 90.0, 500.0, 95.0, 505.0];
+
+        assert_eq!(filter(distances, 100.0), Some(505.0));
+    }
+
+    #[test]
+    fn filter_keeps_pings_within_deviation() {
+        let distances = vec![400.0, 500.0, 600.0];
+
+        assert_eq!(filter(distances.clone(), 100.0), Some(500.0));
+        assert_eq!(filter(distances, 50.0), Some(500.0));
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::Error as IOError;
+
+use thiserror::Error as LibError;
+use toml::de::Error as TomlError;
+
+pub use sim::{SimConfig, SimRover};
+pub use world::{Point, Pose, Segment, World};
+
+mod sim;
+mod world;
+
+#[derive(Debug, LibError)]
+pub enum Error {
+    #[error("Input/output error: {0:?}")]
+    IO(#[from] IOError),
+
+    #[error("Invalid world map: {0}")]
+    Map(#[from] TomlError),
+}
+
+type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,500 @@
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Deserialize;
+
+use libdriver::api::{
+    Detection, DetectionReading, DistanceReading, LookLimits, Millimeters, MoveType,
+    RoverSnapshot, SensorDescriptor, SensorKind, SensorPosition,
+};
+use libdriver::{api, util};
+
+use crate::world::{Pose, World};
+use crate::{Error, Result};
+
+// pose integration step, in seconds
+const SIMULATION_STEP: f32 = 0.01;
+
+// pan/tilt servo limits, in degrees
+const PAN_LIMIT_DEGREES: i16 = 90;
+const TILT_LIMIT_DEGREES: i16 = 90;
+
+/// Physical parameters of the simulated rover. Distances are in mm, angles are in degrees.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SimConfig {
+    /// Linear speed reached at the maximum speed setting (255), mm/s.
+    pub max_speed: f32,
+
+    /// Distance between left and right wheels.
+    pub track_width: f32,
+
+    /// Radius of the circle around rover center that cannot overlap with walls.
+    pub body_radius: f32,
+
+    /// Offset of the sonar from rover center along its heading.
+    pub sonar_offset: f32,
+    pub sonar_range: f32,
+
+    /// Position of the IR sensors as (forward, sideways) offset from rover center.
+    pub ir_offset: (f32, f32),
+    /// Outward angle of IR sensors relative to rover heading.
+    pub ir_angle: f32,
+    pub ir_range: f32,
+
+    /// Position of the line sensors as (forward, sideways) offset from rover center.
+    pub line_sensor_offset: (f32, f32),
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            max_speed: 300.0,
+            track_width: 140.0,
+            body_radius: 100.0,
+            sonar_offset: 90.0,
+            sonar_range: 4000.0,
+            ir_offset: (100.0, 50.0),
+            ir_angle: 30.0,
+            ir_range: 150.0,
+            line_sensor_offset: (90.0, 15.0),
+        }
+    }
+}
+
+pub struct SimRover {
+    world: World,
+    config: SimConfig,
+    pose: Pose,
+    pose_timestamp: Instant,
+    move_type: MoveType,
+    look_direction: (i16, i16),
+}
+
+impl SimRover {
+    pub fn new(world: World) -> SimRover {
+        SimRover::with_config(world, SimConfig::default())
+    }
+
+    pub fn with_config(world: World, config: SimConfig) -> SimRover {
+        let pose = world.start;
+
+        SimRover {
+            world,
+            config,
+            pose,
+            pose_timestamp: Instant::now(),
+            move_type: MoveType::None,
+            look_direction: (0, 0),
+        }
+    }
+
+    pub fn from_map<P: AsRef<Path>>(map_path: P, config: SimConfig) -> Result<SimRover> {
+        Ok(SimRover::with_config(World::load(map_path)?, config))
+    }
+
+    /// Current rover pose, as it is after driving with the last requested move type until now.
+    pub fn pose(&self) -> Pose {
+        self.project(Instant::now())
+    }
+
+    // linear (mm/s) and angular (degrees/s) velocities for current move type
+    fn velocities(&self) -> (f32, f32) {
+        let side = |speed: i16| {
+            let speed = speed.clamp(-(u8::MAX as i16), u8::MAX as i16);
+
+            self.config.max_speed * speed as f32 / u8::MAX as f32
+        };
+        let (left, right) = match self.move_type {
+            MoveType::Forward(speed) => (speed as i16, speed as i16),
+            MoveType::Backward(speed) => (-(speed as i16), -(speed as i16)),
+            MoveType::SpinCW(speed) => (speed as i16, -(speed as i16)),
+            MoveType::SpinCCW(speed) => (-(speed as i16), speed as i16),
+            MoveType::Drive(left, right) => (left, right),
+            MoveType::None => (0, 0),
+        };
+        let (left, right) = (side(left), side(right));
+
+        (
+            (left + right) / 2.0,
+            ((right - left) / self.config.track_width).to_degrees(),
+        )
+    }
+
+    fn project(&self, now: Instant) -> Pose {
+        let (linear, angular) = self.velocities();
+        let mut remaining = now.duration_since(self.pose_timestamp).as_secs_f32();
+        let mut pose = self.pose;
+
+        if linear == 0.0 && angular == 0.0 {
+            return pose;
+        }
+
+        while remaining > 0.0 {
+            let dt = remaining.min(SIMULATION_STEP);
+            remaining -= dt;
+
+            let heading = pose.heading + angular * dt;
+            let (sin, cos) = ((pose.heading + heading) / 2.0).to_radians().sin_cos();
+            let next = Pose {
+                x: pose.x + linear * dt * cos,
+                y: pose.y + linear * dt * sin,
+                heading,
+            };
+
+            // rover stalls when pushing into the wall but can still turn or back off
+            let clearance = |p: &Pose| self.world.clearance(p.position()).unwrap_or(f32::MAX);
+            if clearance(&next) < self.config.body_radius && clearance(&next) < clearance(&pose) {
+                pose.heading = heading;
+            } else {
+                pose = next;
+            }
+        }
+
+        pose.heading = pose.heading.rem_euclid(360.0);
+
+        pose
+    }
+
+    fn set_move_type(&mut self, move_type: MoveType) {
+        let now = Instant::now();
+
+        self.pose = self.project(now);
+        self.pose_timestamp = now;
+        self.move_type = move_type;
+    }
+
+    fn detect_obstacle(&self, pose: &Pose, side: f32) -> bool {
+        let (forward, sideways) = self.config.ir_offset;
+        let origin = pose.to_world(forward, side * sideways);
+        let angle = pose.heading + side * self.config.ir_angle;
+
+        self.world
+            .cast_ray(origin, angle)
+            .is_some_and(|distance| distance <= self.config.ir_range)
+    }
+
+    fn detect_line(&self, pose: &Pose, side: f32) -> bool {
+        let (forward, sideways) = self.config.line_sensor_offset;
+
+        self.world.is_on_line(pose.to_world(forward, side * sideways))
+    }
+}
+
+impl api::Mover for SimRover {
+    type Error = Error;
+
+    fn stop(&mut self) -> Result<()> {
+        self.set_move_type(MoveType::None);
+
+        Ok(())
+    }
+
+    fn move_forward(&mut self, speed: u8) -> Result<()> {
+        self.set_move_type(MoveType::Forward(speed));
+
+        Ok(())
+    }
+
+    fn move_backward(&mut self, speed: u8) -> Result<()> {
+        self.set_move_type(MoveType::Backward(speed));
+
+        Ok(())
+    }
+
+    fn spin_right(&mut self, speed: u8) -> Result<()> {
+        self.set_move_type(MoveType::SpinCW(speed));
+
+        Ok(())
+    }
+
+    fn spin_left(&mut self, speed: u8) -> Result<()> {
+        self.set_move_type(MoveType::SpinCCW(speed));
+
+        Ok(())
+    }
+
+    fn drive(&mut self, left: i16, right: i16) -> Result<()> {
+        self.set_move_type(MoveType::Drive(left, right));
+
+        Ok(())
+    }
+
+    fn get_move_type(&self) -> Result<MoveType> {
+        Ok(self.move_type)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.stop()
+    }
+}
+
+impl api::Looker for SimRover {
+    type Error = Error;
+
+    fn look_at(&mut self, h: i16, v: i16) -> Result<()> {
+        self.look_direction = self.get_look_limits()?.clamp(h, v);
+
+        Ok(())
+    }
+
+    fn get_look_direction(&self) -> Result<(i16, i16)> {
+        Ok(self.look_direction)
+    }
+
+    fn get_look_limits(&self) -> Result<LookLimits> {
+        Ok(LookLimits {
+            h: (-PAN_LIMIT_DEGREES, PAN_LIMIT_DEGREES),
+            v: (-TILT_LIMIT_DEGREES, TILT_LIMIT_DEGREES),
+        })
+    }
+}
+
+impl api::Sensor for SimRover {
+    type Error = Error;
+
+    fn get_sensors(&self) -> Result<Vec<SensorDescriptor>> {
+        let sensor = |kind, position| SensorDescriptor { kind, position };
+
+        Ok(vec![
+            sensor(SensorKind::Obstacle, SensorPosition::Left),
+            sensor(SensorKind::Obstacle, SensorPosition::Right),
+            sensor(SensorKind::Line, SensorPosition::Left),
+            sensor(SensorKind::Line, SensorPosition::Right),
+            sensor(SensorKind::Distance, SensorPosition::Center),
+        ])
+    }
+
+    fn get_obstacles(&self) -> Result<DetectionReading> {
+        let pose = self.pose();
+
+        Ok(DetectionReading::new(vec![
+            Detection {
+                position: SensorPosition::Left,
+                detected: self.detect_obstacle(&pose, 1.0),
+            },
+            Detection {
+                position: SensorPosition::Right,
+                detected: self.detect_obstacle(&pose, -1.0),
+            },
+        ]))
+    }
+
+    fn get_lines(&self) -> Result<DetectionReading> {
+        let pose = self.pose();
+
+        Ok(DetectionReading::new(vec![
+            Detection {
+                position: SensorPosition::Left,
+                detected: self.detect_line(&pose, 1.0),
+            },
+            Detection {
+                position: SensorPosition::Right,
+                detected: self.detect_line(&pose, -1.0),
+            },
+        ]))
+    }
+
+    fn scan_distance(&mut self) -> Result<DistanceReading> {
+        let pose = self.pose();
+        let pan = self.look_direction.0;
+
+        let origin = pose.to_world(self.config.sonar_offset, 0.0);
+        let reading = match self.world.cast_ray(origin, pose.heading + pan as f32) {
+            Some(distance) if distance <= self.config.sonar_range => {
+                DistanceReading::new(Millimeters(distance), true)
+            }
+            _ => DistanceReading::new(Millimeters(self.config.sonar_range), false),
+        };
+
+        Ok(reading)
+    }
+
+    fn snapshot(&mut self) -> Result<RoverSnapshot> {
+        Ok(RoverSnapshot {
+            obstacles: self.get_obstacles()?,
+            lines: self.get_lines()?,
+            distance: self.scan_distance()?,
+            move_type: Some(self.move_type),
+            look_direction: Some(self.look_direction),
+        })
+    }
+}
+
+impl util::splittable::SplittableRover for SimRover {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use libdriver::api::{Looker, Mover, Sensor};
+
+    use super::*;
+    use crate::world::{Point, Segment};
+
+    const EPSILON: f32 = 0.5;
+
+    fn wall(from: (f32, f32), to: (f32, f32)) -> Segment {
+        Segment {
+            from: Point::new(from.0, from.1),
+            to: Point::new(to.0, to.1),
+        }
+    }
+
+    fn rover(walls: Vec<Segment>, lines: Vec<Segment>) -> SimRover {
+        SimRover::new(World {
+            walls,
+            lines,
+            line_width: 20.0,
+            ..World::default()
+        })
+    }
+
+    /// Pose after moving with given move type for given time, starting from current pose.
+    fn drive(rover: &mut SimRover, move_type: MoveType, seconds: f32) -> Pose {
+        rover.move_type = move_type;
+
+        let pose = rover.project(rover.pose_timestamp + Duration::from_secs_f32(seconds));
+        rover.pose = pose;
+
+        pose
+    }
+
+    #[test]
+    fn stands_still_without_move() {
+        let mut rover = rover(vec![], vec![]);
+
+        assert_eq!(drive(&mut rover, MoveType::None, 1.0), Pose::default());
+    }
+
+    #[test]
+    fn drives_straight() {
+        let mut rover = rover(vec![], vec![]);
+
+        let pose = drive(&mut rover, MoveType::Forward(255), 1.0);
+        assert!((pose.x - 300.0).abs() < EPSILON);
+        assert!(pose.y.abs() < EPSILON);
+
+        let pose = drive(&mut rover, MoveType::Backward(51), 1.0);
+        assert!((pose.x - 240.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn spins_in_place() {
+        let mut rover = rover(vec![], vec![]);
+        // wheels at full speed in opposite directions, over half the track width
+        let rate = (300.0 / 70.0f32).to_degrees();
+
+        let pose = drive(&mut rover, MoveType::SpinCCW(255), 0.25);
+        assert!((pose.heading - rate * 0.25).abs() < EPSILON);
+        assert!(pose.x.abs() < EPSILON && pose.y.abs() < EPSILON);
+
+        // heading wraps around
+        let pose = drive(&mut rover, MoveType::SpinCW(255), 0.5);
+        assert!((pose.heading - (360.0 - rate * 0.25)).abs() < EPSILON);
+    }
+
+    #[test]
+    fn drives_along_arc() {
+        let mut rover = rover(vec![], vec![]);
+        // right wheel twice as fast: arc of 1.5 track width radius around the point to the left
+        let rate = (100.0 / 140.0f32).to_degrees();
+        let seconds = 90.0 / rate;
+
+        let pose = drive(&mut rover, MoveType::Drive(85, 170), seconds);
+        assert!((pose.heading - 90.0).abs() < EPSILON);
+        assert!((pose.x - 210.0).abs() < 2.0 * EPSILON);
+        assert!((pose.y - 210.0).abs() < 2.0 * EPSILON);
+    }
+
+    #[test]
+    fn stalls_at_wall() {
+        let mut rover = rover(vec![wall((500.0, -500.0), (500.0, 500.0))], vec![]);
+
+        let pose = drive(&mut rover, MoveType::Forward(255), 5.0);
+        assert!(pose.x < 400.0 && pose.x > 390.0);
+
+        // backs off just fine
+        let pose = drive(&mut rover, MoveType::Backward(255), 1.0);
+        assert!(pose.x < 100.0);
+    }
+
+    #[test]
+    fn turns_while_stalled() {
+        let mut rover = rover(vec![wall((500.0, -500.0), (500.0, 500.0))], vec![]);
+
+        drive(&mut rover, MoveType::Forward(255), 5.0);
+        let pose = drive(&mut rover, MoveType::SpinCCW(255), 0.1);
+
+        assert!(pose.heading > 20.0);
+    }
+
+    #[test]
+    fn sonar_measures_distance_to_wall_ahead() {
+        let mut rover = rover(vec![wall((1000.0, -500.0), (1000.0, 500.0))], vec![]);
+
+        let reading = rover.scan_distance().unwrap();
+        assert!(reading.valid);
+        assert!((reading.distance.0 - 910.0).abs() < EPSILON);
+
+        // nothing to the left
+        rover.look_at(90, 0).unwrap();
+        let reading = rover.scan_distance().unwrap();
+        assert!(!reading.valid);
+        assert_eq!(reading.distance.0, SimConfig::default().sonar_range);
+    }
+
+    #[test]
+    fn ir_sensors_detect_close_walls() {
+        let far = rover(vec![wall((500.0, -500.0), (500.0, 500.0))], vec![]);
+        let obstacles = far.get_obstacles().unwrap();
+        assert_eq!(obstacles.at(SensorPosition::Left), Some(false));
+        assert_eq!(obstacles.at(SensorPosition::Right), Some(false));
+
+        let close = rover(vec![wall((200.0, -500.0), (200.0, 500.0))], vec![]);
+        let obstacles = close.get_obstacles().unwrap();
+        assert_eq!(obstacles.at(SensorPosition::Left), Some(true));
+        assert_eq!(obstacles.at(SensorPosition::Right), Some(true));
+
+        // only the left sensor looks towards the wall
+        let aside = rover(vec![wall((0.0, 120.0), (500.0, 120.0))], vec![]);
+        let obstacles = aside.get_obstacles().unwrap();
+        assert_eq!(obstacles.at(SensorPosition::Left), Some(true));
+        assert_eq!(obstacles.at(SensorPosition::Right), Some(false));
+    }
+
+    #[test]
+    fn line_sensors_see_line_under_them() {
+        let mut rover = rover(vec![], vec![wall((0.0, 12.0), (1000.0, 12.0))]);
+
+        let lines = rover.get_lines().unwrap();
+        assert_eq!(lines.at(SensorPosition::Left), Some(true));
+        assert_eq!(lines.at(SensorPosition::Right), Some(false));
+
+        rover.pose.y = 24.0;
+        let lines = rover.get_lines().unwrap();
+        assert_eq!(lines.at(SensorPosition::Left), Some(false));
+        assert_eq!(lines.at(SensorPosition::Right), Some(true));
+    }
+
+    #[test]
+    fn snapshot_reports_move_and_look() {
+        let mut rover = rover(vec![], vec![]);
+        rover.spin_left(100).unwrap();
+        rover.look_at(30, -10).unwrap();
+
+        let snapshot = rover.snapshot().unwrap();
+        assert_eq!(snapshot.move_type, Some(MoveType::SpinCCW(100)));
+        assert_eq!(snapshot.look_direction, Some((30, -10)));
+        assert_eq!(snapshot.obstacles.detections.len(), 2);
+    }
+
+    #[test]
+    fn looker_stops_at_limits() {
+        let mut rover = rover(vec![], vec![]);
+        rover.look_at(120, -100).unwrap();
+
+        assert_eq!(rover.get_look_direction().unwrap(), (90, -90));
+        assert_eq!(rover.snapshot().unwrap().look_direction, Some((90, -90)));
+    }
+}
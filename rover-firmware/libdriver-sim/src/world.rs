@@ -0,0 +1,314 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::Result;
+
+const DEFAULT_LINE_WIDTH: f32 = 20.0; // in mm
+
+/// Point on the world plane, coordinates are in mm.
+#[derive(Debug, Deserialize, PartialEq, Copy, Clone, Default)]
+#[serde(from = "[f32; 2]")]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Point {
+        Point { x, y }
+    }
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+
+    fn dot(self, other: Point) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    fn cross(self, other: Point) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+}
+
+impl From<[f32; 2]> for Point {
+    fn from(coords: [f32; 2]) -> Self {
+        Point::new(coords[0], coords[1])
+    }
+}
+
+/// Straight piece of a wall or of a floor line.
+#[derive(Debug, Deserialize, PartialEq, Copy, Clone)]
+pub struct Segment {
+    pub from: Point,
+    pub to: Point,
+}
+
+impl Segment {
+    /// Distance from the given point to the closest point of the segment.
+    pub fn distance_to(&self, p: Point) -> f32 {
+        let span = self.to.sub(self.from);
+        let span_len_sq = span.dot(span);
+
+        let t = if span_len_sq > 0.0 {
+            (p.sub(self.from).dot(span) / span_len_sq).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let closest = Point::new(self.from.x + span.x * t, self.from.y + span.y * t);
+
+        p.sub(closest).length()
+    }
+
+    /// Distance along the ray from `origin` towards `direction` (unit vector) to the point
+    /// where it crosses the segment, if it does.
+    pub fn intersect_ray(&self, origin: Point, direction: Point) -> Option<f32> {
+        let span = self.to.sub(self.from);
+        let denom = direction.cross(span);
+
+        if denom.abs() < f32::EPSILON {
+            return None; // parallel
+        }
+
+        let to_start = self.from.sub(origin);
+        let t = to_start.cross(span) / denom;
+        let u = to_start.cross(direction) / denom;
+
+        if t >= 0.0 && (0.0..=1.0).contains(&u) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+/// Position and orientation of the rover. Heading is in degrees counter-clockwise from X axis.
+#[derive(Debug, Deserialize, PartialEq, Copy, Clone, Default)]
+pub struct Pose {
+    pub x: f32,
+    pub y: f32,
+    pub heading: f32,
+}
+
+impl Pose {
+    pub fn position(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    /// Translates point given in rover frame (forward, left) into world coordinates.
+    pub fn to_world(&self, forward: f32, left: f32) -> Point {
+        let (sin, cos) = self.heading.to_radians().sin_cos();
+
+        Point::new(
+            self.x + forward * cos - left * sin,
+            self.y + forward * sin + left * cos,
+        )
+    }
+}
+
+/// Static environment the simulated rover drives in, normally loaded from a TOML map file.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct World {
+    #[serde(default)]
+    pub start: Pose,
+
+    #[serde(default)]
+    pub walls: Vec<Segment>,
+
+    #[serde(default)]
+    pub lines: Vec<Segment>,
+
+    #[serde(default = "default_line_width")]
+    pub line_width: f32,
+}
+
+fn default_line_width() -> f32 {
+    DEFAULT_LINE_WIDTH
+}
+
+impl World {
+    pub fn load<P: AsRef<Path>>(map_path: P) -> Result<World> {
+        let map = fs::read_to_string(map_path)?;
+
+        World::parse(&map)
+    }
+
+    pub fn parse(map: &str) -> Result<World> {
+        Ok(toml::from_str(map)?)
+    }
+
+    /// Distance from `origin` to the closest wall in the direction of `angle` (degrees).
+    pub fn cast_ray(&self, origin: Point, angle: f32) -> Option<f32> {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let direction = Point::new(cos, sin);
+
+        self.walls
+            .iter()
+            .filter_map(|wall| wall.intersect_ray(origin, direction))
+            .min_by(f32::total_cmp)
+    }
+
+    /// Distance from the given point to the closest wall.
+    pub fn clearance(&self, p: Point) -> Option<f32> {
+        self.walls
+            .iter()
+            .map(|wall| wall.distance_to(p))
+            .min_by(f32::total_cmp)
+    }
+
+    pub fn is_on_line(&self, p: Point) -> bool {
+        self.lines
+            .iter()
+            .any(|line| line.distance_to(p) <= self.line_width / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn segment(from: (f32, f32), to: (f32, f32)) -> Segment {
+        Segment {
+            from: Point::new(from.0, from.1),
+            to: Point::new(to.0, to.1),
+        }
+    }
+
+    #[test]
+    fn distance_to_segment() {
+        let wall = segment((0.0, 0.0), (100.0, 0.0));
+
+        assert!((wall.distance_to(Point::new(50.0, 30.0)) - 30.0).abs() < EPSILON);
+        // past the end the closest point is the end itself
+        assert!((wall.distance_to(Point::new(130.0, 40.0)) - 50.0).abs() < EPSILON);
+        assert!((wall.distance_to(Point::new(-30.0, 0.0)) - 30.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn distance_to_degenerate_segment() {
+        let dot = segment((10.0, 10.0), (10.0, 10.0));
+
+        assert!((dot.distance_to(Point::new(13.0, 14.0)) - 5.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn ray_crosses_segment() {
+        let wall = segment((100.0, -50.0), (100.0, 50.0));
+
+        let hit = wall.intersect_ray(Point::new(0.0, 0.0), Point::new(1.0, 0.0));
+        assert!((hit.unwrap() - 100.0).abs() < EPSILON);
+
+        let (sin, cos) = 45f32.to_radians().sin_cos();
+        let hit = wall.intersect_ray(Point::new(0.0, -50.0), Point::new(cos, sin));
+        assert!((hit.unwrap() - 100.0 * 2f32.sqrt()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn ray_misses_segment() {
+        let wall = segment((100.0, -50.0), (100.0, 50.0));
+
+        // passes by the end
+        assert_eq!(
+            wall.intersect_ray(Point::new(0.0, 60.0), Point::new(1.0, 0.0)),
+            None
+        );
+        // points away
+        assert_eq!(
+            wall.intersect_ray(Point::new(0.0, 0.0), Point::new(-1.0, 0.0)),
+            None
+        );
+        // parallel
+        assert_eq!(
+            wall.intersect_ray(Point::new(0.0, 0.0), Point::new(0.0, 1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn pose_to_world() {
+        let pose = Pose {
+            x: 10.0,
+            y: 20.0,
+            heading: 90.0,
+        };
+
+        let point = pose.to_world(100.0, 30.0);
+        assert!((point.x - -20.0).abs() < EPSILON);
+        assert!((point.y - 120.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn parse_map() {
+        let world = World::parse(
+            r#"
+            [start]
+            x = 100.0
+            y = 200.0
+            heading = 45.0
+
+            [[walls]]
+            from = [0.0, 0.0]
+            to = [1000.0, 0.0]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            world.start,
+            Pose {
+                x: 100.0,
+                y: 200.0,
+                heading: 45.0
+            }
+        );
+        assert_eq!(world.walls, vec![segment((0.0, 0.0), (1000.0, 0.0))]);
+        assert!(world.lines.is_empty());
+        assert_eq!(world.line_width, DEFAULT_LINE_WIDTH);
+    }
+
+    #[test]
+    fn parse_invalid_map() {
+        assert!(World::parse("walls = 3").is_err());
+    }
+
+    #[test]
+    fn ray_hits_closest_wall() {
+        let world = World {
+            walls: vec![
+                segment((300.0, -100.0), (300.0, 100.0)),
+                segment((200.0, -100.0), (200.0, 100.0)),
+            ],
+            ..World::default()
+        };
+
+        let distance = world.cast_ray(Point::new(0.0, 0.0), 0.0).unwrap();
+        assert!((distance - 200.0).abs() < EPSILON);
+
+        assert_eq!(world.cast_ray(Point::new(0.0, 0.0), 180.0), None);
+    }
+
+    #[test]
+    fn clearance_and_lines() {
+        let world = World {
+            walls: vec![segment((0.0, 100.0), (1000.0, 100.0))],
+            lines: vec![segment((0.0, 0.0), (1000.0, 0.0))],
+            line_width: 20.0,
+            ..World::default()
+        };
+
+        assert!((world.clearance(Point::new(500.0, 30.0)).unwrap() - 70.0).abs() < EPSILON);
+        assert!(world.is_on_line(Point::new(500.0, 9.0)));
+        assert!(!world.is_on_line(Point::new(500.0, 11.0)));
+
+        assert_eq!(World::default().clearance(Point::new(0.0, 0.0)), None);
+    }
+}
@@ -1,17 +1,141 @@
+use std::sync::OnceLock;
+use std::time::Instant;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use crate::RoverError;
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
 pub enum MoveType {
     Forward(u8),
     Backward(u8),
     SpinCW(u8),
     SpinCCW(u8),
+    /// Left and right side speeds, negative ones move backward.
+    Drive(i16, i16),
     None
 }
 
+/// Directions the looker can turn to, as (min, max) degrees for each axis.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
+pub struct LookLimits {
+    pub h: (i16, i16),
+    pub v: (i16, i16),
+}
+
+impl LookLimits {
+    /// Direction the looker actually turns to when asked to look at (h, v).
+    pub fn clamp(&self, h: i16, v: i16) -> (i16, i16) {
+        let within = |degrees: i16, (a, b): (i16, i16)| degrees.clamp(a.min(b), a.max(b));
+
+        (within(h, self.h), within(v, self.v))
+    }
+}
+
+/// Where a sensor is mounted on the rover, as seen from behind.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+pub enum SensorPosition {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+pub enum SensorKind {
+    Obstacle,
+    Line,
+    Distance,
+}
+
+/// Sensor the rover is equipped with.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+pub struct SensorDescriptor {
+    pub kind: SensorKind,
+    pub position: SensorPosition,
+}
+
+/// Monotonic time a reading was taken at, in ms since the first reading taken by the process.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub struct Timestamp(pub u64);
+
+impl Timestamp {
+    pub fn now() -> Timestamp {
+        static START: OnceLock<Instant> = OnceLock::new();
+
+        Timestamp(START.get_or_init(Instant::now).elapsed().as_millis() as u64)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, PartialOrd, Copy, Clone)]
+pub struct Millimeters(pub f32);
+
+/// State of a binary sensor, e.g. whether it sees an obstacle.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+pub struct Detection {
+    pub position: SensorPosition,
+    pub detected: bool,
+}
+
+/// States of all binary sensors of one kind, read together.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct DetectionReading {
+    pub timestamp: Timestamp,
+    pub detections: Vec<Detection>,
+}
+
+impl DetectionReading {
+    /// Reading taken now.
+    pub fn new(detections: Vec<Detection>) -> DetectionReading {
+        DetectionReading {
+            timestamp: Timestamp::now(),
+            detections,
+        }
+    }
+
+    /// State of the sensor at given position, if there is one.
+    pub fn at(&self, position: SensorPosition) -> Option<bool> {
+        self.detections
+            .iter()
+            .find(|d| d.position == position)
+            .map(|d| d.detected)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
+pub struct DistanceReading {
+    pub timestamp: Timestamp,
+    pub distance: Millimeters,
+
+    /// False if nothing was found within sensor range, `distance` is the range then.
+    pub valid: bool,
+}
+
+impl DistanceReading {
+    /// Reading taken now.
+    pub fn new(distance: Millimeters, valid: bool) -> DistanceReading {
+        DistanceReading {
+            timestamp: Timestamp::now(),
+            distance,
+            valid,
+        }
+    }
+}
+
+/// All sensor readings taken together, with the motion and look direction they were taken in.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RoverSnapshot {
+    pub obstacles: DetectionReading,
+    pub lines: DetectionReading,
+    pub distance: DistanceReading,
+
+    /// Absent if the sensor does not know how the rover moves.
+    pub move_type: Option<MoveType>,
+
+    /// Absent if the sensor does not know where the rover looks.
+    pub look_direction: Option<(i16, i16)>,
+}
+
 pub trait Mover {
     type Error: RoverError;
 
@@ -21,6 +145,10 @@ pub trait Mover {
     fn spin_right(&mut self, speed: u8) -> Result<(), Self::Error>;
     fn spin_left(&mut self, speed: u8) -> Result<(), Self::Error>;
 
+    /// Drives left and right sides with given speeds in [-255; 255] range (larger ones are cut),
+    /// negative speeds move backward.
+    fn drive(&mut self, left: i16, right: i16) -> Result<(), Self::Error>;
+
     fn get_move_type(&self) -> Result<MoveType, Self::Error>;
 
     fn reset(&mut self) -> Result<(), Self::Error> {
@@ -34,6 +162,7 @@ pub trait Looker {
     fn look_at(&mut self, h: i16, v: i16) -> Result<(), Self::Error>;
 
     fn get_look_direction(&self) -> Result<(i16, i16), Self::Error>;
+    fn get_look_limits(&self) -> Result<LookLimits, Self::Error>;
 
     fn reset(&mut self) -> Result<(), Self::Error> {
         Ok(())
@@ -43,9 +172,22 @@ pub trait Looker {
 pub trait Sensor {
     type Error: RoverError;
 
-    fn get_obstacles(&self) -> Result<Vec<bool>, Self::Error>;
-    fn get_lines(&self) -> Result<Vec<bool>, Self::Error>;
-    fn scan_distance(&mut self) -> Result<f32, Self::Error>;
+    fn get_sensors(&self) -> Result<Vec<SensorDescriptor>, Self::Error>;
+
+    fn get_obstacles(&self) -> Result<DetectionReading, Self::Error>;
+    fn get_lines(&self) -> Result<DetectionReading, Self::Error>;
+    fn scan_distance(&mut self) -> Result<DistanceReading, Self::Error>;
+
+    /// Reads all sensors at once.
+    fn snapshot(&mut self) -> Result<RoverSnapshot, Self::Error> {
+        Ok(RoverSnapshot {
+            obstacles: self.get_obstacles()?,
+            lines: self.get_lines()?,
+            distance: self.scan_distance()?,
+            move_type: None,
+            look_direction: None,
+        })
+    }
 
     fn reset(&mut self) -> Result<(), Self::Error> {
         Ok(())
@@ -61,9 +203,16 @@ pub trait AsyncMover {
     async fn move_backward(&mut self, speed: u8) -> Result<(), Self::Error>;
     async fn spin_right(&mut self, speed: u8) -> Result<(), Self::Error>;
     async fn spin_left(&mut self, speed: u8) -> Result<(), Self::Error>;
+    async fn drive(&mut self, left: i16, right: i16) -> Result<(), Self::Error>;
 
     async fn get_move_type(&self) -> Result<MoveType, Self::Error>;
 
+    /// Confirms the controlling party is still there, so that current motion may go on.
+    /// Only matters for remote rovers.
+    async fn heartbeat(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     async fn reset(&mut self) -> Result<(), Self::Error> {
         Ok(())
     }
@@ -76,6 +225,7 @@ pub trait AsyncLooker {
     async fn look_at(&mut self, h: i16, v: i16) -> Result<(), Self::Error>;
 
     async fn get_look_direction(&self) -> Result<(i16, i16), Self::Error>;
+    async fn get_look_limits(&self) -> Result<LookLimits, Self::Error>;
 
     async fn reset(&mut self) -> Result<(), Self::Error> {
         Ok(())
@@ -86,9 +236,22 @@ pub trait AsyncLooker {
 pub trait AsyncSensor {
     type Error: RoverError;
 
-    async fn get_obstacles(&self) -> Result<Vec<bool>, Self::Error>;
-    async fn get_lines(&self) -> Result<Vec<bool>, Self::Error>;
-    async fn scan_distance(&mut self) -> Result<f32, Self::Error>;
+    async fn get_sensors(&self) -> Result<Vec<SensorDescriptor>, Self::Error>;
+
+    async fn get_obstacles(&self) -> Result<DetectionReading, Self::Error>;
+    async fn get_lines(&self) -> Result<DetectionReading, Self::Error>;
+    async fn scan_distance(&mut self) -> Result<DistanceReading, Self::Error>;
+
+    /// Reads all sensors at once.
+    async fn snapshot(&mut self) -> Result<RoverSnapshot, Self::Error> {
+        Ok(RoverSnapshot {
+            obstacles: self.get_obstacles().await?,
+            lines: self.get_lines().await?,
+            distance: self.scan_distance().await?,
+            move_type: None,
+            look_direction: None,
+        })
+    }
 
     async fn reset(&mut self) -> Result<(), Self::Error> {
         Ok(())
@@ -1,4 +1,5 @@
 pub mod api;
+pub mod session;
 pub mod util;
 
 use std::error::Error;
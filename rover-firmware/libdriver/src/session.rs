@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+    DetectionReading, DistanceReading, LookLimits, MoveType, RoverSnapshot, SensorDescriptor,
+};
+
+/// Rover control that a driver call goes to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
+pub enum Device {
+    Mover,
+    Looker,
+    Sensor,
+}
+
+/// Driver call with its arguments.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone)]
+pub enum DriverCall {
+    Stop,
+    MoveForward(u8),
+    MoveBackward(u8),
+    SpinRight(u8),
+    SpinLeft(u8),
+    Drive(i16, i16),
+    GetMoveType,
+    Heartbeat,
+    ResetMover,
+
+    LookAt(i16, i16),
+    GetLookDirection,
+    GetLookLimits,
+    ResetLooker,
+
+    GetSensors,
+    GetObstacles,
+    GetLines,
+    ScanDistance,
+    Snapshot,
+    ResetSensor,
+}
+
+impl DriverCall {
+    pub fn device(&self) -> Device {
+        match self {
+            DriverCall::Stop
+            | DriverCall::MoveForward(_)
+            | DriverCall::MoveBackward(_)
+            | DriverCall::SpinRight(_)
+            | DriverCall::SpinLeft(_)
+            | DriverCall::Drive(_, _)
+            | DriverCall::GetMoveType
+            | DriverCall::Heartbeat
+            | DriverCall::ResetMover => Device::Mover,
+            DriverCall::LookAt(_, _)
+            | DriverCall::GetLookDirection
+            | DriverCall::GetLookLimits
+            | DriverCall::ResetLooker => Device::Looker,
+            DriverCall::GetSensors
+            | DriverCall::GetObstacles
+            | DriverCall::GetLines
+            | DriverCall::ScanDistance
+            | DriverCall::Snapshot
+            | DriverCall::ResetSensor => Device::Sensor,
+        }
+    }
+
+    /// Whether the call tells the rover to move or to look elsewhere.
+    pub fn is_command(&self) -> bool {
+        matches!(
+            self,
+            DriverCall::Stop
+                | DriverCall::MoveForward(_)
+                | DriverCall::MoveBackward(_)
+                | DriverCall::SpinRight(_)
+                | DriverCall::SpinLeft(_)
+                | DriverCall::Drive(_, _)
+                | DriverCall::LookAt(_, _)
+        )
+    }
+}
+
+/// Value returned by a driver call.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum DriverReply {
+    Done,
+    MoveType(MoveType),
+    LookDirection((i16, i16)),
+    LookLimits(LookLimits),
+    Sensors(Vec<SensorDescriptor>),
+    Detections(DetectionReading),
+    Distance(DistanceReading),
+    Snapshot(RoverSnapshot),
+}
+
+/// Driver call made during a session, with its outcome.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CallRecord {
+    /// Time since session start, in ms.
+    pub time_ms: u64,
+    pub call: DriverCall,
+    pub result: Result<DriverReply, String>,
+}
+
+/// Driver calls in the order they were made.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct Session {
+    pub calls: Vec<CallRecord>,
+}
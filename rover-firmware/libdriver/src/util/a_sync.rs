@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 use tokio::task::spawn_blocking;
 
-use crate::api::{AsyncLooker, AsyncMover, AsyncSensor, Looker, Mover, MoveType, Sensor};
+use crate::api::{
+    AsyncLooker, AsyncMover, AsyncSensor, DetectionReading, DistanceReading, LookLimits, Looker,
+    Mover, MoveType, RoverSnapshot, Sensor, SensorDescriptor,
+};
 use std::sync::{Arc, Mutex};
 
 impl<T> From<T> for AsyncRover<T>
@@ -68,6 +71,14 @@ where
             .expect("Async wrapper error")
     }
 
+    async fn drive(&mut self, left: i16, right: i16) -> Result<(), Self::Error> {
+        let mover_ref = Arc::clone(&self.0);
+
+        spawn_blocking(move || mover_ref.lock().unwrap().drive(left, right))
+            .await
+            .expect("Async wrapper error")
+    }
+
     async fn get_move_type(&self) -> Result<MoveType, Self::Error> {
         let mover_ref = Arc::clone(&self.0);
 
@@ -75,6 +86,14 @@ where
             .await
             .expect("Async wrapper error")
     }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        let mover_ref = Arc::clone(&self.0);
+
+        spawn_blocking(move || Mover::reset(&mut *mover_ref.lock().unwrap()))
+            .await
+            .expect("Async wrapper error")
+    }
 }
 
 #[async_trait]
@@ -99,6 +118,22 @@ where
             .await
             .expect("Async wrapper error")
     }
+
+    async fn get_look_limits(&self) -> Result<LookLimits, Self::Error> {
+        let looker_ref = Arc::clone(&self.0);
+
+        spawn_blocking(move || looker_ref.lock().unwrap().get_look_limits())
+            .await
+            .expect("Async wrapper error")
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        let looker_ref = Arc::clone(&self.0);
+
+        spawn_blocking(move || Looker::reset(&mut *looker_ref.lock().unwrap()))
+            .await
+            .expect("Async wrapper error")
+    }
 }
 
 #[async_trait]
@@ -108,7 +143,15 @@ where
 {
     type Error = T::Error;
 
-    async fn get_obstacles(&self) -> Result<Vec<bool>, Self::Error> {
+    async fn get_sensors(&self) -> Result<Vec<SensorDescriptor>, Self::Error> {
+        let sensor_ref = Arc::clone(&self.0);
+
+        spawn_blocking(move || sensor_ref.lock().unwrap().get_sensors())
+            .await
+            .expect("Async wrapper error")
+    }
+
+    async fn get_obstacles(&self) -> Result<DetectionReading, Self::Error> {
         let sensor_ref = Arc::clone(&self.0);
 
         spawn_blocking(move || sensor_ref.lock().unwrap().get_obstacles())
@@ -116,7 +159,7 @@ where
             .expect("Async wrapper error")
     }
 
-    async fn get_lines(&self) -> Result<Vec<bool>, Self::Error> {
+    async fn get_lines(&self) -> Result<DetectionReading, Self::Error> {
         let sensor_ref = Arc::clone(&self.0);
 
         spawn_blocking(move || sensor_ref.lock().unwrap().get_lines())
@@ -124,11 +167,27 @@ where
             .expect("Async wrapper error")
     }
 
-    async fn scan_distance(&mut self) -> Result<f32, Self::Error> {
+    async fn scan_distance(&mut self) -> Result<DistanceReading, Self::Error> {
         let sensor_ref = Arc::clone(&self.0);
 
         spawn_blocking(move || sensor_ref.lock().unwrap().scan_distance())
             .await
             .expect("Async wrapper error")
     }
+
+    async fn snapshot(&mut self) -> Result<RoverSnapshot, Self::Error> {
+        let sensor_ref = Arc::clone(&self.0);
+
+        spawn_blocking(move || sensor_ref.lock().unwrap().snapshot())
+            .await
+            .expect("Async wrapper error")
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        let sensor_ref = Arc::clone(&self.0);
+
+        spawn_blocking(move || Sensor::reset(&mut *sensor_ref.lock().unwrap()))
+            .await
+            .expect("Async wrapper error")
+    }
 }
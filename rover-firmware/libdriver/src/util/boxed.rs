@@ -0,0 +1,264 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use async_trait::async_trait;
+
+use crate::api::{
+    AsyncLooker, AsyncMover, AsyncSensor, DetectionReading, DistanceReading, LookLimits, MoveType,
+    RoverSnapshot, SensorDescriptor,
+};
+
+/// Error of a type-erased driver, transparent to the error it wraps.
+pub struct DriverError(Box<dyn Error + Send + Sync>);
+
+impl DriverError {
+    pub fn new<E: Error + Send + Sync + 'static>(error: E) -> DriverError {
+        DriverError(Box::new(error))
+    }
+}
+
+impl Debug for DriverError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for DriverError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for DriverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+pub type BoxedMover = Box<dyn AsyncMover<Error = DriverError> + Send + Sync>;
+pub type BoxedLooker = Box<dyn AsyncLooker<Error = DriverError> + Send + Sync>;
+pub type BoxedSensor = Box<dyn AsyncSensor<Error = DriverError> + Send + Sync>;
+
+pub fn boxed_mover<T>(mover: T) -> BoxedMover
+where
+    T: AsyncMover + Send + Sync + 'static,
+{
+    Box::new(Erased(mover))
+}
+
+pub fn boxed_looker<T>(looker: T) -> BoxedLooker
+where
+    T: AsyncLooker + Send + Sync + 'static,
+{
+    Box::new(Erased(looker))
+}
+
+pub fn boxed_sensor<T>(sensor: T) -> BoxedSensor
+where
+    T: AsyncSensor + Send + Sync + 'static,
+{
+    Box::new(Erased(sensor))
+}
+
+// hides concrete error type of the wrapped driver behind DriverError
+struct Erased<T>(T);
+
+#[async_trait]
+impl<T> AsyncMover for Erased<T>
+where
+    T: AsyncMover + Send + Sync,
+{
+    type Error = DriverError;
+
+    async fn stop(&mut self) -> Result<(), Self::Error> {
+        self.0.stop().await.map_err(DriverError::new)
+    }
+
+    async fn move_forward(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.0.move_forward(speed).await.map_err(DriverError::new)
+    }
+
+    async fn move_backward(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.0.move_backward(speed).await.map_err(DriverError::new)
+    }
+
+    async fn spin_right(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.0.spin_right(speed).await.map_err(DriverError::new)
+    }
+
+    async fn spin_left(&mut self, speed: u8) -> Result<(), Self::Error> {
+        self.0.spin_left(speed).await.map_err(DriverError::new)
+    }
+
+    async fn drive(&mut self, left: i16, right: i16) -> Result<(), Self::Error> {
+        self.0.drive(left, right).await.map_err(DriverError::new)
+    }
+
+    async fn get_move_type(&self) -> Result<MoveType, Self::Error> {
+        self.0.get_move_type().await.map_err(DriverError::new)
+    }
+
+    async fn heartbeat(&mut self) -> Result<(), Self::Error> {
+        self.0.heartbeat().await.map_err(DriverError::new)
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncMover::reset(&mut self.0).await.map_err(DriverError::new)
+    }
+}
+
+#[async_trait]
+impl<T> AsyncLooker for Erased<T>
+where
+    T: AsyncLooker + Send + Sync,
+{
+    type Error = DriverError;
+
+    async fn look_at(&mut self, h: i16, v: i16) -> Result<(), Self::Error> {
+        self.0.look_at(h, v).await.map_err(DriverError::new)
+    }
+
+    async fn get_look_direction(&self) -> Result<(i16, i16), Self::Error> {
+        self.0.get_look_direction().await.map_err(DriverError::new)
+    }
+
+    async fn get_look_limits(&self) -> Result<LookLimits, Self::Error> {
+        self.0.get_look_limits().await.map_err(DriverError::new)
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncLooker::reset(&mut self.0).await.map_err(DriverError::new)
+    }
+}
+
+#[async_trait]
+impl<T> AsyncSensor for Erased<T>
+where
+    T: AsyncSensor + Send + Sync,
+{
+    type Error = DriverError;
+
+    async fn get_sensors(&self) -> Result<Vec<SensorDescriptor>, Self::Error> {
+        self.0.get_sensors().await.map_err(DriverError::new)
+    }
+
+    async fn get_obstacles(&self) -> Result<DetectionReading, Self::Error> {
+        self.0.get_obstacles().await.map_err(DriverError::new)
+    }
+
+    async fn get_lines(&self) -> Result<DetectionReading, Self::Error> {
+        self.0.get_lines().await.map_err(DriverError::new)
+    }
+
+    async fn scan_distance(&mut self) -> Result<DistanceReading, Self::Error> {
+        self.0.scan_distance().await.map_err(DriverError::new)
+    }
+
+    async fn snapshot(&mut self) -> Result<RoverSnapshot, Self::Error> {
+        self.0.snapshot().await.map_err(DriverError::new)
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncSensor::reset(&mut self.0).await.map_err(DriverError::new)
+    }
+}
+
+#[async_trait]
+impl<T> AsyncMover for Box<T>
+where
+    T: AsyncMover + Send + Sync + ?Sized,
+{
+    type Error = T::Error;
+
+    async fn stop(&mut self) -> Result<(), Self::Error> {
+        (**self).stop().await
+    }
+
+    async fn move_forward(&mut self, speed: u8) -> Result<(), Self::Error> {
+        (**self).move_forward(speed).await
+    }
+
+    async fn move_backward(&mut self, speed: u8) -> Result<(), Self::Error> {
+        (**self).move_backward(speed).await
+    }
+
+    async fn spin_right(&mut self, speed: u8) -> Result<(), Self::Error> {
+        (**self).spin_right(speed).await
+    }
+
+    async fn spin_left(&mut self, speed: u8) -> Result<(), Self::Error> {
+        (**self).spin_left(speed).await
+    }
+
+    async fn drive(&mut self, left: i16, right: i16) -> Result<(), Self::Error> {
+        (**self).drive(left, right).await
+    }
+
+    async fn get_move_type(&self) -> Result<MoveType, Self::Error> {
+        (**self).get_move_type().await
+    }
+
+    async fn heartbeat(&mut self) -> Result<(), Self::Error> {
+        (**self).heartbeat().await
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncMover::reset(&mut **self).await
+    }
+}
+
+#[async_trait]
+impl<T> AsyncLooker for Box<T>
+where
+    T: AsyncLooker + Send + Sync + ?Sized,
+{
+    type Error = T::Error;
+
+    async fn look_at(&mut self, h: i16, v: i16) -> Result<(), Self::Error> {
+        (**self).look_at(h, v).await
+    }
+
+    async fn get_look_direction(&self) -> Result<(i16, i16), Self::Error> {
+        (**self).get_look_direction().await
+    }
+
+    async fn get_look_limits(&self) -> Result<LookLimits, Self::Error> {
+        (**self).get_look_limits().await
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncLooker::reset(&mut **self).await
+    }
+}
+
+#[async_trait]
+impl<T> AsyncSensor for Box<T>
+where
+    T: AsyncSensor + Send + Sync + ?Sized,
+{
+    type Error = T::Error;
+
+    async fn get_sensors(&self) -> Result<Vec<SensorDescriptor>, Self::Error> {
+        (**self).get_sensors().await
+    }
+
+    async fn get_obstacles(&self) -> Result<DetectionReading, Self::Error> {
+        (**self).get_obstacles().await
+    }
+
+    async fn get_lines(&self) -> Result<DetectionReading, Self::Error> {
+        (**self).get_lines().await
+    }
+
+    async fn scan_distance(&mut self) -> Result<DistanceReading, Self::Error> {
+        (**self).scan_distance().await
+    }
+
+    async fn snapshot(&mut self) -> Result<RoverSnapshot, Self::Error> {
+        (**self).snapshot().await
+    }
+
+    async fn reset(&mut self) -> Result<(), Self::Error> {
+        AsyncSensor::reset(&mut **self).await
+    }
+}
@@ -1,4 +1,5 @@
 pub mod a_sync;
+pub mod boxed;
 pub mod splittable;
 
 // RaspberryPi model B+ physical pins to BCM map
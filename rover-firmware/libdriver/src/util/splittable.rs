@@ -1,6 +1,9 @@
 use std::sync::{Arc, Mutex};
 
-use crate::api::{Looker, Mover, MoveType, Sensor};
+use crate::api::{
+    DetectionReading, DistanceReading, LookLimits, Looker, Mover, MoveType, RoverSnapshot,
+    Sensor, SensorDescriptor,
+};
 
 pub struct MoverPart<'a, T>(Arc<Mutex<&'a mut T>>)
 where
@@ -37,10 +40,20 @@ where
         mover.spin_left(speed)
     }
 
+    fn drive(&mut self, left: i16, right: i16) -> Result<(), T::Error> {
+        let mut mover = self.0.lock().unwrap();
+        mover.drive(left, right)
+    }
+
     fn get_move_type(&self) -> Result<MoveType, Self::Error> {
         let mover = self.0.lock().unwrap();
         mover.get_move_type()
     }
+
+    fn reset(&mut self) -> Result<(), Self::Error> {
+        let mut mover = self.0.lock().unwrap();
+        Mover::reset(*mover)
+    }
 }
 
 pub struct LookerPart<'a, T>(Arc<Mutex<&'a mut T>>)
@@ -62,6 +75,16 @@ where
         let looker = self.0.lock().unwrap();
         looker.get_look_direction()
     }
+
+    fn get_look_limits(&self) -> Result<LookLimits, Self::Error> {
+        let looker = self.0.lock().unwrap();
+        looker.get_look_limits()
+    }
+
+    fn reset(&mut self) -> Result<(), Self::Error> {
+        let mut looker = self.0.lock().unwrap();
+        Looker::reset(*looker)
+    }
 }
 
 pub struct SensorPart<'a, T>(Arc<Mutex<&'a mut T>>)
@@ -74,27 +97,42 @@ where
 {
     type Error = T::Error;
 
-    fn get_obstacles(&self) -> Result<Vec<bool>, Self::Error> {
+    fn get_sensors(&self) -> Result<Vec<SensorDescriptor>, Self::Error> {
+        let sensor = self.0.lock().unwrap();
+        sensor.get_sensors()
+    }
+
+    fn get_obstacles(&self) -> Result<DetectionReading, Self::Error> {
         let sensor = self.0.lock().unwrap();
         sensor.get_obstacles()
     }
 
-    fn get_lines(&self) -> Result<Vec<bool>, Self::Error> {
+    fn get_lines(&self) -> Result<DetectionReading, Self::Error> {
         let sensor = self.0.lock().unwrap();
         sensor.get_lines()
     }
 
-    fn scan_distance(&mut self) -> Result<f32, Self::Error> {
+    fn scan_distance(&mut self) -> Result<DistanceReading, Self::Error> {
         let mut sensor = self.0.lock().unwrap();
         sensor.scan_distance()
     }
+
+    fn snapshot(&mut self) -> Result<RoverSnapshot, Self::Error> {
+        let mut sensor = self.0.lock().unwrap();
+        sensor.snapshot()
+    }
+
+    fn reset(&mut self) -> Result<(), Self::Error> {
+        let mut sensor = self.0.lock().unwrap();
+        Sensor::reset(*sensor)
+    }
 }
 
 pub trait SplittableRover
 where
     Self: Sized + Mover + Looker + Sensor,
 {
-    fn split(&mut self) -> (MoverPart<Self>, LookerPart<Self>, SensorPart<Self>) {
+    fn split(&mut self) -> (MoverPart<'_, Self>, LookerPart<'_, Self>, SensorPart<'_, Self>) {
         let l = Arc::new(Mutex::new(self));
 
         (
@@ -1,7 +1,8 @@
 use std::borrow::Cow;
 
-use config::Config;
+use config::{Config, ConfigError};
 use log::debug;
+use serde::Deserialize;
 
 use crate::logger::init_log;
 use crate::sys::normalize_path;
@@ -39,3 +40,15 @@ pub fn bootstrap(config_path: &str) -> Result<Config, Box<dyn std::error::Error>
 
     Ok(settings)
 }
+
+/// Reads a setting that may be absent.
+pub fn get_optional<'de, T: Deserialize<'de>>(
+    settings: &Config,
+    key: &str,
+) -> Result<Option<T>, ConfigError> {
+    match settings.get::<T>(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(ConfigError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
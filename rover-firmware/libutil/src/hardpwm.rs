@@ -0,0 +1,50 @@
+use rppal::pwm::{Channel, Polarity};
+
+use crate::pwm::{Pwm, Result};
+
+/// PWM generated by the SoC's PWM peripheral. Pins the channels are routed to are chosen by
+/// `pwm` or `pwm-2chan` device tree overlay.
+pub struct HardPwm {
+    pwm: rppal::pwm::Pwm,
+    frequency: f32,
+    duty_cycle: f32,
+}
+
+impl HardPwm {
+    pub fn new(channel: Channel, frequency: f32, duty_cycle: f32) -> Result<HardPwm> {
+        let pwm = rppal::pwm::Pwm::with_frequency(
+            channel,
+            frequency as f64,
+            duty_cycle as f64,
+            Polarity::Normal,
+            true,
+        )?;
+
+        Ok(HardPwm {
+            pwm,
+            frequency,
+            duty_cycle,
+        })
+    }
+}
+
+impl Pwm for HardPwm {
+    fn set_frequency(&mut self, frequency: f32) -> Result<()> {
+        if frequency != self.frequency {
+            self.pwm
+                .set_frequency(frequency as f64, self.duty_cycle as f64)?;
+            self.frequency = frequency;
+        }
+
+        Ok(())
+    }
+
+    fn set_duty_cycle(&mut self, duty_cycle: f32) -> Result<()> {
+        if duty_cycle != self.duty_cycle {
+            self.pwm.set_duty_cycle(duty_cycle as f64)?;
+            self.duty_cycle = duty_cycle;
+        }
+
+        Ok(())
+    }
+}
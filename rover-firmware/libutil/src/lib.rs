@@ -5,11 +5,21 @@ pub mod app;
 #[cfg(feature = "logger")]
 pub mod logger;
 
+#[cfg(feature = "pwm")]
+pub mod pwm;
+#[cfg(feature = "pwm")]
+pub use pwm::Pwm;
+
 #[cfg(feature = "softpwm")]
 pub mod softpwm;
 #[cfg(feature = "softpwm")]
 pub use softpwm::SoftPwm;
 
+#[cfg(feature = "hardpwm")]
+pub mod hardpwm;
+#[cfg(feature = "hardpwm")]
+pub use hardpwm::HardPwm;
+
 #[cfg(feature = "sys")]
 pub mod sys;
 
@@ -0,0 +1,22 @@
+use thiserror::Error as LibError;
+
+#[derive(Debug, LibError)]
+pub enum Error {
+    #[error("PWM update error")]
+    UpdateError,
+
+    #[cfg(feature = "hardpwm")]
+    #[error("Hardware PWM error: {0:?}")]
+    Hardware(#[from] rppal::pwm::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Pulse-width modulated output.
+pub trait Pwm: Send {
+    /// Sets frequency, in Hz.
+    fn set_frequency(&mut self, frequency: f32) -> Result<()>;
+
+    /// Sets the portion of the period when output is active, in [0; 1] range.
+    fn set_duty_cycle(&mut self, duty_cycle: f32) -> Result<()>;
+}
@@ -3,9 +3,11 @@ use std::thread;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
-use log::{error, trace};
+use log::trace;
 use rppal::gpio::{Level, OutputPin};
-use thiserror::Error as LibError;
+
+pub use crate::pwm::{Error, Result};
+use crate::pwm::Pwm;
 
 enum PwmUpdate {
     Stop,
@@ -13,14 +15,6 @@ enum PwmUpdate {
     DutyCycle(f32),
 }
 
-#[derive(Debug, LibError)]
-pub enum Error {
-    #[error("PWM update error")]
-    UpdateError,
-}
-
-pub type Result<T> = std::result::Result<T, Error>;
-
 pub struct SoftPwm {
     channel: mpsc::Sender<PwmUpdate>,
     worker: Option<JoinHandle<()>>,
@@ -41,14 +35,16 @@ impl SoftPwm {
             })),
         }
     }
+}
 
-    pub fn set_frequency(&mut self, new_frequency: f32) -> Result<()> {
+impl Pwm for SoftPwm {
+    fn set_frequency(&mut self, new_frequency: f32) -> Result<()> {
         self.channel
             .send(PwmUpdate::Frequency(new_frequency))
             .map_err(|_| Error::UpdateError)
     }
 
-    pub fn set_duty_cycle(&mut self, new_duty_cycle: f32) -> Result<()> {
+    fn set_duty_cycle(&mut self, new_duty_cycle: f32) -> Result<()> {
         self.channel
             .send(PwmUpdate::DutyCycle(new_duty_cycle))
             .map_err(|_| Error::UpdateError)
@@ -141,13 +137,9 @@ impl SoftPwmWorker {
     }
 
     fn run(&mut self) {
-        loop {
-            if let Some((time_on, _)) = self.check_updates(self.time_on) {
-                //                println!("Pin {} HIGH for {} ns.", self.pin, time_on);
-                self.drive(time_on, Level::High);
-            } else {
-                break;
-            }
+        while let Some((time_on, _)) = self.check_updates(self.time_on) {
+            //                println!("Pin {} HIGH for {} ns.", self.pin, time_on);
+            self.drive(time_on, Level::High);
 
             if let Some((_, time_off)) = self.check_updates(self.time_off) {
                 //                println!("Pin {} LOW for {} ns.", self.pin, time_off);
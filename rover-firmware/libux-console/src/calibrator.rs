@@ -0,0 +1,195 @@
+use std::io::{stdin, stdout, Stdout, Write};
+use std::time::Duration;
+
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::{IntoRawMode, RawTerminal};
+
+use libdriver_robohat::{Calibration, ServoChannels, ServoController, ServoPoint};
+
+use crate::Result;
+
+// pulse width range safe for hobby servos, in us
+const PULSE_WIDTH_MIN: i16 = 500;
+const PULSE_WIDTH_MAX: i16 = 2500;
+
+// pulse width adjustment steps, in us
+const COARSE_STEP: i16 = 10;
+const FINE_STEP: i16 = 1;
+
+#[derive(Copy, Clone)]
+enum Servo {
+    Pan,
+    Tilt,
+}
+
+#[derive(Copy, Clone)]
+enum Mark {
+    Min,
+    Max,
+    Center,
+}
+
+const STEPS: [(Servo, Mark, &str); 6] = [
+    (Servo::Pan, Mark::Min, "Turn the camera fully right"),
+    (Servo::Pan, Mark::Max, "Turn the camera fully left"),
+    (Servo::Pan, Mark::Center, "Turn the camera straight ahead"),
+    (Servo::Tilt, Mark::Min, "Tilt the camera fully up"),
+    (Servo::Tilt, Mark::Max, "Tilt the camera fully down"),
+    (Servo::Tilt, Mark::Center, "Tilt the camera level"),
+];
+
+/// Interactive wizard marking servo endpoints and center. Positions keep degrees of the initial
+/// calibration, only pulse widths are marked.
+pub struct Calibrator {
+    output: RawTerminal<Stdout>,
+    servo: Box<dyn ServoController>,
+    channels: ServoChannels,
+    calibration: Calibration,
+}
+
+impl Calibrator {
+    pub fn new(
+        servo: Box<dyn ServoController>,
+        channels: ServoChannels,
+        calibration: Calibration,
+    ) -> Result<Calibrator> {
+        Ok(Calibrator {
+            output: stdout().into_raw_mode()?,
+            servo,
+            channels,
+            calibration,
+        })
+    }
+
+    fn init_screen(out: &mut dyn Write) -> Result<()> {
+        write!(
+            out,
+            "{}{}Arrows adjust by {}us, '+'/'-' by {}us, 'Enter' marks position, 'Esc' aborts.{}",
+            termion::clear::All,
+            termion::cursor::Goto(1, 1),
+            COARSE_STEP,
+            FINE_STEP,
+            termion::cursor::Hide
+        )?;
+
+        out.flush()?;
+
+        Ok(())
+    }
+
+    fn print_step(
+        out: &mut dyn Write,
+        step: usize,
+        prompt: &str,
+        point: &ServoPoint,
+    ) -> Result<()> {
+        write!(
+            out,
+            "{}{}[{}/{}] {} ({} degrees).",
+            termion::cursor::Goto(1, 3),
+            termion::clear::CurrentLine,
+            step + 1,
+            STEPS.len(),
+            prompt,
+            point.degrees
+        )?;
+
+        write!(
+            out,
+            "{}{}Pulse width: {}us",
+            termion::cursor::Goto(1, 4),
+            termion::clear::CurrentLine,
+            point.pulse_width
+        )?;
+
+        out.flush()?;
+
+        Ok(())
+    }
+
+    fn point(&mut self, servo: Servo, mark: Mark) -> &mut ServoPoint {
+        let calibration = match servo {
+            Servo::Pan => &mut self.calibration.pan,
+            Servo::Tilt => &mut self.calibration.tilt,
+        };
+
+        match mark {
+            Mark::Min => &mut calibration.min,
+            Mark::Max => &mut calibration.max,
+            Mark::Center => &mut calibration.center,
+        }
+    }
+
+    fn apply(&mut self, servo: Servo, pulse_width: i16) -> Result<()> {
+        let channel = match servo {
+            Servo::Pan => self.channels.pan,
+            Servo::Tilt => self.channels.tilt,
+        };
+
+        self.servo
+            .set_pulse_width(channel, Duration::from_micros(pulse_width as u64))?;
+
+        Ok(())
+    }
+
+    /// Walks through all the marks, returns `None` if aborted.
+    pub fn run(&mut self) -> Result<Option<Calibration>> {
+        Self::init_screen(&mut self.output)?;
+
+        // start from the center so the servos do not jump between endpoints
+        let pan_center = self.calibration.pan.center.pulse_width;
+        let tilt_center = self.calibration.tilt.center.pulse_width;
+        self.apply(Servo::Pan, pan_center)?;
+        self.apply(Servo::Tilt, tilt_center)?;
+
+        let mut keys = stdin().keys();
+
+        for (step, &(servo, mark, prompt)) in STEPS.iter().enumerate() {
+            let mut point = *self.point(servo, mark);
+
+            self.apply(servo, point.pulse_width)?;
+            Self::print_step(&mut self.output, step, prompt, &point)?;
+
+            loop {
+                let delta = match (servo, keys.next()) {
+                    (_, Some(Ok(Key::Esc))) | (_, None) => return Ok(None),
+                    (_, Some(Ok(Key::Char('\n')))) => break,
+                    (_, Some(Ok(Key::Char('+')))) => FINE_STEP,
+                    (_, Some(Ok(Key::Char('-')))) => -FINE_STEP,
+                    (Servo::Pan, Some(Ok(Key::Left))) => COARSE_STEP,
+                    (Servo::Pan, Some(Ok(Key::Right))) => -COARSE_STEP,
+                    (Servo::Tilt, Some(Ok(Key::Down))) => COARSE_STEP,
+                    (Servo::Tilt, Some(Ok(Key::Up))) => -COARSE_STEP,
+                    _ => continue,
+                };
+
+                point.pulse_width =
+                    (point.pulse_width + delta).clamp(PULSE_WIDTH_MIN, PULSE_WIDTH_MAX);
+
+                self.apply(servo, point.pulse_width)?;
+                Self::print_step(&mut self.output, step, prompt, &point)?;
+            }
+
+            *self.point(servo, mark) = point;
+        }
+
+        self.apply(Servo::Pan, self.calibration.pan.center.pulse_width)?;
+        self.apply(Servo::Tilt, self.calibration.tilt.center.pulse_width)?;
+
+        Ok(Some(self.calibration))
+    }
+}
+
+impl Drop for Calibrator {
+    fn drop(&mut self) {
+        write!(
+            self.output,
+            "{}{}{}",
+            termion::clear::All,
+            termion::cursor::Goto(1, 1),
+            termion::cursor::Show
+        )
+        .unwrap();
+    }
+}
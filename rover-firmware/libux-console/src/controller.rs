@@ -7,13 +7,16 @@ use termion::event::Key;
 use termion::input::TermRead;
 use termion::raw::{IntoRawMode, RawTerminal};
 
-use libdriver::api::{AsyncLooker, AsyncMover, AsyncSensor};
+use libbehavior::{BehaviorConfig, BehaviorHost, LineFollowConfig, WanderConfig};
+use libdriver::api::{
+    AsyncLooker, AsyncMover, AsyncSensor, DetectionReading, DistanceReading, SensorPosition,
+};
 
 use crate::Result;
 
 pub struct RideController<T>
 where
-    T: AsyncMover + AsyncLooker + AsyncSensor,
+    T: AsyncMover + AsyncLooker + AsyncSensor + BehaviorHost + Send,
 {
     output: RawTerminal<Stdout>,
     rover: T,
@@ -21,7 +24,7 @@ where
 
 impl<T> RideController<T>
 where
-    T: AsyncMover + AsyncLooker + AsyncSensor,
+    T: AsyncMover + AsyncLooker + AsyncSensor + BehaviorHost + Send,
 {
     pub fn new(rover: T) -> Result<RideController<T>> {
         Ok(RideController {
@@ -33,7 +36,7 @@ where
     fn init_screen(out: &mut dyn Write) -> Result<()> {
         write!(
             out,
-            "{}{}Press 'Esc' to exit.{}",
+            "{}{}Press 'Esc' to exit, 'g' to wander, 'f' to follow line.{}",
             termion::clear::All,
             termion::cursor::Goto(1, 1),
             termion::cursor::Hide
@@ -44,7 +47,13 @@ where
         Ok(())
     }
 
-    fn print_run_params(out: &mut dyn Write, speed: u8, pan: i16, tilt: i16) -> Result<()> {
+    fn print_run_params(
+        out: &mut dyn Write,
+        speed: u8,
+        pan: i16,
+        tilt: i16,
+        behavior: Option<&str>,
+    ) -> Result<()> {
         write!(
             out,
             "{}{}Speed: {}",
@@ -62,41 +71,63 @@ where
             tilt
         )?;
 
+        write!(
+            out,
+            "{}{}Drive mode: {}",
+            termion::cursor::Goto(1, 4),
+            termion::clear::CurrentLine,
+            behavior.unwrap_or("manual")
+        )?;
+
         Ok(())
     }
 
     fn print_sensors(
         out: &mut dyn Write,
-        left_obstacle: bool,
-        right_obstacle: bool,
-        left_line: bool,
-        right_line: bool,
-        distance: f32,
+        obstacles: &DetectionReading,
+        lines: &DetectionReading,
+        distance: &DistanceReading,
     ) -> Result<()> {
         let (sx, sy) = termion::terminal_size()?;
 
+        let state = |reading: &DetectionReading, position| match reading.at(position) {
+            Some(true) => "1",
+            Some(false) => "0",
+            None => "-",
+        };
+
         write!(
             out,
             "{}Left obstacle: {}   Right obstacle: {}",
             termion::cursor::Goto(sx / 2 - 17, sy / 2),
-            if left_obstacle { 1 } else { 0 },
-            if right_obstacle { 1 } else { 0 }
+            state(obstacles, SensorPosition::Left),
+            state(obstacles, SensorPosition::Right)
         )?;
 
         write!(
             out,
             "{}Left line: {}   Right line: {}",
             termion::cursor::Goto(sx / 2 - 13, sy / 2 + 1),
-            if left_line { 1 } else { 0 },
-            if right_line { 1 } else { 0 }
+            state(lines, SensorPosition::Left),
+            state(lines, SensorPosition::Right)
         )?;
 
-        write!(
-            out,
-            "{}Distance to obstacle: {:3.3} m",
-            termion::cursor::Goto(sx / 2 - 14, sy / 2 + 2),
-            distance / 1000.0
-        )?;
+        if distance.valid {
+            write!(
+                out,
+                "{}{}Distance to obstacle: {:3.3} m",
+                termion::cursor::Goto(sx / 2 - 14, sy / 2 + 2),
+                termion::clear::CurrentLine,
+                distance.distance.0 / 1000.0
+            )?;
+        } else {
+            write!(
+                out,
+                "{}{}Distance to obstacle: out of range",
+                termion::cursor::Goto(sx / 2 - 14, sy / 2 + 2),
+                termion::clear::CurrentLine
+            )?;
+        }
 
         Ok(())
     }
@@ -118,10 +149,14 @@ where
         let mut pan: i16 = 0;
         let mut tilt: i16 = 0;
 
+        let limits = self.rover.get_look_limits().await?;
+
         self.rover.look_at(pan, tilt).await?;
 
+        let mut behavior = None;
+
         Self::print_direction(out, '_')?;
-        Self::print_run_params(out, speed, pan, tilt)?;
+        Self::print_run_params(out, speed, pan, tilt, behavior)?;
 
         out.flush()?;
 
@@ -156,38 +191,62 @@ where
                     self.rover.stop().await?;
                     Self::print_direction(out, '_')?;
                 }
+                Some(Ok(Key::Char('g'))) => {
+                    let config = BehaviorConfig::Wander(WanderConfig {
+                        speed,
+                        ..Default::default()
+                    });
+
+                    self.rover.start_behavior(config).await?;
+                    behavior = Some("wander");
+                    Self::print_direction(out, '*')?;
+                }
+                Some(Ok(Key::Char('f'))) => {
+                    let config = BehaviorConfig::Line(LineFollowConfig {
+                        speed,
+                        ..Default::default()
+                    });
+
+                    self.rover.start_behavior(config).await?;
+                    behavior = Some("line follow");
+                    Self::print_direction(out, '*')?;
+                }
                 Some(Ok(Key::Char('w'))) => {
-                    tilt = tilt.saturating_add(1);
+                    tilt = tilt.saturating_add(1).min(limits.v.1);
                     self.rover.look_at(pan, tilt).await?;
                 }
                 Some(Ok(Key::Char('s'))) => {
-                    tilt = tilt.saturating_sub(1);
+                    tilt = tilt.saturating_sub(1).max(limits.v.0);
                     self.rover.look_at(pan, tilt).await?;
                 }
                 Some(Ok(Key::Char('a'))) => {
-                    pan = pan.saturating_add(1);
+                    pan = pan.saturating_add(1).min(limits.h.1);
                     self.rover.look_at(pan, tilt).await?;
                 }
                 Some(Ok(Key::Char('d'))) => {
-                    pan = pan.saturating_sub(1);
+                    pan = pan.saturating_sub(1).max(limits.h.0);
                     self.rover.look_at(pan, tilt).await?;
                 }
                 _ => {
-                    let obstacles = self.rover.get_obstacles().await?;
-                    let lines = self.rover.get_lines().await?;
+                    self.rover.heartbeat().await?;
+
+                    behavior = self.rover.get_behavior().await?.map(|config| match config {
+                        BehaviorConfig::Wander(_) => "wander",
+                        BehaviorConfig::Line(_) => "line follow",
+                    });
+
+                    let snapshot = self.rover.snapshot().await?;
                     Self::print_sensors(
                         out,
-                        obstacles[0],
-                        obstacles[1],
-                        lines[0],
-                        lines[1],
-                        self.rover.scan_distance().await?,
+                        &snapshot.obstacles,
+                        &snapshot.lines,
+                        &snapshot.distance,
                     )?;
                     thread::sleep(Duration::from_millis(100));
                 }
             }
 
-            Self::print_run_params(out, speed, pan, tilt)?;
+            Self::print_run_params(out, speed, pan, tilt, behavior)?;
 
             out.flush()?;
         }
@@ -200,7 +259,7 @@ where
 
 impl<T> Drop for RideController<T>
 where
-    T: AsyncMover + AsyncLooker + AsyncSensor,
+    T: AsyncMover + AsyncLooker + AsyncSensor + BehaviorHost + Send,
 {
     fn drop(&mut self) {
         write!(self.output, "{}", termion::cursor::Show).unwrap();
@@ -1,3 +1,4 @@
+pub mod calibrator;
 pub mod controller;
 
 pub type Result<T> = std::result::Result<T, anyhow::Error>;
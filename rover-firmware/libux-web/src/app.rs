@@ -1,18 +1,28 @@
 use std::rc::Rc;
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
+use gloo_timers::callback::Interval;
 use log::{debug, error, trace, warn};
 use stylist::yew::use_style;
 use web_time::SystemTime;
 use yew::prelude::*;
 
-use libapi_http::api::{MoveType, ValueResponse};
+use libapi_http::api::{
+    BehaviorType, DetectionReading, DistanceReading, MoveType, RoverState, SensorPosition,
+    ValueResponse, WsEvent,
+};
 
 use crate::components::direction_control::{
     DirectionControl, DirectionControlMode, DirectionModuleMode,
 };
+use crate::components::scene::Scene;
 use crate::components::sensors_data::SensorsData;
 use crate::services::rover_service::{RoverService, Status};
+use crate::services::rover_service_ws::RoverWsService;
+use crate::services::HEARTBEAT_INTERVAL_MS;
+
+// how often the rover pushes its state over web-socket
+const STATE_UPDATE_PERIOD_MS: u32 = 100;
 
 #[derive(Debug)]
 pub enum AppAction {
@@ -20,12 +30,12 @@ pub enum AppAction {
     SensorDirectionUpdateError(Error, (i32, i32)),
     MoveDirectionUpdate((i32, i32)),
     MoveDirectionUpdateError(Error, (i32, i32)),
-    DistanceUpdate(f32),
-    DistanceUpdateError(Error),
-    ObstaclesUpdate(Vec<bool>),
-    ObstaclesUpdateError(Error),
-    LinesUpdate(Vec<bool>),
-    LinesUpdateError(Error),
+    DriveModeUpdate(Option<BehaviorType>),
+    DriveModeUpdateError(Error),
+    StateUpdate(RoverState),
+    StateUpdateError(Error),
+    ChannelUpdate(bool),
+    ChannelError(Error),
 }
 
 #[derive(Debug)]
@@ -34,20 +44,25 @@ pub struct AppState {
     pub sensor_direction_error: Rc<Option<Error>>,
     pub move_direction: (i32, i32),
     pub move_direction_error: Rc<Option<Error>>,
-    pub distance: f32,
-    pub distance_error: Rc<Option<Error>>,
-    pub distance_timestamp: SystemTime,
-    pub lines: Rc<Vec<bool>>,
-    pub lines_error: Rc<Option<Error>>,
-    pub lines_timestamp: SystemTime,
-    pub obstacles: Rc<Vec<bool>>,
-    pub obstacles_error: Rc<Option<Error>>,
-    pub obstacles_timestamp: SystemTime,
+    /// Behavior driving the rover, manual control if none.
+    pub drive_mode: Option<BehaviorType>,
+    pub drive_mode_error: Rc<Option<Error>>,
+    pub distance: DistanceReading,
+    pub lines: Rc<DetectionReading>,
+    pub obstacles: Rc<DetectionReading>,
+    pub state_error: Rc<Option<Error>>,
+    pub state_timestamp: SystemTime,
+    /// Whether the rover is controlled over web-socket, HTTP requests are used otherwise.
+    pub channel_connected: bool,
+    /// Failure of the last command sent over web-socket.
+    pub channel_error: Rc<Option<Error>>,
 }
 
 impl AppState {
     fn select_move_type(&self) -> Option<MoveType> {
-        if self.move_direction.1 > 0 {
+        if self.move_direction.0 != 0 && self.move_direction.1 != 0 {
+            Some(MoveType::Drive)
+        } else if self.move_direction.1 > 0 {
             Some(MoveType::Forward)
         } else if self.move_direction.1 < 0 {
             Some(MoveType::Backward)
@@ -72,12 +87,24 @@ impl AppState {
         ((unscaled_speed.abs() as f64 / i32::MAX as f64) * (u8::MAX as f64)).floor() as u8
     }
 
+    /// Left and right side speeds that blend forward/backward motion with turning.
+    fn select_drive(&self) -> (i16, i16) {
+        let scale = |v: i32| v as f64 / i32::MAX as f64 * u8::MAX as f64;
+        let limit = |v: f64| v.round().clamp(-(u8::MAX as f64), u8::MAX as f64) as i16;
+
+        let turn = scale(self.move_direction.0);
+        let forward = scale(self.move_direction.1);
+
+        (limit(forward + turn), limit(forward - turn))
+    }
+
     fn move_type_repr(&self) -> char {
         match self.select_move_type() {
             Some(MoveType::Forward) => '↑',
             Some(MoveType::Backward) => '↓',
             Some(MoveType::CWSpin) => '↻',
             Some(MoveType::CCWSpin) => '↺',
+            Some(MoveType::Drive) => '↝',
             None => '■',
         }
     }
@@ -90,15 +117,15 @@ impl Default for AppState {
             sensor_direction_error: Default::default(),
             move_direction: Default::default(),
             move_direction_error: Default::default(),
+            drive_mode: Default::default(),
+            drive_mode_error: Default::default(),
             distance: Default::default(),
-            distance_error: Default::default(),
-            distance_timestamp: SystemTime::UNIX_EPOCH,
             lines: Default::default(),
-            lines_error: Default::default(),
-            lines_timestamp: SystemTime::UNIX_EPOCH,
             obstacles: Default::default(),
-            obstacles_error: Default::default(),
-            obstacles_timestamp: SystemTime::UNIX_EPOCH,
+            state_error: Default::default(),
+            state_timestamp: SystemTime::UNIX_EPOCH,
+            channel_connected: Default::default(),
+            channel_error: Default::default(),
         }
     }
 }
@@ -113,20 +140,21 @@ impl Reducible for AppState {
         let mut sensor_direction_error = self.sensor_direction_error.clone();
         let mut move_direction = self.move_direction.clone();
         let mut move_direction_error = self.move_direction_error.clone();
-        let mut distance = self.distance.clone();
-        let mut distance_error = self.distance_error.clone();
-        let mut distance_timestamp = self.distance_timestamp;
+        let mut drive_mode = self.drive_mode;
+        let mut drive_mode_error = self.drive_mode_error.clone();
+        let mut distance = self.distance;
         let mut lines = self.lines.clone();
-        let mut lines_error = self.lines_error.clone();
-        let mut lines_timestamp = self.lines_timestamp;
         let mut obstacles = self.obstacles.clone();
-        let mut obstacles_error = self.obstacles_error.clone();
-        let mut obstacles_timestamp = self.obstacles_timestamp;
+        let mut state_error = self.state_error.clone();
+        let mut state_timestamp = self.state_timestamp;
+        let mut channel_connected = self.channel_connected;
+        let mut channel_error = self.channel_error.clone();
 
         match action {
             AppAction::SensorDirectionUpdate(dir) => {
                 sensor_direction = dir;
                 sensor_direction_error = None.into();
+                channel_error = None.into();
             }
             AppAction::SensorDirectionUpdateError(e, dir) => {
                 sensor_direction = dir;
@@ -135,37 +163,39 @@ impl Reducible for AppState {
             AppAction::MoveDirectionUpdate(dir) => {
                 move_direction = dir;
                 move_direction_error = None.into();
+                channel_error = None.into();
+                // manual move takes the rover over from a behavior
+                drive_mode = None;
             }
             AppAction::MoveDirectionUpdateError(e, dir) => {
                 move_direction = dir;
                 move_direction_error = Some(e).into();
             }
-            AppAction::ObstaclesUpdate(v) => {
-                obstacles = v.into();
-                obstacles_error = None.into();
-                obstacles_timestamp = SystemTime::now();
+            AppAction::DriveModeUpdate(mode) => {
+                drive_mode = mode;
+                drive_mode_error = None.into();
             }
-            AppAction::ObstaclesUpdateError(e) => {
-                obstacles_error = Some(e).into();
-                obstacles_timestamp = SystemTime::now();
+            AppAction::DriveModeUpdateError(e) => {
+                drive_mode = None;
+                drive_mode_error = Some(e).into();
             }
-            AppAction::DistanceUpdate(d) => {
-                distance = d;
-                distance_error = None.into();
-                distance_timestamp = SystemTime::now();
+            AppAction::StateUpdate(v) => {
+                distance = v.distance;
+                lines = v.lines.into();
+                obstacles = v.obstacles.into();
+                state_error = None.into();
+                state_timestamp = SystemTime::now();
             }
-            AppAction::DistanceUpdateError(e) => {
-                distance_error = Some(e).into();
-                distance_timestamp = SystemTime::now();
+            AppAction::StateUpdateError(e) => {
+                state_error = Some(e).into();
+                state_timestamp = SystemTime::now();
             }
-            AppAction::LinesUpdate(v) => {
-                lines = v.into();
-                lines_error = None.into();
-                lines_timestamp = SystemTime::now();
+            AppAction::ChannelUpdate(connected) => {
+                channel_connected = connected;
+                channel_error = None.into();
             }
-            AppAction::LinesUpdateError(e) => {
-                lines_error = Some(e).into();
-                lines_timestamp = SystemTime::now();
+            AppAction::ChannelError(e) => {
+                channel_error = Some(e).into();
             }
         };
 
@@ -174,15 +204,15 @@ impl Reducible for AppState {
             sensor_direction_error,
             move_direction,
             move_direction_error,
+            drive_mode,
+            drive_mode_error,
             distance,
-            distance_error,
-            distance_timestamp,
             lines,
-            lines_error,
-            lines_timestamp,
             obstacles,
-            obstacles_error,
-            obstacles_timestamp,
+            state_error,
+            state_timestamp,
+            channel_connected,
+            channel_error,
         };
 
         debug!("Updated state: {:#?}", new_state);
@@ -235,29 +265,73 @@ pub fn app() -> Html {
                 margin: 10px auto;
                 text-align: center;
             }
+
+            .modes>button.selected {
+                font-weight: bold;
+            }
         "
     );
 
     // define state
     let rover_service = use_mut_ref(|| RoverService::new("http://rover/api"));
+    let rover_ws_service = use_mut_ref(|| None::<RoverWsService>);
     let state = use_reducer(AppState::default);
 
     // define side effects
-    // {
-    //     // establish web-socket connection
-    //     let rover_service = rover_service.clone();
-    //     use_effect(move {
-    //         // rover_service.connect();
-    //         // || rover_service.disconnect();
-    //     });
-    // }
+    {
+        // web-socket channel, HTTP requests stand in for it while it is down
+        let rover_ws_service = rover_ws_service.clone();
+        let state = state.clone();
+
+        use_effect_with((), move |_| {
+            let onevent = {
+                let state = state.clone();
+
+                Callback::from(move |event| match event {
+                    WsEvent::State(v) => state.dispatch(AppAction::StateUpdate(v)),
+                    WsEvent::Error { message } => {
+                        warn!("[App] Rover command failed: {}", message);
+                        state.dispatch(AppAction::ChannelError(anyhow!(message)));
+                    }
+                })
+            };
+            let onconnection = Callback::from(move |connected| {
+                debug!("[App] Rover web-socket connected: {}", connected);
+                state.dispatch(AppAction::ChannelUpdate(connected));
+            });
+
+            *rover_ws_service.borrow_mut() = Some(RoverWsService::connect(
+                "ws://rover/api/ws",
+                STATE_UPDATE_PERIOD_MS,
+                onevent,
+                onconnection,
+            ));
+
+            move || drop(rover_ws_service.borrow_mut().take())
+        });
+    }
     {
         // sensor direction
         let rover_service = rover_service.clone();
+        let rover_ws_service = rover_ws_service.clone();
         let state = state.clone();
         let sensor_direction = state.sensor_direction;
+        let channel_connected = state.channel_connected;
 
         use_effect_with(sensor_direction, move |_| {
+            if let Some(ref ws) = *rover_ws_service.borrow() {
+                if channel_connected {
+                    trace!("[App] Sending sensor direction update.");
+
+                    let (h, v) = (-sensor_direction.0 as i16, -sensor_direction.1 as i16);
+                    if let Err(e) = ws.look_at(h, v) {
+                        state.dispatch(AppAction::SensorDirectionUpdateError(e, sensor_direction));
+                    }
+
+                    return;
+                }
+            }
+
             trace!("[App] Scheduling sensor direction update.");
 
             match rover_service.borrow().look_at(
@@ -279,26 +353,38 @@ pub fn app() -> Html {
         })
     }
     {
-        // move direction
+        // move direction, sent again over the channel that takes over
         let rover_service = rover_service.clone();
+        let rover_ws_service = rover_ws_service.clone();
         let state = state.clone();
         let move_direction = state.move_direction;
+        let channel_connected = state.channel_connected;
 
-        use_effect_with(move_direction, move |_| {
-            trace!("[App] Scheduling move direction update.");
+        use_effect_with((move_direction, channel_connected), move |_| {
+            // manual moves reset drive mode, so it is set only while a behavior drives
+            if state.drive_mode.is_some() {
+                return;
+            }
+
+            let (left, right) = state.select_drive();
+
+            if let Some(ref ws) = *rover_ws_service.borrow() {
+                if channel_connected {
+                    trace!("[App] Sending move direction update.");
 
-            let mut speed = state.select_speed();
-            let move_type = match state.select_move_type() {
-                Some(m_t) => m_t,
-                None => {
-                    speed = 0;
-                    MoveType::Forward
+                    if let Err(e) = ws.drive(left, right) {
+                        state.dispatch(AppAction::MoveDirectionUpdateError(e, move_direction));
+                    }
+
+                    return;
                 }
-            };
+            }
+
+            trace!("[App] Scheduling move direction update.");
 
-            match rover_service.borrow().r#move(
-                move_type,
-                speed,
+            match rover_service.borrow().drive(
+                left,
+                right,
                 Callback::from(move |status| match status {
                     Err(e) => {
                         warn!("[App] Rover move direction update failed: {:?}", e);
@@ -315,80 +401,58 @@ pub fn app() -> Html {
         })
     }
     {
-        // distance sensor
+        // keep the rover moving, otherwise its watchdog stops it; web-socket channel does it itself
         let rover_service = rover_service.clone();
-        let state = state.clone();
-        let distance_timestamp = state.distance_timestamp;
-
-        use_effect_with(distance_timestamp, move |_| {
-            trace!("[App] Scheduling distance sensor query.");
-
-            match rover_service.borrow().get_distance(Callback::from(
-                move |status: Status<ValueResponse<f32>>| match status {
-                    Err(e) => {
-                        warn!("[App] Rover distance sensor query failed: {:?}", e);
-                        state.dispatch(AppAction::DistanceUpdateError(e));
-                    }
-                    Ok(result) => {
-                        trace!("[App] Rover distance sensor query succeeded.");
-                        state.dispatch(AppAction::DistanceUpdate(result.value));
+        let move_direction = state.move_direction;
+        let channel_connected = state.channel_connected;
+
+        use_effect_with((move_direction, channel_connected), move |_| {
+            let heartbeat = (move_direction != (0, 0) && !channel_connected).then(|| {
+                Interval::new(HEARTBEAT_INTERVAL_MS, move || {
+                    if let Err(e) = rover_service.borrow().heartbeat(Callback::from(
+                        |status: Status| {
+                            if let Err(e) = status {
+                                warn!("[App] Rover heartbeat failed: {:?}", e);
+                            }
+                        },
+                    )) {
+                        error!("[App] Rover heartbeat scheduling failed: {:?}", e);
                     }
-                },
-            )) {
-                Ok(_) => trace!("[App] Rover distance sensor query scheduled."),
-                Err(e) => error!("[App] Distance sensor query scheduling failed: {:?}", e),
-            };
-        });
-    }
-    {
-        // lines sensor
-        let rover_service = rover_service.clone();
-        let state = state.clone();
-        let lines_timestamp = state.lines_timestamp;
+                })
+            });
 
-        use_effect_with(lines_timestamp, move |_| {
-            trace!("[App] Scheduling line sensors query.");
-
-            match rover_service.borrow().get_lines(Callback::from(
-                move |status: Status<ValueResponse<Vec<bool>>>| match status {
-                    Err(e) => {
-                        warn!("[App] Rover line sensors query failed: {:?}", e);
-                        state.dispatch(AppAction::LinesUpdateError(e));
-                    }
-                    Ok(result) => {
-                        trace!("[App] Rover line sensors query succeeded.");
-                        state.dispatch(AppAction::LinesUpdate(result.value));
-                    }
-                },
-            )) {
-                Ok(_) => trace!("[App] Rover line sensors query scheduled."),
-                Err(e) => error!("[App] Line sensors query scheduling failed: {:?}", e),
-            };
+            move || drop(heartbeat)
         });
     }
     {
-        // obstacles sensor
+        // all sensors at once, so that the readings are consistent; polled only while web-socket
+        // channel is down, it pushes the state otherwise
         let rover_service = rover_service.clone();
         let state = state.clone();
-        let obstacles_timestamp = state.obstacles_timestamp;
+        let state_timestamp = state.state_timestamp;
+        let channel_connected = state.channel_connected;
 
-        use_effect_with(obstacles_timestamp, move |_| {
-            trace!("[App] Scheduling obstacle sensors query.");
+        use_effect_with((state_timestamp, channel_connected), move |_| {
+            if channel_connected {
+                return;
+            }
 
-            match rover_service.borrow().get_obstacles(Callback::from(
-                move |status: Status<ValueResponse<Vec<bool>>>| match status {
+            trace!("[App] Scheduling rover state query.");
+
+            match rover_service.borrow().get_state(Callback::from(
+                move |status: Status<ValueResponse<RoverState>>| match status {
                     Err(e) => {
-                        warn!("[App] Rover obstacle sensors query failed: {:?}", e);
-                        state.dispatch(AppAction::ObstaclesUpdateError(e));
+                        warn!("[App] Rover state query failed: {:?}", e);
+                        state.dispatch(AppAction::StateUpdateError(e));
                     }
                     Ok(result) => {
-                        trace!("[App] Rover obstacle sensors query succeeded.");
-                        state.dispatch(AppAction::ObstaclesUpdate(result.value));
+                        trace!("[App] Rover state query succeeded.");
+                        state.dispatch(AppAction::StateUpdate(result.value));
                     }
                 },
             )) {
-                Ok(_) => trace!("[App] Rover obstacle sensors query scheduled."),
-                Err(e) => error!("[App] Obstacle sensors query scheduling failed: {:?}", e),
+                Ok(_) => trace!("[App] Rover state query scheduled."),
+                Err(e) => error!("[App] Rover state query scheduling failed: {:?}", e),
             };
         });
     }
@@ -406,15 +470,51 @@ pub fn app() -> Html {
         move |dir| state.dispatch(AppAction::MoveDirectionUpdate(dir))
     };
 
+    let on_drive_mode_change = {
+        let rover_service = rover_service.clone();
+        let state = state.clone();
+
+        move |mode: Option<BehaviorType>| {
+            let rover_service = rover_service.clone();
+            let state = state.clone();
+
+            Callback::from(move |_| {
+                trace!("[App] Scheduling drive mode update.");
+
+                let oncomplete = {
+                    let state = state.clone();
+
+                    Callback::from(move |status| match status {
+                        Err(e) => {
+                            warn!("[App] Rover drive mode update failed: {:?}", e);
+                            state.dispatch(AppAction::DriveModeUpdateError(e));
+                        }
+                        _ => {
+                            trace!("[App] Rover drive mode update succeeded.");
+                            state.dispatch(AppAction::DriveModeUpdate(mode));
+                        }
+                    })
+                };
+
+                let result = match mode {
+                    Some(mode) => rover_service.borrow().start_behavior(mode, None, oncomplete),
+                    None => rover_service.borrow().stop_behavior(oncomplete),
+                };
+
+                match result {
+                    Ok(_) => trace!("[App] Drive mode update scheduled."),
+                    Err(e) => error!("[App] Drive mode update scheduling failed: {:?}", e),
+                };
+            })
+        }
+    };
+
+    let drive_mode_class =
+        |mode: Option<BehaviorType>| (state.drive_mode == mode).then_some("selected");
+
     let mut extra_messages: Vec<String> = vec![];
-    if let Some(ref distance_err) = *state.distance_error {
-        extra_messages.push(format!("Distance/{}", distance_err));
-    }
-    if let Some(ref lines_err) = *state.lines_error {
-        extra_messages.push(format!("Lines/{}", lines_err));
-    }
-    if let Some(ref obstactles_err) = *state.obstacles_error {
-        extra_messages.push(format!("Obstacles/{}", obstactles_err));
+    if let Some(ref state_err) = *state.state_error {
+        extra_messages.push(format!("Sensors/{}", state_err));
     }
     if let Some(ref look_err) = *state.sensor_direction_error {
         extra_messages.push(format!("Look/{}", look_err))
@@ -422,16 +522,23 @@ pub fn app() -> Html {
     if let Some(ref move_err) = *state.move_direction_error {
         extra_messages.push(format!("Move/{}", move_err))
     }
+    if let Some(ref mode_err) = *state.drive_mode_error {
+        extra_messages.push(format!("Mode/{}", mode_err))
+    }
+    if let Some(ref channel_err) = *state.channel_error {
+        extra_messages.push(format!("Channel/{}", channel_err))
+    }
 
     html! {
         <div class={style}>
             <SensorsData
-                left_obstacle={state.obstacles.get(0).unwrap_or(&false)}
-                right_obstacle={state.obstacles.get(1).unwrap_or(&false)}
-                left_line={state.lines.get(0).unwrap_or(&false)}
-                right_line={state.lines.get(1).unwrap_or(&false)}
-                distance={state.distance}
+                left_obstacle={state.obstacles.at(SensorPosition::Left).unwrap_or(false)}
+                right_obstacle={state.obstacles.at(SensorPosition::Right).unwrap_or(false)}
+                left_line={state.lines.at(SensorPosition::Left).unwrap_or(false)}
+                right_line={state.lines.at(SensorPosition::Right).unwrap_or(false)}
+                distance={state.distance.valid.then_some(state.distance.distance_mm)}
                 messages={extra_messages} />
+            <Scene />
             <div class="controls">
                 <div>
                     <h5>{"Sensor Direction"}</h5>
@@ -459,6 +566,11 @@ pub fn app() -> Html {
                         xinc_title="↻"
                         xdec_title="↺"
                         has_reset={true} />
+                    <div class="modes">
+                        <button class={drive_mode_class(None)} onclick={on_drive_mode_change(None)}>{"Manual"}</button>
+                        <button class={drive_mode_class(Some(BehaviorType::Wander))} onclick={on_drive_mode_change(Some(BehaviorType::Wander))}>{"Wander"}</button>
+                        <button class={drive_mode_class(Some(BehaviorType::LineFollow))} onclick={on_drive_mode_change(Some(BehaviorType::LineFollow))}>{"Line follow"}</button>
+                    </div>
                 </div>
             </div>
         </div>
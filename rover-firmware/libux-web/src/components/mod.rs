@@ -1,3 +1,3 @@
 pub(crate) mod direction_control;
-// pub(crate) mod scene;
+pub(crate) mod scene;
 pub(crate) mod sensors_data;
\ No newline at end of file
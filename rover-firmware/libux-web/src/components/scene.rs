@@ -1,49 +1,131 @@
-use log::debug;
-use css_in_rust::Style;
-use yew::{html, Component, Properties, ComponentLink, Html, ShouldRender};
+use std::cell::RefCell;
+use std::rc::Rc;
 
-pub struct Scene {
-    link: ComponentLink<Self>,
-    style: Style,
+use js_sys::{Array, Date, Uint8Array};
+use log::warn;
+use stylist::yew::use_style;
+use web_sys::{Blob, BlobPropertyBag, Url};
+use yew::{function_component, html, use_effect_with, use_state, Callback, Html};
+
+use crate::services::video_service::{VideoFrame, VideoService};
+
+/// Picture shown along with frame capture latency, as seen by browser's clock.
+#[derive(PartialEq, Clone)]
+struct View {
+    url: String,
+    latency_ms: i64,
 }
 
-impl Component for Scene {
-    type Message = ();
-    type Properties = ();
-
-    fn create(_props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let style = Style::create(
-            "Scene",
-            r#"
-                width: 100%;
-                height: 100%;
-
-                display: flex;
-                flex-direction: column;
-                justify-content: center;
-                align-items: center;
-            "#)
-            .unwrap();
-
-        Scene {
-            link,
-            style,
-        }
-    }
+/// Wraps JPEG image into object URL `<img>` can show.
+fn to_object_url(jpeg: &[u8]) -> Option<String> {
+    let parts = Array::of1(&Uint8Array::from(jpeg));
+    let blob = Blob::new_with_u8_array_sequence_and_options(
+        &parts,
+        BlobPropertyBag::new().type_("image/jpeg"),
+    )
+    .ok()?;
 
-    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
-        false
-    }
+    Url::create_object_url_with_blob(&blob).ok()
+}
+
+/// Live video from rover camera.
+#[function_component(Scene)]
+pub fn scene() -> Html {
+    let style = use_style!(
+        r"
+            width: 100%;
+            flex-grow: 1;
+            min-height: 0;
+
+            display: flex;
+            flex-direction: column;
+            justify-content: center;
+            align-items: center;
+
+            img {
+                max-width: 100%;
+                min-height: 0;
+                object-fit: contain;
+            }
+
+            .no-video {
+                padding: 40px;
+                color: gray;
+            }
+
+            .latency {
+                font-size: small;
+            }
+        "
+    );
 
-    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
-        false
+    let view = use_state(|| None::<View>);
+
+    {
+        let view = view.setter();
+
+        use_effect_with((), move |_| {
+            // URLs of two latest pictures are kept, so that the one shown stays valid until the
+            // next one replaces it
+            let urls: Rc<RefCell<Vec<String>>> = Rc::default();
+
+            let onframe = {
+                let view = view.clone();
+                let urls = urls.clone();
+
+                Callback::from(move |frame: VideoFrame| {
+                    let url = match to_object_url(&frame.jpeg) {
+                        Some(url) => url,
+                        None => {
+                            warn!("[Scene] Failed to make URL of video frame.");
+                            return;
+                        }
+                    };
+
+                    let mut urls = urls.borrow_mut();
+                    while urls.len() > 1 {
+                        let _ = Url::revoke_object_url(&urls.remove(0));
+                    }
+                    urls.push(url.clone());
+
+                    view.set(Some(View {
+                        url,
+                        latency_ms: Date::now() as i64 - frame.timestamp_ms as i64,
+                    }));
+                })
+            };
+            let onconnection = Callback::from(move |connected| {
+                if !connected {
+                    view.set(None);
+                }
+            });
+
+            let video_service =
+                VideoService::connect("ws://rover/api/camera/ws", onframe, onconnection);
+
+            move || {
+                drop(video_service);
+
+                for url in urls.borrow_mut().drain(..) {
+                    let _ = Url::revoke_object_url(&url);
+                }
+            }
+        });
     }
 
-    fn view(&self) -> Html {
-        html! {
-            <div class=self.style.clone()>
-                {"LOL"}
-            </div>
-        }
+    html! {
+        <div class={style}>
+            {
+                match *view {
+                    Some(ref view) => html! {
+                        <>
+                            <img src={view.url.clone()} alt="Rover camera" />
+                            <div class="latency">{format!("Latency {} ms", view.latency_ms)}</div>
+                        </>
+                    },
+                    None => html! { <div class="no-video">{"No video"}</div> },
+                }
+            }
+        </div>
     }
-}
\ No newline at end of file
+}
@@ -15,8 +15,9 @@ pub struct SensorsDataProps {
     #[prop_or_default]
     pub right_line: bool,
 
+    /// Distance to obstacle in mm, absent if there is none within sonar range.
     #[prop_or_default]
-    pub distance: f32,
+    pub distance: Option<f32>,
 
     #[prop_or(vec![])]
     pub messages: Vec<String>,
@@ -71,7 +72,12 @@ pub fn sensors_data(props: &SensorsDataProps) -> Html {
             <div class="obstacle">{format!("{}", if props.left_obstacle { "OBSTACLE >>>" } else { "|" })}</div>
             <div class="line line-left">{format!("{}", if props.left_line { "|LINE|" } else { "<\u{00a0}\u{00a0}\u{00a0}\u{00a0}>" })}</div>
             <div class="main">
-                <div class="distance">{format!("{} mm", props.distance)}</div>
+                <div class="distance">{
+                    match props.distance {
+                        Some(distance) => format!("{} mm", distance),
+                        None => "-- mm".to_string(),
+                    }
+                }</div>
                 <div class="error">
                     {
                         for props.messages.iter().map(|m| { html! { <div>{m}</div> } })
@@ -27,19 +27,72 @@ export function initMirage() {
             this.post("move", (schema, request) => {
                 return new Response(204);
             });
+            this.post("move/heartbeat", (schema, request) => {
+                return new Response(204);
+            });
             this.post("look", (schema, request) => {
                 return new Response(204);
             });
+
+            let behavior = null;
+
+            this.post("behavior", (schema, request) => {
+                behavior = JSON.parse(request.requestBody);
+                return new Response(204);
+            });
+            this.post("behavior/stop", (schema, request) => {
+                behavior = null;
+                return new Response(204);
+            });
+            this.get("behavior", (schema, request) => {
+                return { value: behavior };
+            });
+            const detections = () => ({
+                timestamp: Math.floor(performance.now()),
+                detections: [
+                    { position: "Left", detected: Math.random() > 0.5 },
+                    { position: "Right", detected: Math.random() < 0.5 },
+                ],
+            });
+
+            this.get("sense/sensors", (schema, request) => {
+                return {
+                    value: [
+                        { kind: "Obstacle", position: "Left" },
+                        { kind: "Obstacle", position: "Right" },
+                        { kind: "Line", position: "Left" },
+                        { kind: "Line", position: "Right" },
+                        { kind: "Distance", position: "Center" },
+                    ]
+                };
+            });
             this.get("sense/obstacles", (schema, request) => {
-                return { value: [Math.random() > 0.5, Math.random() < 0.5] };
+                return { value: detections() };
             }, { timing: Math.random() * 3000 });
             this.get("sense/lines", (schema, request) => {
-                return { value: [Math.random() > 0.5, Math.random() < 0.5] };
+                return { value: detections() };
             }, { timing: Math.random() * 3000 });
-            this.get("sense/distance", (schema, request) => {
-                distance += (Math.random() > 0.5 ? 1 : -1) * Math.floor(Math.random() * 100);
+            const distanceReading = () => {
+                distance = Math.max(0, distance + (Math.random() > 0.5 ? 1 : -1) * Math.floor(Math.random() * 100));
 
-                return { value: distance };
+                return {
+                    timestamp: Math.floor(performance.now()),
+                    distance_mm: distance,
+                    valid: distance <= 4000,
+                };
+            };
+            this.get("sense/distance", (schema, request) => {
+                return { value: distanceReading() };
+            }, { timing: Math.random() * 3000 });
+            this.get("state", (schema, request) => {
+                return {
+                    value: {
+                        obstacles: detections(),
+                        lines: detections(),
+                        distance: distanceReading(),
+                        look: { h: 0, v: 0 },
+                    }
+                };
             }, { timing: Math.random() * 3000 });
         }
     });
@@ -1,2 +1,10 @@
 pub(crate) mod rover_service;
-mod rover_service_ws;
\ No newline at end of file
+pub(crate) mod rover_service_ws;
+pub(crate) mod video_service;
+
+// how often to confirm to the rover that it should keep moving
+pub(crate) const HEARTBEAT_INTERVAL_MS: u32 = 250;
+
+// web-socket reconnection delay doubles after each failed attempt, within these bounds
+pub(crate) const MIN_RECONNECT_DELAY_MS: u32 = 500;
+pub(crate) const MAX_RECONNECT_DELAY_MS: u32 = 8000;
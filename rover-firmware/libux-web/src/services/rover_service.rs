@@ -13,7 +13,10 @@ use web_sys::AbortController;
 use yew::platform::spawn_local;
 use yew::Callback;
 
-use libapi_http::api::{LookRequest, MoveRequest, MoveType, SenseType, ValueResponse};
+use libapi_http::api::{
+    BehaviorRequest, BehaviorType, DetectionReading, DistanceReading, ErrorResponse, LookRequest,
+    MoveRequest, MoveType, RoverState, SenseType, ValueResponse,
+};
 use libutil::helpers::calc_hash;
 
 pub struct RoverService {
@@ -49,11 +52,34 @@ impl RoverService {
         oncomplete: Callback<Status>,
     ) -> PendingStatus {
         let api_endpoint = format!("{}/move", self.rover_api_endpoint);
-        let data = MoveRequest { r#type, speed };
+        let data = MoveRequest {
+            r#type,
+            speed,
+            left: None,
+            right: None,
+        };
+
+        self.schedule_request(&api_endpoint, Method::POST, &data, oncomplete)
+    }
+
+    pub fn drive(&self, left: i16, right: i16, oncomplete: Callback<Status>) -> PendingStatus {
+        let api_endpoint = format!("{}/move", self.rover_api_endpoint);
+        let data = MoveRequest {
+            r#type: MoveType::Drive,
+            speed: 0,
+            left: Some(left),
+            right: Some(right),
+        };
 
         self.schedule_request(&api_endpoint, Method::POST, &data, oncomplete)
     }
 
+    pub fn heartbeat(&self, oncomplete: Callback<Status>) -> PendingStatus {
+        let api_endpoint = format!("{}/move/heartbeat", self.rover_api_endpoint);
+
+        self.schedule_request(&api_endpoint, Method::POST, &(), oncomplete)
+    }
+
     pub fn look_at(
         &self,
         h: i16,
@@ -66,27 +92,54 @@ impl RoverService {
         self.schedule_request(&api_endpoint, Method::POST, &data, oncomplete)
     }
 
+    pub fn start_behavior(
+        &self,
+        r#type: BehaviorType,
+        speed: Option<u8>,
+        oncomplete: Callback<Status>,
+    ) -> PendingStatus {
+        let api_endpoint = format!("{}/behavior", self.rover_api_endpoint);
+        let data = BehaviorRequest { r#type, speed };
+
+        self.schedule_request(&api_endpoint, Method::POST, &data, oncomplete)
+    }
+
+    pub fn stop_behavior(&self, oncomplete: Callback<Status>) -> PendingStatus {
+        let api_endpoint = format!("{}/behavior/stop", self.rover_api_endpoint);
+
+        self.schedule_request(&api_endpoint, Method::POST, &(), oncomplete)
+    }
+
     pub fn get_distance(
         &self,
-        oncomplete: Callback<Status<ValueResponse<f32>>>,
+        oncomplete: Callback<Status<ValueResponse<DistanceReading>>>,
     ) -> PendingStatus {
         self.sense(SenseType::Distance, oncomplete)
     }
 
     pub fn get_lines(
         &self,
-        oncomplete: Callback<Status<ValueResponse<Vec<bool>>>>,
+        oncomplete: Callback<Status<ValueResponse<DetectionReading>>>,
     ) -> PendingStatus {
         self.sense(SenseType::Lines, oncomplete)
     }
 
     pub fn get_obstacles(
         &self,
-        oncomplete: Callback<Status<ValueResponse<Vec<bool>>>>,
+        oncomplete: Callback<Status<ValueResponse<DetectionReading>>>,
     ) -> PendingStatus {
         self.sense(SenseType::Obstacles, oncomplete)
     }
 
+    pub fn get_state(
+        &self,
+        oncomplete: Callback<Status<ValueResponse<RoverState>>>,
+    ) -> PendingStatus {
+        let api_endpoint = format!("{}/state", self.rover_api_endpoint);
+
+        self.schedule_request(&api_endpoint, Method::GET, &(), oncomplete)
+    }
+
     fn sense<T>(
         &self,
         r#type: SenseType,
@@ -187,12 +240,19 @@ impl RoverService {
                                 res.status(),
                                 response_body
                             );
-                            oncomplete.emit(Err(anyhow!(
-                                "{{{}}} failed: [{}] {:?}",
-                                api_endpoint,
-                                res.status(),
-                                response_body
-                            )));
+
+                            let error = response_body
+                                .ok()
+                                .and_then(|body| serde_json::from_str::<ErrorResponse>(&body).ok());
+                            oncomplete.emit(Err(match error {
+                                Some(error) => anyhow!(
+                                    "{{{}}} failed: [{}] {}",
+                                    api_endpoint,
+                                    error.code,
+                                    error.message
+                                ),
+                                None => anyhow!("{{{}}} failed: [{}]", api_endpoint, res.status()),
+                            }));
                         }
                     }
                     Err(e) => {
@@ -1,20 +1,230 @@
-use gloo_net::websocket::{Message, futures::WebSocket};
-use wasm_bindgen_futures::spawn_local;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use futures::channel::mpsc;
+use futures::future::select;
 use futures::{SinkExt, StreamExt};
+use gloo_net::websocket::{futures::WebSocket, Message};
+use gloo_timers::callback::Interval;
+use gloo_timers::future::TimeoutFuture;
+use log::{debug, trace, warn};
+use yew::platform::spawn_local;
+use yew::Callback;
+
+use libapi_http::api::{LookRequest, MoveRequest, MoveType, WsCommand, WsEvent};
+
+use crate::services::rover_service::Status;
+use crate::services::{HEARTBEAT_INTERVAL_MS, MAX_RECONNECT_DELAY_MS, MIN_RECONNECT_DELAY_MS};
+
+/// Rover control over web-socket channel of api-http. Channel is reopened whenever it drops,
+/// rover state is pushed over it with requested period and the rover is kept moving with
+/// heartbeats while it is told to move.
+pub struct RoverWsService {
+    link: Rc<Link>,
+    _heartbeat: Interval,
+}
+
+#[derive(Default)]
+struct Link {
+    outbox: RefCell<Option<mpsc::UnboundedSender<WsCommand>>>,
+    /// Set once the server responded over current socket.
+    connected: Cell<bool>,
+    moving: Cell<bool>,
+    closed: Cell<bool>,
+}
+
+impl Link {
+    fn send(&self, command: WsCommand) -> Status {
+        match *self.outbox.borrow() {
+            Some(ref outbox) if self.connected.get() => outbox
+                .unbounded_send(command)
+                .map_err(|e| anyhow!("Web-socket send failed: {}", e)),
+            _ => Err(anyhow!("Web-socket is not connected.")),
+        }
+    }
+}
+
+impl RoverWsService {
+    /// Starts connecting to the channel at given endpoint. Pushed events and connection changes
+    /// are reported to given callbacks.
+    pub fn connect(
+        endpoint: &str,
+        state_period_ms: u32,
+        onevent: Callback<WsEvent>,
+        onconnection: Callback<bool>,
+    ) -> Self {
+        let link = Rc::new(Link::default());
+
+        spawn_local(Self::run(
+            endpoint.to_owned(),
+            state_period_ms,
+            link.clone(),
+            onevent,
+            onconnection,
+        ));
 
-fn test() {
-    let mut ws = WebSocket::open("wss://echo.websocket.org").unwrap();
-    let (mut write, mut read) = ws.split();
+        let heartbeat = {
+            let link = link.clone();
 
-    spawn_local(async move {
-        write.send(Message::Text(String::from("test"))).await.unwrap();
-        write.send(Message::Text(String::from("test 2"))).await.unwrap();
-    });
+            Interval::new(HEARTBEAT_INTERVAL_MS, move || {
+                if link.moving.get() {
+                    if let Err(e) = link.send(WsCommand::Heartbeat) {
+                        warn!("[RoverWs] Rover heartbeat failed: {:?}", e);
+                    }
+                }
+            })
+        };
 
-    spawn_local(async move {
-        while let Some(msg) = read.next().await {
-            // console_log!(format!("1. {:?}", msg))
+        RoverWsService {
+            link,
+            _heartbeat: heartbeat,
         }
-        // console_log!("WebSocket Closed")
-    });
-}
\ No newline at end of file
+    }
+
+    async fn run(
+        endpoint: String,
+        state_period_ms: u32,
+        link: Rc<Link>,
+        onevent: Callback<WsEvent>,
+        onconnection: Callback<bool>,
+    ) {
+        let mut reconnect_delay_ms = MIN_RECONNECT_DELAY_MS;
+
+        while !link.closed.get() {
+            trace!("[RoverWs] Connecting to {}...", endpoint);
+
+            match WebSocket::open(&endpoint) {
+                Ok(socket) => {
+                    let (mut write, mut read) = socket.split();
+                    let (outbox, mut inbox) = mpsc::unbounded::<WsCommand>();
+
+                    // pushed state doubles as a sign the channel is up
+                    let _ = outbox.unbounded_send(WsCommand::Subscribe {
+                        period_ms: state_period_ms,
+                    });
+                    link.outbox.replace(Some(outbox));
+
+                    let sending = async move {
+                        while let Some(command) = inbox.next().await {
+                            let text = match serde_json::to_string(&command) {
+                                Ok(text) => text,
+                                Err(e) => {
+                                    warn!("[RoverWs] Failed to encode {:?}: {}", command, e);
+                                    continue;
+                                }
+                            };
+
+                            if let Err(e) = write.send(Message::Text(text)).await {
+                                debug!("[RoverWs] Failed to send {:?}: {:?}", command, e);
+                                break;
+                            }
+                        }
+                    };
+
+                    let receiving = {
+                        let link = link.clone();
+                        let onevent = onevent.clone();
+                        let onconnection = onconnection.clone();
+
+                        async move {
+                            while let Some(message) = read.next().await {
+                                match message {
+                                    Ok(Message::Text(text)) => {
+                                        match serde_json::from_str::<WsEvent>(&text) {
+                                            Ok(event) => {
+                                                if !link.connected.replace(true) {
+                                                    debug!("[RoverWs] Connected.");
+                                                    onconnection.emit(true);
+                                                }
+
+                                                onevent.emit(event);
+                                            }
+                                            Err(e) => {
+                                                warn!("[RoverWs] Unexpected event {}: {}", text, e)
+                                            }
+                                        }
+                                    }
+                                    Ok(Message::Bytes(_)) => {
+                                        warn!("[RoverWs] Unexpected binary message.")
+                                    }
+                                    Err(e) => {
+                                        debug!("[RoverWs] Connection dropped: {:?}", e);
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                    };
+
+                    select(Box::pin(sending), Box::pin(receiving)).await;
+
+                    link.outbox.take();
+
+                    if link.connected.replace(false) {
+                        debug!("[RoverWs] Disconnected.");
+                        onconnection.emit(false);
+                        reconnect_delay_ms = MIN_RECONNECT_DELAY_MS;
+                    }
+                }
+                Err(e) => warn!("[RoverWs] Failed to open {}: {:?}", endpoint, e),
+            }
+
+            if link.closed.get() {
+                break;
+            }
+
+            TimeoutFuture::new(reconnect_delay_ms).await;
+            reconnect_delay_ms = (reconnect_delay_ms * 2).min(MAX_RECONNECT_DELAY_MS);
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.link.connected.get()
+    }
+
+    pub fn r#move(&self, r#type: MoveType, speed: u8) -> Status {
+        let data = MoveRequest {
+            r#type,
+            speed,
+            left: None,
+            right: None,
+        };
+
+        self.link.moving.set(speed != 0);
+        self.link.send(WsCommand::Move(data))
+    }
+
+    pub fn drive(&self, left: i16, right: i16) -> Status {
+        let data = MoveRequest {
+            r#type: MoveType::Drive,
+            speed: 0,
+            left: Some(left),
+            right: Some(right),
+        };
+
+        self.link.moving.set((left, right) != (0, 0));
+        self.link.send(WsCommand::Move(data))
+    }
+
+    pub fn stop(&self) -> Status {
+        self.link.moving.set(false);
+        self.link.send(WsCommand::Stop)
+    }
+
+    pub fn look_at(&self, h: i16, v: i16) -> Status {
+        self.link.send(WsCommand::Look(LookRequest { h, v }))
+    }
+
+    /// Closes the channel for good.
+    pub fn disconnect(&self) {
+        self.link.closed.set(true);
+        self.link.outbox.take();
+    }
+}
+
+impl Drop for RoverWsService {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
@@ -0,0 +1,118 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use futures::StreamExt;
+use gloo_net::websocket::{futures::WebSocket, Message};
+use gloo_timers::future::TimeoutFuture;
+use log::{debug, trace, warn};
+use yew::platform::spawn_local;
+use yew::Callback;
+
+use libapi_http::api::CameraFrame;
+
+use crate::services::{MAX_RECONNECT_DELAY_MS, MIN_RECONNECT_DELAY_MS};
+
+/// Video frame received from the rover.
+pub struct VideoFrame {
+    /// Rover clock time of capture, in ms since UNIX epoch.
+    pub timestamp_ms: u64,
+    pub jpeg: Vec<u8>,
+}
+
+/// Camera video pushed over web-socket of api-http, reopened whenever it drops.
+pub struct VideoService {
+    closed: Rc<Cell<bool>>,
+}
+
+impl VideoService {
+    /// Starts receiving video from given endpoint. Frames and connection changes are reported
+    /// to given callbacks.
+    pub fn connect(
+        endpoint: &str,
+        onframe: Callback<VideoFrame>,
+        onconnection: Callback<bool>,
+    ) -> Self {
+        let closed = Rc::new(Cell::new(false));
+
+        spawn_local(Self::run(
+            endpoint.to_owned(),
+            closed.clone(),
+            onframe,
+            onconnection,
+        ));
+
+        VideoService { closed }
+    }
+
+    async fn run(
+        endpoint: String,
+        closed: Rc<Cell<bool>>,
+        onframe: Callback<VideoFrame>,
+        onconnection: Callback<bool>,
+    ) {
+        let mut reconnect_delay_ms = MIN_RECONNECT_DELAY_MS;
+
+        while !closed.get() {
+            trace!("[Video] Connecting to {}...", endpoint);
+
+            match WebSocket::open(&endpoint) {
+                Ok(mut socket) => {
+                    let mut connected = false;
+
+                    while let Some(message) = socket.next().await {
+                        if closed.get() {
+                            break;
+                        }
+
+                        match message {
+                            Ok(Message::Bytes(bytes)) => match CameraFrame::from_bytes(&bytes) {
+                                Some(frame) => {
+                                    if !connected {
+                                        debug!("[Video] Connected.");
+                                        connected = true;
+                                        onconnection.emit(true);
+                                        reconnect_delay_ms = MIN_RECONNECT_DELAY_MS;
+                                    }
+
+                                    onframe.emit(VideoFrame {
+                                        timestamp_ms: frame.timestamp_ms,
+                                        jpeg: frame.jpeg.to_vec(),
+                                    });
+                                }
+                                None => {
+                                    warn!("[Video] Frame of {} bytes is too short.", bytes.len())
+                                }
+                            },
+                            Ok(Message::Text(text)) => {
+                                warn!("[Video] Unexpected text message: {}", text)
+                            }
+                            Err(e) => {
+                                debug!("[Video] Connection dropped: {:?}", e);
+                                break;
+                            }
+                        }
+                    }
+
+                    if connected {
+                        debug!("[Video] Disconnected.");
+                        onconnection.emit(false);
+                    }
+                }
+                Err(e) => warn!("[Video] Failed to open {}: {:?}", endpoint, e),
+            }
+
+            if closed.get() {
+                break;
+            }
+
+            TimeoutFuture::new(reconnect_delay_ms).await;
+            reconnect_delay_ms = (reconnect_delay_ms * 2).min(MAX_RECONNECT_DELAY_MS);
+        }
+    }
+}
+
+impl Drop for VideoService {
+    fn drop(&mut self) {
+        self.closed.set(true);
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::Read;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::jpeg::find_image;
+use crate::{Error, Frame, FrameSource, Result};
+
+// biggest frame accepted before the stream is considered garbage
+const MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CommandSourceConfig {
+    /// Shell command writing MJPEG stream (concatenated JPEG images) to its standard output.
+    pub command: String,
+}
+
+impl Default for CommandSourceConfig {
+    fn default() -> Self {
+        CommandSourceConfig {
+            command: "ffmpeg -loglevel error -i http://localhost:5858 -f mjpeg -q:v 5 -".to_owned(),
+        }
+    }
+}
+
+/// Frames read from standard output of an external command, like ffmpeg converting the camera
+/// stream. Command is restarted whenever it exits.
+pub struct CommandSource {
+    config: CommandSourceConfig,
+    process: Option<(Child, ChildStdout)>,
+    buffer: Vec<u8>,
+}
+
+impl CommandSource {
+    pub fn new(config: CommandSourceConfig) -> CommandSource {
+        CommandSource {
+            config,
+            process: None,
+            buffer: vec![],
+        }
+    }
+
+    fn stdout(&mut self) -> Result<&mut ChildStdout> {
+        if self.process.is_none() {
+            info!("[Video] Starting `{}`...", self.config.command);
+
+            let mut child = Command::new("sh")
+                .arg("-c")
+                .arg(&self.config.command)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let stdout = child.stdout.take().expect("Standard output is piped");
+
+            self.buffer.clear();
+            self.process = Some((child, stdout));
+        }
+
+        Ok(&mut self.process.as_mut().unwrap().1)
+    }
+
+    fn kill(&mut self) {
+        if let Some((mut child, _)) = self.process.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    /// Cuts the first complete JPEG image out of the buffer, dropping anything before it.
+    fn take_frame(&mut self) -> Option<Vec<u8>> {
+        let image = find_image(&self.buffer)?;
+
+        let frame = self.buffer[image.clone()].to_vec();
+        self.buffer.drain(..image.end);
+
+        Some(frame)
+    }
+
+    fn read_frame(&mut self) -> Result<Vec<u8>> {
+        let mut chunk = [0u8; 16 * 1024];
+
+        loop {
+            if let Some(frame) = self.take_frame() {
+                return Ok(frame);
+            }
+
+            if self.buffer.len() > MAX_FRAME_SIZE {
+                return Err(Error::Stream(format!(
+                    "No frame in {} bytes of output.",
+                    self.buffer.len()
+                )));
+            }
+
+            let read = self.stdout()?.read(&mut chunk)?;
+            if read == 0 {
+                return Err(Error::Stream("Command exited.".to_owned()));
+            }
+
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+impl FrameSource for CommandSource {
+    fn next_frame(&mut self) -> Result<Frame> {
+        match self.read_frame() {
+            Ok(jpeg) => Ok(Frame::now(jpeg)),
+            Err(e) => {
+                debug!(
+                    "[Video] Stopping `{}` after error: {}",
+                    self.config.command, e
+                );
+                self.kill();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for CommandSource {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::Path;
+
+use bytes::Bytes;
+use serde::Deserialize;
+
+use crate::jpeg::find_image;
+use crate::{Error, Frame, FrameSource, Pace, Result};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct FileSourceConfig {
+    /// JPEG image, MJPEG file (like a recording) or directory of `.jpg` images.
+    pub path: String,
+    pub fps: f32,
+}
+
+impl Default for FileSourceConfig {
+    fn default() -> Self {
+        FileSourceConfig {
+            path: "video.mjpeg".to_owned(),
+            fps: 10.0,
+        }
+    }
+}
+
+/// Frames played in a loop from files, standing in for the camera.
+pub struct FileSource {
+    images: Vec<Bytes>,
+    number: usize,
+    pace: Pace,
+}
+
+impl FileSource {
+    pub fn from_config(config: FileSourceConfig) -> Result<FileSource> {
+        let path = Path::new(&config.path);
+
+        let images = if path.is_dir() {
+            let mut paths: Vec<_> = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<_>>()?;
+            paths.retain(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "jpg" || extension == "jpeg")
+            });
+            paths.sort();
+
+            paths
+                .iter()
+                .map(|path| fs::read(path).map(Bytes::from))
+                .collect::<std::io::Result<_>>()?
+        } else {
+            Self::split(fs::read(path)?)
+        };
+
+        if images.is_empty() {
+            return Err(Error::Stream(format!("No images in {}.", config.path)));
+        }
+
+        Ok(FileSource {
+            images,
+            number: 0,
+            pace: Pace::new(config.fps),
+        })
+    }
+
+    fn split(data: Vec<u8>) -> Vec<Bytes> {
+        let mut data = Bytes::from(data);
+        let mut images = vec![];
+
+        while let Some(image) = find_image(&data) {
+            images.push(data.slice(image.clone()));
+            data = data.split_off(image.end);
+        }
+
+        images
+    }
+}
+
+impl FrameSource for FileSource {
+    fn next_frame(&mut self) -> Result<Frame> {
+        self.pace.wait();
+
+        let image = self.images[self.number % self.images.len()].clone();
+        self.number += 1;
+
+        Ok(Frame::now(image))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::jpeg::encode_grayscale;
+    use crate::MjpegWriter;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}-{}", name, std::process::id()))
+    }
+
+    fn source(path: &Path) -> Result<FileSource> {
+        FileSource::from_config(FileSourceConfig {
+            path: path.to_string_lossy().into_owned(),
+            fps: 1000.0,
+        })
+    }
+
+    /// Images of uniform brightness, telling frames apart.
+    fn images() -> Vec<Bytes> {
+        [0, 128, 255]
+            .iter()
+            .map(|&level| Bytes::from(encode_grayscale(&[level; 16 * 16], 16, 16, 80)))
+            .collect()
+    }
+
+    #[test]
+    fn plays_back_recording() {
+        let path = temp_path("recording.mjpeg");
+        let images = images();
+
+        let mut writer = MjpegWriter::create(&path).unwrap();
+        for (i, image) in images.iter().enumerate() {
+            let frame = Frame {
+                timestamp_ms: 1000 + 100 * i as u64,
+                data: image.clone(),
+            };
+            writer.write(&frame).unwrap();
+        }
+
+        assert_eq!(writer.frames(), 3);
+        assert_eq!(
+            writer.size(),
+            images.iter().map(|image| image.len() as u64).sum::<u64>()
+        );
+        assert_eq!(writer.duration_ms(), 200);
+        writer.finish().unwrap();
+
+        let mut source = source(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // played in a loop
+        for image in images.iter().chain(images.iter()) {
+            assert_eq!(&source.next_frame().unwrap().data, image);
+        }
+    }
+
+    #[test]
+    fn plays_back_directory_in_name_order() {
+        let path = temp_path("frames");
+        let images = images();
+
+        fs::create_dir_all(&path).unwrap();
+        for (i, image) in images.iter().enumerate().rev() {
+            fs::write(path.join(format!("{}.jpg", i)), image).unwrap();
+        }
+        fs::write(path.join("notes.txt"), "not an image").unwrap();
+
+        let result = source(&path);
+        fs::remove_dir_all(&path).unwrap();
+
+        let mut source = result.unwrap();
+        for image in &images {
+            assert_eq!(&source.next_frame().unwrap().data, image);
+        }
+    }
+
+    #[test]
+    fn rejects_file_without_images() {
+        let path = temp_path("empty.mjpeg");
+        fs::write(&path, [0xff, 0xd8, 0x00]).unwrap();
+
+        let result = source(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(Error::Stream(_))));
+    }
+}
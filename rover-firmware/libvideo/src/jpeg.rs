@@ -0,0 +1,284 @@
+//! Baseline JPEG encoder of grayscale images, good enough for generated pictures, and splitting
+//! of MJPEG streams into images.
+
+use std::ops::Range;
+
+// quantization table for luminance from JPEG standard, in natural order, for quality of 50
+const LUMINANCE_QUANTIZATION: [u8; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, //
+    12, 12, 14, 19, 26, 58, 60, 55, //
+    14, 13, 16, 24, 40, 57, 69, 56, //
+    14, 17, 22, 29, 51, 87, 80, 62, //
+    18, 22, 37, 56, 68, 109, 103, 77, //
+    24, 35, 55, 64, 81, 104, 113, 92, //
+    49, 64, 78, 87, 103, 121, 120, 101, //
+    72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+// natural order index of each coefficient in zigzag order
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+// Huffman tables for luminance from JPEG standard: number of codes of each length and the values
+const DC_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+const AC_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+const AC_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+    0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+    0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+/// Huffman code and its length in bits, by symbol.
+struct HuffmanTable([(u16, u8); 256]);
+
+impl HuffmanTable {
+    fn new(bits: &[u8; 16], values: &[u8]) -> HuffmanTable {
+        let mut codes = [(0, 0); 256];
+        let mut code = 0u16;
+        let mut values = values.iter();
+
+        for (length, &count) in (1..=16).zip(bits) {
+            for _ in 0..count {
+                codes[*values.next().unwrap() as usize] = (code, length);
+                code += 1;
+            }
+            code <<= 1;
+        }
+
+        HuffmanTable(codes)
+    }
+}
+
+/// Writes entropy coded data, stuffing zero after each 0xFF byte.
+struct BitWriter<'a> {
+    output: &'a mut Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl<'a> BitWriter<'a> {
+    fn write(&mut self, bits: u16, length: u8) {
+        self.buffer = (self.buffer << length) | (bits as u32 & ((1 << length) - 1));
+        self.count += length;
+
+        while self.count >= 8 {
+            let byte = (self.buffer >> (self.count - 8)) as u8;
+            self.output.push(byte);
+            if byte == 0xff {
+                self.output.push(0);
+            }
+            self.count -= 8;
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.write(0x7f, 8 - self.count);
+        }
+    }
+}
+
+/// Number of bits in magnitude of the value and the bits themselves, as JPEG codes them.
+fn magnitude(value: i32) -> (u16, u8) {
+    let size = (32 - value.unsigned_abs().leading_zeros()) as u8;
+    let bits = if value < 0 { value - 1 } else { value };
+
+    (bits as u16, size)
+}
+
+/// Encodes 8-bit grayscale image given row by row. Quality is in [1; 100] range.
+pub fn encode_grayscale(pixels: &[u8], width: u16, height: u16, quality: u8) -> Vec<u8> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+
+    let quality = quality.clamp(1, 100) as u32;
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - quality * 2
+    };
+    let quantization: Vec<u8> = LUMINANCE_QUANTIZATION
+        .iter()
+        .map(|&q| ((q as u32 * scale + 50) / 100).clamp(1, 255) as u8)
+        .collect();
+
+    let mut output = vec![];
+
+    // start of image, JFIF header
+    output.extend_from_slice(&[0xff, 0xd8]);
+    output.extend_from_slice(&[0xff, 0xe0, 0, 16]);
+    output.extend_from_slice(b"JFIF\0");
+    output.extend_from_slice(&[1, 1, 0, 0, 1, 0, 1, 0, 0]);
+
+    // quantization table
+    output.extend_from_slice(&[0xff, 0xdb, 0, 67, 0]);
+    output.extend(ZIGZAG.iter().map(|&i| quantization[i]));
+
+    // frame of a single component
+    output.extend_from_slice(&[0xff, 0xc0, 0, 11, 8]);
+    output.extend_from_slice(&height.to_be_bytes());
+    output.extend_from_slice(&width.to_be_bytes());
+    output.extend_from_slice(&[1, 1, 0x11, 0]);
+
+    // Huffman tables
+    for (class, bits, values) in [
+        (0x00, &DC_BITS, &DC_VALUES[..]),
+        (0x10, &AC_BITS, &AC_VALUES[..]),
+    ] {
+        output.extend_from_slice(&[0xff, 0xc4]);
+        output.extend_from_slice(&(3 + 16 + values.len() as u16).to_be_bytes());
+        output.push(class);
+        output.extend_from_slice(bits);
+        output.extend_from_slice(values);
+    }
+
+    // scan
+    output.extend_from_slice(&[0xff, 0xda, 0, 8, 1, 1, 0, 0, 63, 0]);
+
+    let dc_table = HuffmanTable::new(&DC_BITS, &DC_VALUES);
+    let ac_table = HuffmanTable::new(&AC_BITS, &AC_VALUES);
+
+    let cosines: Vec<f32> = (0..64)
+        .map(|i| {
+            let (x, u) = ((i / 8) as f32, (i % 8) as f32);
+            let c = if u == 0.0 {
+                std::f32::consts::FRAC_1_SQRT_2
+            } else {
+                1.0
+            };
+            c * ((2.0 * x + 1.0) * u * std::f32::consts::PI / 16.0).cos() / 2.0
+        })
+        .collect();
+
+    let mut writer = BitWriter {
+        output: &mut output,
+        buffer: 0,
+        count: 0,
+    };
+    let mut previous_dc = 0;
+
+    for block_y in (0..height as usize).step_by(8) {
+        for block_x in (0..width as usize).step_by(8) {
+            // level shifted samples, edges repeated to fill partial blocks
+            let mut block = [0f32; 64];
+            for (i, sample) in block.iter_mut().enumerate() {
+                let x = (block_x + i % 8).min(width as usize - 1);
+                let y = (block_y + i / 8).min(height as usize - 1);
+                *sample = pixels[y * width as usize + x] as f32 - 128.0;
+            }
+
+            // separable DCT, rows then columns
+            let mut rows = [0f32; 64];
+            for y in 0..8 {
+                for u in 0..8 {
+                    rows[y * 8 + u] = (0..8).map(|x| block[y * 8 + x] * cosines[x * 8 + u]).sum();
+                }
+            }
+
+            let mut coefficients = [0i32; 64];
+            for (k, &i) in ZIGZAG.iter().enumerate() {
+                let (v, u) = (i / 8, i % 8);
+                let value: f32 = (0..8).map(|y| rows[y * 8 + u] * cosines[y * 8 + v]).sum();
+                coefficients[k] = (value / quantization[i] as f32).round() as i32;
+            }
+
+            let (bits, size) = magnitude(coefficients[0] - previous_dc);
+            let (code, length) = dc_table.0[size as usize];
+            writer.write(code, length);
+            writer.write(bits, size);
+            previous_dc = coefficients[0];
+
+            let mut zeros = 0;
+            for coefficient in coefficients[1..].iter().map(|&c| c.clamp(-1023, 1023)) {
+                if coefficient == 0 {
+                    zeros += 1;
+                    continue;
+                }
+
+                while zeros >= 16 {
+                    let (code, length) = ac_table.0[0xf0];
+                    writer.write(code, length);
+                    zeros -= 16;
+                }
+
+                let (bits, size) = magnitude(coefficient);
+                let (code, length) = ac_table.0[(zeros << 4) | size as usize];
+                writer.write(code, length);
+                writer.write(bits, size);
+                zeros = 0;
+            }
+
+            if zeros > 0 {
+                let (code, length) = ac_table.0[0x00];
+                writer.write(code, length);
+            }
+        }
+    }
+
+    writer.flush();
+
+    // end of image
+    output.extend_from_slice(&[0xff, 0xd9]);
+
+    output
+}
+
+/// Locates the first complete image in concatenated JPEG images.
+pub fn find_image(data: &[u8]) -> Option<Range<usize>> {
+    let start = data.windows(2).position(|w| w == [0xff, 0xd8])?;
+    let end = data[start..]
+        .windows(2)
+        .position(|w| w == [0xff, 0xd9])
+        .map(|end| start + end + 2)?;
+
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_complete_image() {
+        let pixels: Vec<u8> = (0..64 * 48).map(|i| (i % 256) as u8).collect();
+        let image = encode_grayscale(&pixels, 64, 48, 80);
+
+        assert_eq!(image[..2], [0xff, 0xd8]);
+        assert_eq!(image[image.len() - 2..], [0xff, 0xd9]);
+        assert_eq!(find_image(&image), Some(0..image.len()));
+    }
+
+    #[test]
+    fn finds_image_among_other_data() {
+        let data = [0x00, 0xff, 0xd8, 0x01, 0xff, 0xd9, 0x02];
+
+        assert_eq!(find_image(&data), Some(1..6));
+    }
+
+    #[test]
+    fn finds_first_of_concatenated_images() {
+        let data = [0xff, 0xd8, 0x01, 0xff, 0xd9, 0xff, 0xd8, 0x02, 0xff, 0xd9];
+
+        assert_eq!(find_image(&data), Some(0..5));
+        assert_eq!(find_image(&data[5..]), Some(0..5));
+    }
+
+    #[test]
+    fn ignores_incomplete_image() {
+        assert_eq!(find_image(&[]), None);
+        assert_eq!(find_image(&[0xff, 0xd8, 0x01, 0xff]), None);
+        assert_eq!(find_image(&[0x01, 0xff, 0xd9]), None);
+    }
+}
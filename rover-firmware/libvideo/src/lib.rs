@@ -0,0 +1,147 @@
+use std::io::Error as IOError;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use futures::stream::{unfold, Stream};
+use log::{info, warn};
+use thiserror::Error as LibError;
+use tokio::sync::watch;
+
+pub use command::{CommandSource, CommandSourceConfig};
+pub use file::{FileSource, FileSourceConfig};
+pub use pattern::{TestPattern, TestPatternConfig};
+pub use recording::MjpegWriter;
+
+mod command;
+mod file;
+pub mod jpeg;
+mod pattern;
+mod recording;
+
+// pause before asking failed source for frames again
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, LibError)]
+pub enum Error {
+    #[error("Input/output error: {0:?}")]
+    IO(#[from] IOError),
+
+    #[error("Invalid video stream: {0}")]
+    Stream(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// JPEG image captured by the camera.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Wall clock time of capture, in ms since UNIX epoch.
+    pub timestamp_ms: u64,
+    pub data: Bytes,
+}
+
+impl Frame {
+    pub fn now(data: impl Into<Bytes>) -> Frame {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+
+        Frame {
+            timestamp_ms,
+            data: data.into(),
+        }
+    }
+}
+
+/// Keeps generated frames to the given rate.
+struct Pace {
+    period: Duration,
+    next_frame_at: Option<Instant>,
+}
+
+impl Pace {
+    fn new(fps: f32) -> Pace {
+        Pace {
+            period: Duration::from_secs_f32(1.0 / fps.max(0.1)),
+            next_frame_at: None,
+        }
+    }
+
+    /// Blocks until it is time for the next frame.
+    fn wait(&mut self) {
+        let now = Instant::now();
+        let frame_at = self.next_frame_at.unwrap_or(now);
+
+        if frame_at > now {
+            thread::sleep(frame_at - now);
+        }
+        self.next_frame_at = Some(frame_at.max(now) + self.period);
+    }
+}
+
+/// Producer of frames, blocking until the next one is available.
+pub trait FrameSource: Send {
+    fn next_frame(&mut self) -> Result<Frame>;
+}
+
+/// Camera shared between viewers. Frames are pulled from the source by a dedicated thread for
+/// as long as the camera or any of its frame receivers is alive; slow viewers skip frames.
+#[derive(Clone)]
+pub struct Camera {
+    frames: watch::Receiver<Option<Frame>>,
+}
+
+impl Camera {
+    pub fn start<S: FrameSource + 'static>(mut source: S) -> Camera {
+        let (sender, frames) = watch::channel(None);
+
+        thread::spawn(move || {
+            info!("[Video] Camera started.");
+
+            while !sender.is_closed() {
+                match source.next_frame() {
+                    Ok(frame) => {
+                        sender.send_replace(Some(frame));
+                    }
+                    Err(e) => {
+                        warn!("[Video] Failed to get frame: {}", e);
+                        thread::sleep(RETRY_DELAY);
+                    }
+                }
+            }
+
+            info!("[Video] Camera stopped.");
+        });
+
+        Camera { frames }
+    }
+
+    /// Receiver notified of each new frame.
+    pub fn frames(&self) -> watch::Receiver<Option<Frame>> {
+        self.frames.clone()
+    }
+
+    /// Stream of frames starting with the most recent one, skipping frames while not polled.
+    pub fn stream(&self) -> impl Stream<Item = Frame> {
+        let mut frames = self.frames();
+        frames.mark_changed();
+
+        unfold(frames, |mut frames| async move {
+            loop {
+                frames.changed().await.ok()?;
+
+                let frame = frames.borrow_and_update().clone();
+                if let Some(frame) = frame {
+                    return Some((frame, frames));
+                }
+            }
+        })
+    }
+
+    /// Most recent frame, absent until the first one is captured.
+    pub fn latest(&self) -> Option<Frame> {
+        self.frames.borrow().clone()
+    }
+}
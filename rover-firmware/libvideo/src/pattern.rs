@@ -0,0 +1,111 @@
+use serde::Deserialize;
+
+use crate::jpeg::encode_grayscale;
+use crate::{Frame, FrameSource, Pace, Result};
+
+// 3x5 pixel digits, top row in the highest bits
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_010_010_010,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct TestPatternConfig {
+    pub width: u16,
+    pub height: u16,
+    pub fps: f32,
+
+    /// JPEG quality, in [1; 100] range.
+    pub quality: u8,
+}
+
+impl Default for TestPatternConfig {
+    fn default() -> Self {
+        TestPatternConfig {
+            width: 320,
+            height: 240,
+            fps: 10.0,
+            quality: 75,
+        }
+    }
+}
+
+/// Generated frames for when there is no camera: gradient with a bar sweeping across it and
+/// frame number drawn over it, so that frozen or lagging video is easy to spot.
+pub struct TestPattern {
+    config: TestPatternConfig,
+    number: u64,
+    pace: Pace,
+}
+
+impl TestPattern {
+    pub fn new(config: TestPatternConfig) -> TestPattern {
+        TestPattern {
+            pace: Pace::new(config.fps),
+            config,
+            number: 0,
+        }
+    }
+
+    fn draw(&self) -> Vec<u8> {
+        let (width, height) = (self.config.width as usize, self.config.height as usize);
+        let bar_x = (self.number as usize * 4) % width.max(1);
+
+        let mut pixels: Vec<u8> = (0..width * height)
+            .map(|i| {
+                let x = i % width;
+                if x.abs_diff(bar_x) < 4 {
+                    255
+                } else {
+                    (x * 200 / width.max(1)) as u8
+                }
+            })
+            .collect();
+
+        // frame number, digits of 4x4 pixel dots in the top left corner
+        let scale = 4;
+        for (position, digit) in self.number.to_string().bytes().enumerate() {
+            let glyph = DIGITS[(digit - b'0') as usize];
+
+            for row in 0..5 {
+                for column in 0..3 {
+                    let lit = glyph & (1 << (14 - row * 3 - column)) != 0;
+                    let (x0, y0) = (scale * (1 + position * 4 + column), scale * (1 + row));
+
+                    for y in y0..(y0 + scale).min(height) {
+                        for x in x0..(x0 + scale).min(width) {
+                            pixels[y * width + x] = if lit { 255 } else { 0 };
+                        }
+                    }
+                }
+            }
+        }
+
+        pixels
+    }
+}
+
+impl FrameSource for TestPattern {
+    fn next_frame(&mut self) -> Result<Frame> {
+        self.pace.wait();
+
+        let jpeg = encode_grayscale(
+            &self.draw(),
+            self.config.width,
+            self.config.height,
+            self.config.quality,
+        );
+        self.number += 1;
+
+        Ok(Frame::now(jpeg))
+    }
+}
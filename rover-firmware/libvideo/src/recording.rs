@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::{Frame, Result};
+
+/// Writes frames one after another into MJPEG file, which [FileSource](crate::FileSource)
+/// and most video players can play back.
+pub struct MjpegWriter {
+    file: BufWriter<File>,
+    frames: u32,
+    size: u64,
+    first_timestamp_ms: Option<u64>,
+    last_timestamp_ms: u64,
+}
+
+impl MjpegWriter {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<MjpegWriter> {
+        Ok(MjpegWriter {
+            file: BufWriter::new(File::create(path)?),
+            frames: 0,
+            size: 0,
+            first_timestamp_ms: None,
+            last_timestamp_ms: 0,
+        })
+    }
+
+    pub fn write(&mut self, frame: &Frame) -> Result<()> {
+        self.file.write_all(&frame.data)?;
+
+        self.frames += 1;
+        self.size += frame.data.len() as u64;
+        self.first_timestamp_ms.get_or_insert(frame.timestamp_ms);
+        self.last_timestamp_ms = frame.timestamp_ms;
+
+        Ok(())
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Bytes written so far.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Time between the first and the last frame, in ms.
+    pub fn duration_ms(&self) -> u64 {
+        self.first_timestamp_ms
+            .map(|first| self.last_timestamp_ms.saturating_sub(first))
+            .unwrap_or_default()
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
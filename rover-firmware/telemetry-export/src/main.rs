@@ -0,0 +1,245 @@
+use std::fs::File;
+use std::io::{stdout, Write};
+
+use clap::{arg, command, value_parser};
+use serde::Serialize;
+use serde_json::Value;
+
+use libapi_net::telemetry::{log_files, read_log, to_session, Event, Record};
+use libdriver_replay::save_session;
+
+/// Flat view of a record, one CSV row.
+#[derive(Debug, Serialize, Default)]
+struct Row {
+    time_ms: u64,
+    event: &'static str,
+    connection: Option<u64>,
+    direction: Option<String>,
+    id: Option<u64>,
+    device: Option<String>,
+    /// Message type or driver method.
+    name: String,
+    args: String,
+    duration_us: Option<u64>,
+    ok: bool,
+    /// Message content as JSON, result of the call or its error.
+    value: String,
+}
+
+/// Splits externally tagged enum value, either a bare name or a single-entry object, into its name
+/// and content as JSON.
+fn split_tagged<T: Serialize>(value: &T) -> serde_json::Result<(String, String)> {
+    Ok(match serde_json::to_value(value)? {
+        Value::String(name) => (name, String::new()),
+        Value::Object(map) => match map.into_iter().next() {
+            Some((name, value)) => (name, value.to_string()),
+            None => (String::new(), String::new()),
+        },
+        value => (String::new(), value.to_string()),
+    })
+}
+
+fn to_row(record: &Record) -> Result<Row, Box<dyn std::error::Error>> {
+    Ok(match record.event {
+        Event::Message {
+            connection,
+            direction,
+            id,
+            ref message,
+        } => {
+            let (name, value) = split_tagged(message)?;
+
+            Row {
+                time_ms: record.time_ms,
+                event: "message",
+                connection: Some(connection),
+                direction: Some(format!("{:?}", direction)),
+                id,
+                name,
+                ok: true,
+                value,
+                ..Default::default()
+            }
+        }
+        Event::Call {
+            call,
+            duration_us,
+            ref result,
+        } => {
+            let (name, args) = split_tagged(&call)?;
+
+            Row {
+                time_ms: record.time_ms,
+                event: "call",
+                device: Some(format!("{:?}", call.device())),
+                name,
+                args,
+                duration_us: Some(duration_us),
+                ok: result.is_ok(),
+                value: match result {
+                    Ok(reply) => split_tagged(reply)?.1,
+                    Err(e) => e.clone(),
+                },
+                ..Default::default()
+            }
+        }
+    })
+}
+
+/// Records of the log along with those of files rotated from it, oldest first.
+fn read_records(log_path: &str) -> Result<Vec<Record>, Box<dyn std::error::Error>> {
+    let files = log_files(log_path);
+
+    if files.is_empty() {
+        return Err(format!("No telemetry log found at {}.", log_path).into());
+    }
+
+    let mut records = vec![];
+    for file in files {
+        records.extend(read_log(file)?);
+    }
+
+    Ok(records)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = command!()
+        .arg(
+            arg!(format: -f --format <FORMAT> "Output format")
+                .long_help(
+                    "Output format: csv or json for all records, session for driver calls to \
+                    replay, written as JSON if output file has .json extension and CBOR otherwise",
+                )
+                .value_parser(["csv", "json", "session"])
+                .default_value("csv"),
+        )
+        .arg(
+            arg!(output: -o --output <FILE> "Write to given file instead of standard output")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            arg!(log: <LOG> "Telemetry log file, files rotated from it are exported first")
+                .value_parser(value_parser!(String)),
+        )
+        .get_matches();
+
+    let records = read_records(opts.get_one::<String>("log").unwrap())?;
+
+    if opts.get_one::<String>("format").map(String::as_str) == Some("session") {
+        let path = opts
+            .get_one::<String>("output")
+            .ok_or("Session needs an output file.")?;
+
+        return Ok(save_session(&to_session(&records), path)?);
+    }
+
+    let output: Box<dyn Write> = match opts.get_one::<String>("output") {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(stdout().lock()),
+    };
+
+    match opts.get_one::<String>("format").map(String::as_str) {
+        Some("json") => {
+            let mut output = output;
+
+            serde_json::to_writer_pretty(&mut output, &records)?;
+            writeln!(output)?;
+        }
+        _ => {
+            let mut writer = csv::Writer::from_writer(output);
+
+            for record in &records {
+                writer.serialize(to_row(record)?)?;
+            }
+
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::thread;
+    use std::time::Duration;
+
+    use libapi_net::contract::data::{ProtocolMessage, StatusResponseData};
+    use libapi_net::telemetry::{Direction, Recorder, TelemetryConfig};
+    use libdriver::session::{DriverCall, DriverReply};
+
+    use super::*;
+
+    #[test]
+    fn recorded_log_is_exported_as_rows() {
+        let dir = std::env::temp_dir().join(format!("telemetry-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("api-net.cbor").display().to_string();
+
+        let recorder = Recorder::start(TelemetryConfig {
+            path: path.clone(),
+            ..TelemetryConfig::default()
+        })
+        .unwrap();
+
+        let status = ProtocolMessage::StatusResponse(StatusResponseData::Success);
+        recorder.record_message(3, Direction::Sent, Some(12), &status);
+        recorder.record(Event::Call {
+            call: DriverCall::Drive(100, -100),
+            duration_us: 480,
+            result: Err("Bus is down.".to_owned()),
+        });
+        recorder.record(Event::Call {
+            call: DriverCall::GetLookDirection,
+            duration_us: 15,
+            result: Ok(DriverReply::LookDirection((30, -10))),
+        });
+
+        // recorder writes in the background
+        let mut records = vec![];
+        for _ in 0..200 {
+            records = read_records(&path).unwrap_or_default();
+            if records.len() == 3 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(records.len(), 3);
+
+        let message = to_row(&records[0]).unwrap();
+        assert_eq!(message.event, "message");
+        assert_eq!(message.connection, Some(3));
+        assert_eq!(message.direction.as_deref(), Some("Sent"));
+        assert_eq!(message.id, Some(12));
+        assert_eq!(message.name, "StatusResponse");
+        assert_eq!(message.value, r#""Success""#);
+        assert!(message.ok);
+
+        let failed = to_row(&records[1]).unwrap();
+        assert_eq!(failed.event, "call");
+        assert_eq!(failed.device.as_deref(), Some("Mover"));
+        assert_eq!(failed.name, "Drive");
+        assert_eq!(failed.args, "[100,-100]");
+        assert_eq!(failed.duration_us, Some(480));
+        assert!(!failed.ok);
+        assert_eq!(failed.value, "Bus is down.");
+
+        let looked = to_row(&records[2]).unwrap();
+        assert_eq!(looked.device.as_deref(), Some("Looker"));
+        assert_eq!(looked.name, "GetLookDirection");
+        assert_eq!(looked.args, "");
+        assert!(looked.ok);
+        assert_eq!(looked.value, "[30,-10]");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_log_is_reported() {
+        let path = std::env::temp_dir().join("no-such-telemetry.cbor");
+        let e = read_records(&path.display().to_string()).unwrap_err();
+
+        assert!(e.to_string().starts_with("No telemetry log found at"));
+    }
+}
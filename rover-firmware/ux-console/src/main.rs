@@ -1,10 +1,19 @@
-use clap::{arg, command, value_parser, ArgAction, ArgGroup};
+use clap::{arg, command, value_parser, ArgAction, ArgGroup, ArgMatches, Command};
+use config::Config;
 
 use libapi_net::client::Client;
+use libapi_net::contract::data::NotificationData;
+use libbehavior::{LocalHost, Mission};
 use libdriver::util::a_sync::AsyncRover;
-use libdriver_robohat::RobohatRover;
+use libdriver_replay::{ReplayConfig, ReplayRover};
+use libdriver_robohat::{Calibration, RobohatConfig, RobohatRover};
+use libdriver_sim::{SimConfig, SimRover};
+use libutil::app::get_optional;
+use libux_console::calibrator::Calibrator;
 use libux_console::controller::RideController;
 
+const CLIENT_NAME: &str = concat!("ux-console/", env!("CARGO_PKG_VERSION"));
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opts = command!()
@@ -16,23 +25,158 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             arg!(address: -r --remote <ADDR> "Enable remote mode (if connecting through net API)")
                 .value_parser(value_parser!(String)),
         )
+        .arg(
+            arg!(map: -s --sim <MAP> "Enable simulation mode (drive simulated rover in given world map)")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            arg!(session: --replay <SESSION> "Enable replay mode (read sensors from recorded driver session)")
+                .value_parser(value_parser!(String)),
+        )
         .group(
             ArgGroup::new("mode")
-                .args(["local", "address"])
+                .args(["local", "address", "map", "session"])
                 .required(true),
         )
+        .subcommand(
+            Command::new("calibrate")
+                .about("Mark pan/tilt servo endpoints and write calibration file (run on the rover)")
+                .arg(
+                    arg!(config: -c --config <FILE> "Read robohat driver settings from api-net configuration")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    arg!(output: -o --output <FILE> "Write calibration to given file")
+                        .value_parser(value_parser!(String))
+                        .default_value("Calibration.toml"),
+                ),
+        )
+        .subcommand(
+            Command::new("mission")
+                .about("Run mission file on the rover through net API, reporting its progress")
+                .arg(
+                    arg!(address: -r --remote <ADDR> "Address of net API")
+                        .value_parser(value_parser!(String))
+                        .required(true),
+                )
+                .arg(
+                    arg!(file: <FILE> "Mission file, TOML or JSON")
+                        .value_parser(value_parser!(String)),
+                ),
+        )
+        .subcommand_negates_reqs(true)
         .get_matches();
 
-    if opts.get_flag("local") {
+    if let Some(calibrate_opts) = opts.subcommand_matches("calibrate") {
+        calibrate(calibrate_opts)?
+    } else if let Some(mission_opts) = opts.subcommand_matches("mission") {
+        run_mission(mission_opts).await?
+    } else if opts.get_flag("local") {
         let async_rover: AsyncRover<RobohatRover> = RobohatRover::new()?.into();
-        RideController::new(async_rover)?.run().await?
+        RideController::new(LocalHost::new(async_rover))?.run().await?
+    } else if let Some(map_path) = opts.get_one::<String>("map") {
+        let async_rover: AsyncRover<SimRover> =
+            SimRover::from_map(map_path, SimConfig::default())?.into();
+        RideController::new(LocalHost::new(async_rover))?.run().await?
+    } else if let Some(session_path) = opts.get_one::<String>("session") {
+        // manual driving hardly repeats recorded commands, only report them
+        let replay_config = ReplayConfig {
+            strict: false,
+            ..Default::default()
+        };
+        let async_rover: AsyncRover<ReplayRover> =
+            ReplayRover::from_file(session_path, replay_config)?.into();
+        RideController::new(LocalHost::new(async_rover))?.run().await?
     } else {
         let rover_address = opts.get_one::<String>("address").unwrap();
 
-        RideController::new(Client::new(rover_address).await?)?
+        let client = Client::with_name(rover_address, CLIENT_NAME).await?;
+
+        RideController::new(client)?
             .run()
             .await?
     }
 
     Ok(())
 }
+
+fn calibrate(opts: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mut robohat_config = match opts.get_one::<String>("config") {
+        Some(config_path) => {
+            let settings = Config::builder()
+                .add_source(config::File::with_name(config_path))
+                .build()?;
+
+            get_optional::<RobohatConfig>(&settings, "driver.robohat")?.unwrap_or_default()
+        }
+        None => RobohatConfig::default(),
+    };
+    robohat_config.load_calibration()?;
+
+    let output_path = opts.get_one::<String>("output").unwrap();
+
+    let servo = robohat_config.servo.controller()?;
+    let initial = Calibration {
+        pan: robohat_config.pan,
+        tilt: robohat_config.tilt,
+    };
+
+    let calibration = Calibrator::new(servo, robohat_config.servo.channels(), initial)?.run()?;
+
+    match calibration {
+        Some(calibration) => {
+            calibration.validate()?;
+            calibration.save(output_path)?;
+
+            println!("Calibration written to {}.", output_path);
+        }
+        None => println!("Calibration aborted."),
+    }
+
+    Ok(())
+}
+
+async fn run_mission(opts: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mission = Mission::load(opts.get_one::<String>("file").unwrap())?;
+    mission.validate()?;
+
+    let rover_address = opts.get_one::<String>("address").unwrap();
+    let client = Client::with_name(rover_address, CLIENT_NAME).await?;
+
+    // mission is aborted once the client disconnects, so stay until it is over
+    let mut notifications = client.notifications();
+
+    client.upload_mission(mission).await?;
+    client.start_mission().await?;
+
+    loop {
+        if let NotificationData::MissionProgress(progress) = notifications.recv().await? {
+            println!(
+                "[{}] Step {}/{}: {:?}",
+                progress.name,
+                progress.step.min(progress.steps.saturating_sub(1)) + 1,
+                progress.steps,
+                progress.status
+            );
+
+            if progress.status.is_over() {
+                for scan in progress.scans {
+                    println!(
+                        "Scan at step {} looking at {:?}: {}",
+                        scan.step + 1,
+                        scan.look_direction,
+                        if scan.distance.valid {
+                            format!("{:.0} mm", scan.distance.distance.0)
+                        } else {
+                            "out of range".to_owned()
+                        }
+                    );
+                }
+
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}